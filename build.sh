@@ -1,6 +1,10 @@
 #!/usr/bin/env bash
 set -e
-if [ "$1" == "test" ];then
+if [ "$1" == "upgrade" ];then
+  (cd runtime/wasm && sh build.sh)
+  cp -f runtime/wasm/target/wasm32-unknown-unknown/release/yee_runtime_wasm.compact.wasm prebuilt/yee_runtime/upgrade/mainnet_v6.wasm
+  exit 0
+elif [ "$1" == "test" ];then
   cp -f prebuilt/yee_runtime/upgrade/mainnet_v5.wasm runtime/wasm/target/wasm32-unknown-unknown/release/yee_runtime_wasm.compact.wasm
 else
   cp -f prebuilt/yee_runtime/mainnet.wasm runtime/wasm/target/wasm32-unknown-unknown/release/yee_runtime_wasm.compact.wasm
@@ -20,6 +20,9 @@ use substrate_service;
 use yee_primitives::{Address, AddressCodec};
 use yee_dev;
 use serde::export::PhantomData;
+use serde_json::{json, Map, Value};
+use consensus::fork::MAINNET_FORK_CONF;
+use crfg::skip::MAINNET_SKIP_CONF;
 use crate::service::WASM_CODE;
 
 // Note this is the URL for the telemetry server
@@ -157,7 +160,7 @@ impl Alternative {
 				None,
 				None,
 				None,
-				None,
+				Some(mainnet_properties()),
 			),
 		})
 	}
@@ -174,6 +177,27 @@ impl Alternative {
 	}
 }
 
+/// Fork points and CRFG skip points of the mainnet
+fn mainnet_properties() -> Map<String, Value> {
+	let forks = MAINNET_FORK_CONF.iter().map(|(shard_num, number, hash, fork_id)| json!({
+		"shardNum": shard_num,
+		"number": number,
+		"hash": hash,
+		"forkId": fork_id,
+	})).collect();
+	let crfg_skips = MAINNET_SKIP_CONF.iter().map(|(shard_num, number, hash, skip_number)| json!({
+		"shardNum": shard_num,
+		"number": number,
+		"hash": hash,
+		"skipNumber": skip_number,
+	})).collect();
+
+	let mut properties = Map::new();
+	properties.insert("forks".to_string(), Value::Array(forks));
+	properties.insert("crfgSkips".to_string(), Value::Array(crfg_skips));
+	properties
+}
+
 fn mainnet_genesis(chain_id: Vec<u8>, endowed_accounts: Vec<(AccountId, u128)>, sudo_accounts: Vec<AccountId>) -> GenesisConfig {
 	let code = WASM_CODE.to_vec();
 	let block_reward_latency = MAX_AUTHORITIES_SIZE + BLOCK_FINAL_LATENCY + 1;
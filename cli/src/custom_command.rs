@@ -23,6 +23,8 @@ use yee_bootnodes_router;
 use substrate_cli::VersionInfo;
 use substrate_service::{ChainSpec, FactoryGenesis, ServiceFactory};
 use crate::chain_revert::{RevertCmd, revert_chain};
+use crate::tx::{TxCmd, run_tx};
+use crate::key::{KeyCmd, run_key};
 use runtime_primitives::traits::{Block as BlockT, Digest, DigestItemFor, Header as HeaderT, NumberFor};
 use crfg::{aux_schema, authorities, CrfgChangeDigestItem, ScheduledChange};
 
@@ -32,6 +34,8 @@ pub enum CustomCommand {
     SwitchCommandCmd(yee_switch::params::SwitchCommandCmd),
     BootnodesRouterCommandCmd(yee_bootnodes_router::params::BootnodesRouterCommandCmd),
     Revert(RevertCmd),
+    Tx(TxCmd),
+    Key(KeyCmd),
     None,
 }
 
@@ -48,6 +52,14 @@ impl StructOpt for CustomCommand {
                 RevertCmd::augment_clap(SubCommand::with_name("revert"))
                     .about("Yee revert block")
             )
+            .subcommand(
+                TxCmd::augment_clap(SubCommand::with_name("tx"))
+                    .about("Yee build, sign and decode extrinsics offline")
+            )
+            .subcommand(
+                KeyCmd::augment_clap(SubCommand::with_name("key"))
+                    .about("Yee account keys, addresses and node keys")
+            )
     }
 
     fn from_clap(matches: &::structopt::clap::ArgMatches) -> Self {
@@ -58,6 +70,10 @@ impl StructOpt for CustomCommand {
                 CustomCommand::BootnodesRouterCommandCmd(yee_bootnodes_router::params::BootnodesRouterCommandCmd::from_clap(matches)),
             ("revert", Some(matches)) =>
                 CustomCommand::Revert(RevertCmd::from_clap(matches)),
+            ("tx", Some(matches)) =>
+                CustomCommand::Tx(TxCmd::from_clap(matches)),
+            ("key", Some(matches)) =>
+                CustomCommand::Key(KeyCmd::from_clap(matches)),
             (_, Some(_)) => CustomCommand::None,
             (_, None) => CustomCommand::None,
         }
@@ -70,6 +86,8 @@ impl GetLogFilter for CustomCommand {
             CustomCommand::SwitchCommandCmd(cmd) => cmd.get_log_filter(),
             CustomCommand::BootnodesRouterCommandCmd(cmd) => cmd.get_log_filter(),
             CustomCommand::Revert(cmd) => cmd.get_log_filter(),
+            CustomCommand::Tx(cmd) => cmd.get_log_filter(),
+            CustomCommand::Key(cmd) => cmd.get_log_filter(),
             CustomCommand::None => None
         }
     }
@@ -87,6 +105,8 @@ pub fn run_custom_command<F, E, S>(params : Option<(CustomCommand, S, E, Version
             CustomCommand::SwitchCommandCmd(cmd) => Ok(yee_switch::run(cmd, version).map_err(|e| format!("{:?}", e))?),
             CustomCommand::BootnodesRouterCommandCmd(cmd) => Ok(yee_bootnodes_router::run(cmd, version).map_err(|e| format!("{:?}", e))?),
             CustomCommand::Revert(cmd) => Ok(revert_chain::<F, S>(cmd, version, spec_factory).map_err(|e|format!("{:?}", e))?),
+            CustomCommand::Tx(cmd) => Ok(run_tx(cmd).map_err(|e| format!("{:?}", e))?),
+            CustomCommand::Key(cmd) => Ok(run_key(cmd).map_err(|e| format!("{:?}", e))?),
             CustomCommand::None => Ok(())
         },
         None => Ok(())
@@ -45,7 +45,9 @@ use inherents::{
     InherentDataProviders, RuntimeString,
 };
 use std::sync::Arc;
-use yee_context::Context;
+use yee_context::{Context, ForkPoint, SkipPoint, AlgorithmPoint, LwmaPoint};
+use primitives::H256;
+use serde_json::{Map, Value};
 use yee_pow_primitives::YeePOWApi;
 use std::path::PathBuf;
 use std::collections::HashMap;
@@ -154,7 +156,7 @@ where
 
     let InitialInfo{genesis_hash: _, context, shard_num, shard_count, scale_out} = get_initial_info::<F>(&config, custom_args.shard_num)?;
 
-    config.custom.hrp = get_hrp( config.chain_spec.id());
+    config.custom.hrp = get_hrp::<F>(&config)?;
 
     config.custom.shard_num = shard_num;
     config.custom.shard_count = shard_count;
@@ -162,11 +164,7 @@ where
 
     if config.roles == Roles::AUTHORITY{
         let coinbase = custom_args.coinbase.clone().ok_or(error::ErrorKind::Input("Coinbase not found".to_string()))?;
-        let (coinbase, hrp) = parse_coinbase(coinbase).map_err(|e| format!("Invalid coinbase: {:?}", e))?;
-
-        if config.custom.hrp != hrp{
-            return Err(error::ErrorKind::Input("Invalid coinbase hrp".to_string()).into());
-        }
+        let coinbase = parse_coinbase(coinbase, &config.custom.hrp)?;
 
         let coinbase_shard_num = shard_num_for(&coinbase, shard_count).expect("qed");
         info!("Coinbase shard num: {}", coinbase_shard_num);
@@ -273,19 +271,32 @@ fn get_native_bootnodes(bootnodes_router_conf: &BootnodesRouterConf, shard_num:
     }
 }
 
-fn parse_coinbase(input: String) -> error::Result<(AccountId, Hrp)> {
+fn parse_coinbase(input: String, hrp: &Hrp) -> error::Result<AccountId> {
 
     let address = Address(input);
-    let (coinbase, hrp) = AccountId::from_address(&address).map_err(|e| format!("{:?}", e))?;
+    let coinbase = AccountId::from_address_with_hrp(&address, hrp)
+        .map_err(|e| error::ErrorKind::Input(format!("Invalid coinbase: {}", e)))?;
 
-    Ok((coinbase, hrp))
+    Ok(coinbase)
 }
 
-fn get_hrp(chain_spec_id: &str) -> Hrp {
-    match chain_spec_id {
-        "mainnet" => Hrp::MAINNET,
-        _ => Hrp::TESTNET,
+/// Address prefix named by the `hrp` property of the chain spec,
+/// `yee` for the mainnet and `tyee` for the other chains if absent
+fn get_hrp<F>(config: &FactoryFullConfiguration<F>) -> error::Result<Hrp> where
+    F: ServiceFactory,
+{
+    let hrp = match config.chain_spec.properties().get("hrp").and_then(|x| x.as_str()) {
+        Some(hrp) => hrp.to_string(),
+        None => match config.chain_spec.id() {
+            "mainnet" => return Ok(Hrp::MAINNET),
+            _ => return Ok(Hrp::TESTNET),
+        },
+    };
+    // bech32 prefix, lower case
+    if hrp.is_empty() || hrp.len() > 83 || !hrp.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()) {
+        return Err(error::ErrorKind::Input(format!("Invalid hrp: {}", hrp)).into());
     }
+    Ok(hrp.into())
 }
 
 #[derive(Debug)]
@@ -360,10 +371,149 @@ fn get_context_from_client<F, C>(config: &FactoryFullConfiguration<F>, client: A
         genesis_target_block_time: api.target_block_time(&block_id)?,
         genesis_shard_count: api.get_genesis_shard_count(&block_id)?,
         genesis_scale_out_observe_blocks: api.get_scale_out_observe_blocks(&block_id)?,
+        genesis_pow_algorithm: get_pow_algorithm(config.chain_spec.properties())?,
+        fork_points: get_fork_points(config.chain_spec.properties())?,
+        skip_points: get_skip_points(config.chain_spec.properties())?,
+        algorithm_points: get_algorithm_points(config.chain_spec.properties())?,
+        lwma_points: get_lwma_points(config.chain_spec.properties())?,
     };
     Ok(context)
 }
 
+/// Pow algorithm named by the `powAlgorithm` property of the chain spec
+fn get_pow_algorithm(properties: &Map<String, Value>) -> error::Result<String> {
+    let algorithm = properties.get("powAlgorithm")
+        .and_then(|x| x.as_str())
+        .unwrap_or(consensus::DEFAULT_POW_ALGORITHM)
+        .to_string();
+    consensus::get_algorithm(&algorithm).map_err(error::ErrorKind::Input)?;
+    Ok(algorithm)
+}
+
+/// Fork points of the `forks` property of the chain spec
+/// `[{"shardNum": 0, "number": 143459, "hash": "0x..", "forkId": 1}]`
+fn get_fork_points(properties: &Map<String, Value>) -> error::Result<Vec<ForkPoint>> {
+    let mut fork_points = Vec::new();
+    for item in get_spec_array(properties, "forks")? {
+        let fork_id = get_spec_u64(&item, "forkId")?;
+        if fork_id > u32::max_value() as u64 {
+            return Err(error::ErrorKind::Input(format!("Invalid forkId in {}", item)).into());
+        }
+        fork_points.push(ForkPoint {
+            shard_num: get_spec_u16(&item, "shardNum")?,
+            number: get_spec_u64(&item, "number")?,
+            hash: get_spec_hash(&item, "hash")?,
+            fork_id: fork_id as u32,
+        });
+    }
+
+    // the fork ids of a shard increase with the block numbers
+    let mut sorted = fork_points.iter().collect::<Vec<_>>();
+    sorted.sort_by_key(|point| (point.shard_num, point.number));
+    for pair in sorted.windows(2) {
+        if pair[0].shard_num == pair[1].shard_num && (pair[0].number == pair[1].number || pair[0].fork_id >= pair[1].fork_id) {
+            return Err(error::ErrorKind::Input(format!("Invalid fork points: {:?}, {:?}", pair[0], pair[1])).into());
+        }
+    }
+
+    info!("Fork points: {:?}", fork_points);
+    Ok(fork_points)
+}
+
+/// CRFG skip points of the `crfgSkips` property of the chain spec
+/// `[{"shardNum": 0, "number": 155699, "hash": "0x..", "skipNumber": 155673}]`
+fn get_skip_points(properties: &Map<String, Value>) -> error::Result<Vec<SkipPoint>> {
+    let mut skip_points: Vec<SkipPoint> = Vec::new();
+    for item in get_spec_array(properties, "crfgSkips")? {
+        let point = SkipPoint {
+            shard_num: get_spec_u16(&item, "shardNum")?,
+            number: get_spec_u64(&item, "number")?,
+            hash: get_spec_hash(&item, "hash")?,
+            skip_number: get_spec_u64(&item, "skipNumber")?,
+        };
+        if point.skip_number >= point.number
+            || skip_points.iter().any(|x| x.shard_num == point.shard_num && x.number == point.number) {
+            return Err(error::ErrorKind::Input(format!("Invalid crfg skip point: {:?}", point)).into());
+        }
+        skip_points.push(point);
+    }
+
+    info!("CRFG skip points: {:?}", skip_points);
+    Ok(skip_points)
+}
+
+/// Pow algorithm switches of the `powAlgorithms` property of the chain spec
+/// `[{"shardNum": 0, "number": 200000, "algorithm": "blake2-scratchpad"}]`
+fn get_algorithm_points(properties: &Map<String, Value>) -> error::Result<Vec<AlgorithmPoint>> {
+    let mut algorithm_points: Vec<AlgorithmPoint> = Vec::new();
+    for item in get_spec_array(properties, "powAlgorithms")? {
+        let algorithm = item.get("algorithm").and_then(Value::as_str)
+            .ok_or_else(|| error::Error::from(error::ErrorKind::Input(format!("Invalid algorithm in {}", item))))?;
+        consensus::get_algorithm(algorithm).map_err(error::ErrorKind::Input)?;
+        let point = AlgorithmPoint {
+            shard_num: get_spec_u16(&item, "shardNum")?,
+            number: get_spec_u64(&item, "number")?,
+            algorithm: algorithm.to_string(),
+        };
+        if algorithm_points.iter().any(|x| x.shard_num == point.shard_num && x.number == point.number) {
+            return Err(error::ErrorKind::Input(format!("Invalid pow algorithm point: {:?}", point)).into());
+        }
+        algorithm_points.push(point);
+    }
+
+    info!("Pow algorithm points: {:?}", algorithm_points);
+    Ok(algorithm_points)
+}
+
+/// Per block pow target adjustment points of the `lwma` property of the chain spec
+/// `[{"shardNum": 0, "number": 200000}]`
+fn get_lwma_points(properties: &Map<String, Value>) -> error::Result<Vec<LwmaPoint>> {
+    let mut lwma_points: Vec<LwmaPoint> = Vec::new();
+    for item in get_spec_array(properties, "lwma")? {
+        let point = LwmaPoint {
+            shard_num: get_spec_u16(&item, "shardNum")?,
+            number: get_spec_u64(&item, "number")?,
+        };
+        // adjusted per block from the first point of the shard on
+        if lwma_points.iter().any(|x| x.shard_num == point.shard_num) {
+            return Err(error::ErrorKind::Input(format!("Invalid lwma point: {:?}", point)).into());
+        }
+        lwma_points.push(point);
+    }
+
+    info!("LWMA points: {:?}", lwma_points);
+    Ok(lwma_points)
+}
+
+fn get_spec_array(properties: &Map<String, Value>, key: &str) -> error::Result<Vec<Value>> {
+    match properties.get(key) {
+        Some(Value::Array(items)) => Ok(items.clone()),
+        Some(_) => Err(error::ErrorKind::Input(format!("Invalid chain spec property {}: array expected", key)).into()),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn get_spec_u64(item: &Value, field: &str) -> error::Result<u64> {
+    item.get(field).and_then(Value::as_u64)
+        .ok_or_else(|| error::ErrorKind::Input(format!("Invalid {} in {}", field, item)).into())
+}
+
+fn get_spec_u16(item: &Value, field: &str) -> error::Result<u16> {
+    let value = get_spec_u64(item, field)?;
+    if value > u16::max_value() as u64 {
+        return Err(error::ErrorKind::Input(format!("Invalid {} in {}", field, item)).into());
+    }
+    Ok(value as u16)
+}
+
+fn get_spec_hash(item: &Value, field: &str) -> error::Result<H256> {
+    let hash = item.get(field).and_then(Value::as_str)
+        .and_then(|x| hex::decode(x.trim_start_matches("0x")).ok())
+        .filter(|x| x.len() == 32)
+        .ok_or_else(|| error::Error::from(error::ErrorKind::Input(format!("Invalid {} in {}", field, item))))?;
+    Ok(H256::from_slice(&hash))
+}
+
 fn get_shard_info_from_client<F, C>(_config: &FactoryFullConfiguration<F>, arg_shard_num: u16, client: Arc<C>, context: &Context<FactoryBlock<F>>) -> error::Result<(u16, u16, Option<ScaleOut>)>
 where
     F: ServiceFactory<Configuration=NodeConfig<F>>,
@@ -451,3 +601,80 @@ fn register_inherent_data_provider(
 fn inherent_to_common_error(err: RuntimeString) -> consensus_common::Error {
     consensus_common::ErrorKind::InherentData(err.into()).into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn properties(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    const HASH: &str = "0x473de2d82aebbefe0e96888d1fde3a7c206f6842aaaafc546fecac4a62f760e1";
+
+    #[test]
+    fn fork_points() {
+        assert_eq!(get_fork_points(&Map::new()).unwrap(), vec![]);
+
+        let points = get_fork_points(&properties(json!({"forks": [
+            {"shardNum": 0, "number": 100, "hash": HASH, "forkId": 1},
+            {"shardNum": 0, "number": 200, "hash": HASH, "forkId": 2},
+            {"shardNum": 1, "number": 100, "hash": HASH, "forkId": 1},
+        ]}))).unwrap();
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[1], ForkPoint { shard_num: 0, number: 200, hash: get_spec_hash(&json!({"hash": HASH}), "hash").unwrap(), fork_id: 2 });
+
+        let invalid = |forks: Value| get_fork_points(&properties(json!({"forks": forks}))).is_err();
+        assert!(invalid(json!({"shardNum": 0})));
+        assert!(invalid(json!([{"shardNum": 0, "number": 100, "hash": "0x1234", "forkId": 1}])));
+        assert!(invalid(json!([{"shardNum": 65536, "number": 100, "hash": HASH, "forkId": 1}])));
+        assert!(invalid(json!([{"shardNum": 0, "number": 100, "hash": HASH, "forkId": 4294967296u64}])));
+        assert!(invalid(json!([{"shardNum": 0, "number": 100, "hash": HASH}])));
+        // same number
+        assert!(invalid(json!([
+            {"shardNum": 0, "number": 100, "hash": HASH, "forkId": 1},
+            {"shardNum": 0, "number": 100, "hash": HASH, "forkId": 2},
+        ])));
+        // fork id not increasing
+        assert!(invalid(json!([
+            {"shardNum": 0, "number": 200, "hash": HASH, "forkId": 1},
+            {"shardNum": 0, "number": 100, "hash": HASH, "forkId": 2},
+        ])));
+    }
+
+    #[test]
+    fn skip_points() {
+        let points = get_skip_points(&properties(json!({"crfgSkips": [
+            {"shardNum": 0, "number": 100, "hash": HASH, "skipNumber": 90},
+            {"shardNum": 1, "number": 100, "hash": HASH, "skipNumber": 90},
+        ]}))).unwrap();
+        assert_eq!(points.len(), 2);
+        assert_eq!((points[1].shard_num, points[1].skip_number), (1, 90));
+
+        let invalid = |skips: Value| get_skip_points(&properties(json!({"crfgSkips": skips}))).is_err();
+        assert!(invalid(json!([{"shardNum": 0, "number": 100, "hash": HASH, "skipNumber": 100}])));
+        assert!(invalid(json!([{"shardNum": 0, "number": 100, "hash": "0x", "skipNumber": 90}])));
+        assert!(invalid(json!([
+            {"shardNum": 0, "number": 100, "hash": HASH, "skipNumber": 90},
+            {"shardNum": 0, "number": 100, "hash": HASH, "skipNumber": 80},
+        ])));
+    }
+
+    #[test]
+    fn algorithm_and_lwma_points() {
+        let props = properties(json!({
+            "powAlgorithms": [{"shardNum": 0, "number": 100, "algorithm": "blake2-scratchpad"}],
+            "lwma": [{"shardNum": 0, "number": 100}],
+        }));
+        assert_eq!(get_algorithm_points(&props).unwrap(), vec![AlgorithmPoint { shard_num: 0, number: 100, algorithm: "blake2-scratchpad".to_string() }]);
+        assert_eq!(get_lwma_points(&props).unwrap(), vec![LwmaPoint { shard_num: 0, number: 100 }]);
+
+        assert!(get_algorithm_points(&properties(json!({"powAlgorithms": [{"shardNum": 0, "number": 100, "algorithm": "unknown"}]}))).is_err());
+        assert!(get_lwma_points(&properties(json!({"lwma": [{"shardNum": 0, "number": 100}, {"shardNum": 0, "number": 200}]}))).is_err());
+        assert!(get_pow_algorithm(&properties(json!({"powAlgorithm": "unknown"}))).is_err());
+    }
+}
@@ -0,0 +1,197 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Account keys, addresses and node keys
+
+use primitives::{sr25519, Pair};
+use structopt::StructOpt;
+use sharding_primitives::utils::shard_num_for;
+use yee_primitives::{Address, AddressCodec, Hrp};
+use yee_runtime::AccountId;
+use yee_dev::{NodeKeyConfig, get_peer_id};
+
+use crate::custom_param::NodeKeyType;
+use crate::error;
+use crate::service::foreign::{parse_secp256k1_secret, parse_ed25519_secret};
+use crate::tx::parse_pair;
+
+/// The `key` command used to manage account keys and node keys.
+#[derive(Debug, StructOpt, Clone)]
+pub struct KeyCmd {
+    #[structopt(subcommand)]
+    pub action: KeyAction,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub enum KeyAction {
+    /// Generate an account key
+    #[structopt(name = "generate")]
+    Generate {
+        #[allow(missing_docs)]
+        #[structopt(flatten)]
+        address_params: AddressParams,
+    },
+
+    /// Show the address and the shard of an account key or of an address
+    #[structopt(name = "inspect")]
+    Inspect {
+        /// sr25519 private key in hex, secret uri or address
+        #[structopt(value_name = "KEY")]
+        input: String,
+
+        #[allow(missing_docs)]
+        #[structopt(flatten)]
+        address_params: AddressParams,
+    },
+
+    /// Generate account keys until one belongs to the shard
+    #[structopt(name = "vanity")]
+    Vanity {
+        /// Specify shard number
+        #[structopt(long = "shard-num", value_name = "SHARD_NUM")]
+        shard_num: u16,
+
+        #[allow(missing_docs)]
+        #[structopt(flatten)]
+        address_params: AddressParams,
+    },
+
+    /// Generate a node key
+    #[structopt(name = "generate-node-key")]
+    GenerateNodeKey {
+        #[allow(missing_docs)]
+        #[structopt(flatten)]
+        node_key_params: KeyNodeKeyParams,
+    },
+
+    /// Show the peer id of a node key
+    #[structopt(name = "inspect-node-key")]
+    InspectNodeKey {
+        /// Node key in hex
+        #[structopt(value_name = "KEY")]
+        node_key: String,
+
+        #[allow(missing_docs)]
+        #[structopt(flatten)]
+        node_key_params: KeyNodeKeyParams,
+    },
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct AddressParams {
+    /// Address prefix, both `yee` and `tyee` addresses are shown if absent
+    #[structopt(long = "hrp", value_name = "HRP")]
+    pub hrp: Option<String>,
+
+    /// Shard count of the chain
+    #[structopt(long = "shard-count", value_name = "SHARD_COUNT", default_value = "4")]
+    pub shard_count: u16,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub struct KeyNodeKeyParams {
+    #[structopt(
+    long = "node-key-type",
+    value_name = "TYPE",
+    raw(
+    possible_values = "&NodeKeyType::variants()",
+    case_insensitive = "true",
+    default_value = r#""Secp256k1""#
+    )
+    )]
+    pub node_key_type: NodeKeyType,
+}
+
+impl substrate_cli::GetLogFilter for KeyCmd {
+    fn get_log_filter(&self) -> Option<String> {
+        None
+    }
+}
+
+pub fn run_key(cmd: KeyCmd) -> error::Result<()> {
+    match cmd.action {
+        KeyAction::Generate { address_params } => {
+            let (pair, phrase) = sr25519::Pair::generate_with_phrase(None);
+            println!("Secret phrase: {}", phrase);
+            print_account(&pair.public(), &address_params)
+        },
+        KeyAction::Inspect { input, address_params } => {
+            let account_id = match AccountId::from_address(&Address(input.clone())) {
+                Ok((account_id, _hrp)) => account_id,
+                Err(_) => parse_pair(&input)?.public(),
+            };
+            print_account(&account_id, &address_params)
+        },
+        KeyAction::Vanity { shard_num, address_params } => {
+            if shard_num >= address_params.shard_count {
+                return Err(error::ErrorKind::Input(format!("Invalid shard num: {}", shard_num)).into());
+            }
+            loop {
+                let (pair, phrase) = sr25519::Pair::generate_with_phrase(None);
+                if shard_num_of(&pair.public(), address_params.shard_count)? == shard_num {
+                    println!("Secret phrase: {}", phrase);
+                    return print_account(&pair.public(), &address_params);
+                }
+            }
+        },
+        KeyAction::GenerateNodeKey { node_key_params } => {
+            let node_key = match node_key_params.node_key_type {
+                NodeKeyType::Secp256k1 =>
+                    hex::encode(network::identity::secp256k1::SecretKey::generate().to_bytes()),
+                NodeKeyType::Ed25519 =>
+                    hex::encode(network::identity::ed25519::SecretKey::generate()),
+            };
+            print_node_key(node_key, node_key_params.node_key_type)
+        },
+        KeyAction::InspectNodeKey { node_key, node_key_params } =>
+            print_node_key(node_key.trim_start_matches("0x").to_string(), node_key_params.node_key_type),
+    }
+}
+
+fn print_account(account_id: &AccountId, address_params: &AddressParams) -> error::Result<()> {
+    println!("Public key: 0x{}", hex::encode(account_id));
+
+    let hrps = match &address_params.hrp {
+        Some(hrp) => vec![Hrp::from(hrp.clone())],
+        None => vec![Hrp::MAINNET, Hrp::TESTNET],
+    };
+    for hrp in hrps {
+        let address = account_id.to_address(hrp)
+            .map_err(|e| error::ErrorKind::Input(format!("Invalid hrp: {}", e)))?;
+        println!("Address: {}", address);
+    }
+
+    println!("Shard num: {} (of {} shards)", shard_num_of(account_id, address_params.shard_count)?, address_params.shard_count);
+
+    Ok(())
+}
+
+fn shard_num_of(account_id: &AccountId, shard_count: u16) -> error::Result<u16> {
+    shard_num_for(account_id, shard_count)
+        .ok_or_else(|| error::ErrorKind::Input(format!("Invalid shard count: {}", shard_count)).into())
+}
+
+fn print_node_key(node_key: String, node_key_type: NodeKeyType) -> error::Result<()> {
+    let node_key_config = match node_key_type {
+        NodeKeyType::Secp256k1 => NodeKeyConfig::Secp256k1(parse_secp256k1_secret(&node_key)?),
+        NodeKeyType::Ed25519 => NodeKeyConfig::Ed25519(parse_ed25519_secret(&node_key)?),
+    };
+    println!("Node key: {}", node_key);
+    println!("Peer id: {}", get_peer_id(&node_key_config));
+
+    Ok(())
+}
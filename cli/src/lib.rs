@@ -5,6 +5,8 @@ mod dev_param;
 mod service;
 mod chain_spec;
 mod chain_revert;
+mod tx;
+mod key;
 
 pub use crate::service::Factory;
 pub use crate::service::NodeConfig;
@@ -41,12 +41,15 @@ use futures::sync::mpsc;
 use yee_primitives::{RecommitRelay, AddressCodec};
 use std::collections::HashMap;
 
-mod foreign;
+pub(crate) mod foreign;
 use foreign::{start_foreign_network};
 
 mod restarter;
 use restarter::{start_restarter};
 
+mod scale_out;
+use scale_out::register_foreign_scale_out_provider;
+
 pub use substrate_executor::NativeExecutor;
 use yee_bootnodes_router::BootnodesRouterConf;
 use yee_rpc::{ProvideRpcExtra, Config};
@@ -214,11 +217,13 @@ impl<F> ProvideRpcExtra<DefaultJob<Block, <Pair as PairT>::Public>, F::Block, Co
         let job_cache_size = self.job_cache_size;
         let shard_num = self.shard_num;
         let shard_count = self.shard_count;
+        let fork_points = self.context.as_ref().map(|context| context.fork_points.clone()).unwrap_or_default();
         let config = Config {
             shard_num,
             shard_count,
             coinbase,
             job_cache_size,
+            fork_points,
         };
         Arc::new(config)
     }
@@ -321,6 +326,14 @@ construct_service_factory! {
                     *config_foreign_network = Some(foreign_network.clone());
                 }
 
+                // foreign scale out phases
+                register_foreign_scale_out_provider::<Self>(
+                    &service.config.custom.inherent_data_providers,
+                    service.config.custom.shard_num,
+                    service.config.custom.shard_count,
+                    service.config.custom.foreign_chains.clone(),
+                ).map_err(|e| format!("{:?}", e))?;
+
 
                 // relay
                 yee_relay::start_relay_transfer::<Self, _, _>(
@@ -397,7 +410,6 @@ construct_service_factory! {
                             trigger_exit: service.config.custom.trigger_exit.clone().expect("qed"),
                         },
                         context: service.config.custom.context.clone().expect("qed"),
-                        chain_spec_id: service.config.chain_spec.id().to_string(),
                     };
 
                     executor.spawn(start_pow::<Self, Self::Block, _, _, _, _, _, _, _>(
@@ -426,10 +438,12 @@ construct_service_factory! {
                     let shard_num = config.custom.shard_num;
                     let import_until = config.custom.import_until.as_ref().and_then(|x| x.get(&shard_num).cloned());
                     let import_leading = config.custom.import_leading;
+                    let context = config.custom.context.as_ref().expect("qed");
 
                     let (block_import, link_half) = crfg::block_import::<_, _, _, RuntimeApi, FullClient<Self>>(
                         client.clone(), client.clone(), validator, import_until, import_leading,
-                        config.chain_spec.id().to_string(),
+                        context.fork_points.clone(),
+                        context.skip_points.clone(),
                         config.custom.shard_num,
                         config.custom.import_crfg_state_providers.clone(),
                     )?;
@@ -452,7 +466,6 @@ construct_service_factory! {
                             trigger_exit: config.custom.trigger_exit.clone().expect("qed"),
                         },
                         config.custom.context.clone().expect("qed"),
-                        config.chain_spec.id().to_string(),
                         true,
                     ).expect("qed");
 
@@ -469,10 +482,12 @@ construct_service_factory! {
                     let shard_num = config.custom.shard_num;
                     let import_until = config.custom.import_until.as_ref().and_then(|x| x.get(&shard_num).cloned());
                     let import_leading = config.custom.import_leading;
+                    let context = config.custom.context.as_ref().expect("qed");
 
                     let (block_import, _) = crfg::block_import::<_, _, _, RuntimeApi, LightClient<Self>>(
                         client.clone(), client.clone(), false, import_until, import_leading,
-                        config.chain_spec.id().to_string(),
+                        context.fork_points.clone(),
+                        context.skip_points.clone(),
                         config.custom.shard_num,
                         config.custom.import_crfg_state_providers.clone(),
                     )?;
@@ -495,7 +510,6 @@ construct_service_factory! {
                             trigger_exit: config.custom.trigger_exit.clone().expect("qed"),
                         },
                         config.custom.context.clone().expect("qed"),
-                        config.chain_spec.id().to_string(),
                         false,
                     ).map_err(Into::into)
 
@@ -340,7 +340,7 @@ fn input_err<T: Into<String>>(msg: T) -> error::Error {
 }
 
 /// Parse a Secp256k1 secret key from a hex string into a `network::Secret`.
-fn parse_secp256k1_secret(hex: &String) -> error::Result<network::Secp256k1Secret> {
+pub(crate) fn parse_secp256k1_secret(hex: &String) -> error::Result<network::Secp256k1Secret> {
     H256::from_str(hex).map_err(invalid_node_key).and_then(|bytes|
         network::identity::secp256k1::SecretKey::from_bytes(bytes)
             .map(network::Secret::Input)
@@ -348,7 +348,7 @@ fn parse_secp256k1_secret(hex: &String) -> error::Result<network::Secp256k1Secre
 }
 
 /// Parse a Ed25519 secret key from a hex string into a `network::Secret`.
-fn parse_ed25519_secret(hex: &String) -> error::Result<network::Ed25519Secret> {
+pub(crate) fn parse_ed25519_secret(hex: &String) -> error::Result<network::Ed25519Secret> {
     H256::from_str(&hex).map_err(invalid_node_key).and_then(|bytes|
         network::identity::ed25519::SecretKey::from_bytes(bytes)
             .map(network::Secret::Input)
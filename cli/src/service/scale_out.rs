@@ -0,0 +1,97 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+use substrate_service::{ServiceFactory, Arc};
+use parking_lot::RwLock;
+use parity_codec::Decode;
+use inherents::{InherentData, InherentDataProviders, ProvideInherentData, RuntimeString};
+use runtime_primitives::generic::BlockId;
+use runtime_primitives::traits::{Header as HeaderT, Digest as DigestT, DigestItemFor, NumberFor};
+use foreign_chain::{ForeignChain, ForeignChainConfig};
+use yee_sharding::{ScaleOutPhaseDigestItem, ScaleOutPhase};
+use log::debug;
+
+/// Provides the latest scale out phase of every foreign shard, read from the foreign light clients
+pub struct ForeignScaleOutProvider<F: ServiceFactory> {
+	shard_num: u16,
+	shard_count: u16,
+	foreign_chains: Arc<RwLock<Option<ForeignChain<F>>>>,
+}
+
+impl<F> ForeignScaleOutProvider<F> where
+	F: ServiceFactory,
+	<F as ServiceFactory>::Configuration: ForeignChainConfig,
+	DigestItemFor<F::Block>: ScaleOutPhaseDigestItem<NumberFor<F::Block>, u16>,
+{
+	pub fn new(shard_num: u16, shard_count: u16, foreign_chains: Arc<RwLock<Option<ForeignChain<F>>>>) -> Self {
+		Self { shard_num, shard_count, foreign_chains }
+	}
+
+	fn foreign_phases(&self) -> Vec<(u16, Option<ScaleOutPhase<NumberFor<F::Block>, u16>>)> {
+		let foreign_chains = self.foreign_chains.read();
+		let foreign_chains = match foreign_chains.as_ref() {
+			Some(foreign_chains) => foreign_chains,
+			None => return Vec::new(),
+		};
+
+		(0..self.shard_count).filter(|x| *x != self.shard_num).filter_map(|shard_num| {
+			let client = foreign_chains.get_shard_component(shard_num)?.client();
+			let best_hash = client.info().ok()?.chain.best_hash;
+			let header = client.header(&BlockId::hash(best_hash)).ok()??;
+			let phase = header.digest().logs().iter().rev()
+				.filter_map(ScaleOutPhaseDigestItem::as_scale_out_phase)
+				.next();
+			debug!("Foreign shard {} scale out phase: {:?}", shard_num, phase);
+			Some((shard_num, phase))
+		}).collect()
+	}
+}
+
+impl<F> ProvideInherentData for ForeignScaleOutProvider<F> where
+	F: ServiceFactory,
+	<F as ServiceFactory>::Configuration: ForeignChainConfig,
+	DigestItemFor<F::Block>: ScaleOutPhaseDigestItem<NumberFor<F::Block>, u16>,
+{
+	fn inherent_identifier(&self) -> &'static [u8; 8] {
+		&srml_sharding::FOREIGN_INHERENT_IDENTIFIER
+	}
+
+	fn provide_inherent_data(&self, inherent_data: &mut InherentData) -> Result<(), RuntimeString> {
+		inherent_data.put_data(srml_sharding::FOREIGN_INHERENT_IDENTIFIER, &self.foreign_phases())
+	}
+
+	fn error_to_string(&self, error: &[u8]) -> Option<String> {
+		RuntimeString::decode(&mut &error[..]).map(Into::into)
+	}
+}
+
+pub fn register_foreign_scale_out_provider<F>(
+	inherent_data_providers: &InherentDataProviders,
+	shard_num: u16,
+	shard_count: u16,
+	foreign_chains: Arc<RwLock<Option<ForeignChain<F>>>>,
+) -> Result<(), RuntimeString> where
+	F: ServiceFactory,
+	<F as ServiceFactory>::Configuration: ForeignChainConfig,
+	DigestItemFor<F::Block>: ScaleOutPhaseDigestItem<NumberFor<F::Block>, u16>,
+{
+	if !inherent_data_providers.has_provider(&srml_sharding::FOREIGN_INHERENT_IDENTIFIER) {
+		inherent_data_providers.register_provider(ForeignScaleOutProvider::new(shard_num, shard_count, foreign_chains))
+	} else {
+		Ok(())
+	}
+}
@@ -0,0 +1,419 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Offline construction, signing and decoding of extrinsics
+//!
+//! Nothing is read from a node: the nonce, the era and the checkpoint hash
+//! (the genesis hash for immortal extrinsics) are given on the command line.
+
+use parity_codec::{Compact, Decode, Encode};
+use primitives::{blake2_256, sr25519, Pair, H256};
+use runtime_primitives::generic::Era;
+use structopt::StructOpt;
+use yee_primitives::{Address, AddressCodec};
+use yee_runtime::{AccountId, AssetsCall, BalancesCall, Call, Nonce, SudoCall, UncheckedExtrinsic};
+
+use crate::error;
+
+/// The `tx` command used to build, sign and decode extrinsics.
+#[derive(Debug, StructOpt, Clone)]
+pub struct TxCmd {
+    #[structopt(subcommand)]
+    pub action: TxAction,
+}
+
+#[derive(Debug, StructOpt, Clone)]
+pub enum TxAction {
+    /// Transfer balance
+    #[structopt(name = "transfer")]
+    Transfer {
+        /// Address of the receiver
+        #[structopt(long = "dest", value_name = "ADDRESS")]
+        dest: String,
+
+        /// Amount to transfer
+        #[structopt(long = "value", value_name = "VALUE")]
+        value: u128,
+
+        #[allow(missing_docs)]
+        #[structopt(flatten)]
+        sign_params: SignParams,
+    },
+
+    /// Transfer assets
+    #[structopt(name = "asset-transfer")]
+    AssetTransfer {
+        /// Shard code of the asset, in hex
+        #[structopt(long = "shard-code", value_name = "SHARD_CODE")]
+        shard_code: String,
+
+        /// Id of the asset
+        #[structopt(long = "asset-id", value_name = "ASSET_ID")]
+        asset_id: u32,
+
+        /// Address of the receiver
+        #[structopt(long = "dest", value_name = "ADDRESS")]
+        dest: String,
+
+        /// Amount to transfer
+        #[structopt(long = "value", value_name = "VALUE")]
+        value: u128,
+
+        #[allow(missing_docs)]
+        #[structopt(flatten)]
+        sign_params: SignParams,
+    },
+
+    /// Dispatch a call with the root origin, signed by a sudo key, while a single key is required
+    #[structopt(name = "sudo")]
+    Sudo {
+        /// Encoded call, in hex
+        #[structopt(long = "call", value_name = "CALL")]
+        call: String,
+
+        #[allow(missing_docs)]
+        #[structopt(flatten)]
+        sign_params: SignParams,
+    },
+
+    /// Propose a call to be dispatched with the root origin, approved by the proposer
+    #[structopt(name = "sudo-propose")]
+    SudoPropose {
+        /// Encoded call, in hex
+        #[structopt(long = "call", value_name = "CALL")]
+        call: String,
+
+        #[allow(missing_docs)]
+        #[structopt(flatten)]
+        sign_params: SignParams,
+    },
+
+    /// Approve a proposed call
+    #[structopt(name = "sudo-approve")]
+    SudoApprove {
+        /// Proposal hash, the blake2 hash of the encoded call (shown by `decode`)
+        #[structopt(long = "proposal-hash", value_name = "HASH")]
+        proposal_hash: String,
+
+        #[allow(missing_docs)]
+        #[structopt(flatten)]
+        sign_params: SignParams,
+    },
+
+    /// Dispatch an approved call once its delay has passed
+    #[structopt(name = "sudo-execute")]
+    SudoExecute {
+        /// Encoded call, in hex
+        #[structopt(long = "call", value_name = "CALL")]
+        call: String,
+
+        #[allow(missing_docs)]
+        #[structopt(flatten)]
+        sign_params: SignParams,
+    },
+
+    /// Drop a proposed call
+    #[structopt(name = "sudo-cancel")]
+    SudoCancel {
+        /// Proposal hash, the blake2 hash of the encoded call (shown by `decode`)
+        #[structopt(long = "proposal-hash", value_name = "HASH")]
+        proposal_hash: String,
+
+        #[allow(missing_docs)]
+        #[structopt(flatten)]
+        sign_params: SignParams,
+    },
+
+    /// Decode an extrinsic or a call
+    #[structopt(name = "decode")]
+    Decode {
+        /// Encoded extrinsic or call, in hex
+        #[structopt(value_name = "HEX")]
+        input: String,
+    },
+}
+
+/// Without a seed, the encoded call is printed instead of a signed extrinsic
+#[derive(Debug, StructOpt, Clone)]
+pub struct SignParams {
+    /// Signer: sr25519 private key in hex (32 bytes mini secret or 64 bytes secret), or secret uri
+    #[structopt(long = "seed", value_name = "SEED")]
+    pub seed: Option<String>,
+
+    /// Nonce of the signer
+    #[structopt(long = "nonce", value_name = "NONCE", default_value = "0")]
+    pub nonce: Nonce,
+
+    /// Genesis hash of the shard, the checkpoint of immortal extrinsics
+    #[structopt(long = "genesis-hash", value_name = "HASH")]
+    pub genesis_hash: Option<String>,
+
+    /// Period of a mortal era, in blocks
+    #[structopt(long = "era-period", value_name = "PERIOD")]
+    pub era_period: Option<u64>,
+
+    /// Current block number of a mortal era
+    #[structopt(long = "era-current", value_name = "NUMBER")]
+    pub era_current: Option<u64>,
+
+    /// Hash of the block the mortal era starts at, the checkpoint of mortal extrinsics
+    #[structopt(long = "checkpoint-hash", value_name = "HASH")]
+    pub checkpoint_hash: Option<String>,
+}
+
+impl substrate_cli::GetLogFilter for TxCmd {
+    fn get_log_filter(&self) -> Option<String> {
+        None
+    }
+}
+
+pub fn run_tx(cmd: TxCmd) -> error::Result<()> {
+    let (call, sign_params) = match cmd.action {
+        TxAction::Transfer { dest, value, sign_params } => {
+            let call = Call::Balances(BalancesCall::transfer(parse_account_id(&dest)?.into(), value));
+            (call, sign_params)
+        },
+        TxAction::AssetTransfer { shard_code, asset_id, dest, value, sign_params } => {
+            let call = Call::Assets(AssetsCall::transfer(parse_hex(&shard_code)?, asset_id, parse_account_id(&dest)?.into(), value));
+            (call, sign_params)
+        },
+        TxAction::Sudo { call, sign_params } =>
+            (Call::Sudo(SudoCall::sudo(Box::new(parse_call(&call)?))), sign_params),
+        TxAction::SudoPropose { call, sign_params } =>
+            (Call::Sudo(SudoCall::propose(proposal_hash(&parse_call(&call)?))), sign_params),
+        TxAction::SudoApprove { proposal_hash, sign_params } =>
+            (Call::Sudo(SudoCall::approve(parse_hash(&proposal_hash)?)), sign_params),
+        TxAction::SudoExecute { call, sign_params } =>
+            (Call::Sudo(SudoCall::execute(Box::new(parse_call(&call)?))), sign_params),
+        TxAction::SudoCancel { proposal_hash, sign_params } =>
+            (Call::Sudo(SudoCall::cancel(parse_hash(&proposal_hash)?)), sign_params),
+        TxAction::Decode { input } => return decode(&input),
+    };
+
+    match sign_params.seed.clone() {
+        Some(seed) => {
+            let xt = sign(call, &seed, &sign_params)?;
+            println!("0x{}", hex::encode(xt.encode()));
+        },
+        None => println!("0x{}", hex::encode(call.encode())),
+    }
+
+    Ok(())
+}
+
+fn sign(call: Call, seed: &str, sign_params: &SignParams) -> error::Result<UncheckedExtrinsic> {
+    let pair = parse_pair(seed)?;
+
+    let (era, checkpoint_hash) = match (sign_params.era_period, sign_params.era_current) {
+        (Some(period), Some(current)) => {
+            let checkpoint_hash = sign_params.checkpoint_hash.as_ref()
+                .ok_or_else(|| error::ErrorKind::Input("Mortal era requires checkpoint-hash".to_string()))?;
+            (Era::mortal(period, current), parse_hash(checkpoint_hash)?)
+        },
+        (None, None) => {
+            let genesis_hash = sign_params.genesis_hash.as_ref()
+                .ok_or_else(|| error::ErrorKind::Input("Immortal era requires genesis-hash".to_string()))?;
+            (Era::Immortal, parse_hash(genesis_hash)?)
+        },
+        _ => return Err(error::ErrorKind::Input("Mortal era requires both era-period and era-current".to_string()).into()),
+    };
+
+    // the payload checked by the runtime, hashed when longer than 256 bytes
+    let payload = (Compact(sign_params.nonce), &call, era, checkpoint_hash).encode();
+    let signature = if payload.len() > 256 {
+        pair.sign(&blake2_256(&payload)[..])
+    } else {
+        pair.sign(&payload)
+    };
+
+    Ok(UncheckedExtrinsic::new_signed(sign_params.nonce, call, pair.public().into(), signature, era))
+}
+
+fn decode(input: &str) -> error::Result<()> {
+    let input = parse_hex(input)?;
+
+    if let Some(xt) = UncheckedExtrinsic::decode(&mut &input[..]) {
+        match &xt.signature {
+            Some((address, signature, nonce, era)) => {
+                println!("signer: {:?}", address);
+                println!("signature: {:?}", signature);
+                println!("nonce: {}", nonce.0);
+                println!("era: {:?}", era);
+            },
+            None => println!("unsigned"),
+        }
+        println!("call: {:?}", xt.function);
+        return Ok(());
+    }
+
+    let call = Call::decode(&mut &input[..])
+        .ok_or_else(|| error::ErrorKind::Input("Invalid extrinsic or call".to_string()))?;
+    println!("call: {:?}", call);
+    println!("proposal hash: {:?}", proposal_hash(&call));
+
+    Ok(())
+}
+
+/// Hash of a call proposed to the sudo keys
+fn proposal_hash(call: &Call) -> H256 {
+    blake2_256(&call.encode()).into()
+}
+
+pub(crate) fn parse_pair(seed: &str) -> error::Result<sr25519::Pair> {
+    match hex::decode(seed.trim_start_matches("0x")) {
+        Ok(seed) => sr25519::Pair::from_seed_slice(&seed),
+        Err(_) => sr25519::Pair::from_string(seed, None),
+    }.map_err(|e| error::ErrorKind::Input(format!("Invalid seed: {:?}", e)).into())
+}
+
+fn parse_call(input: &str) -> error::Result<Call> {
+    Call::decode(&mut &parse_hex(input)?[..])
+        .ok_or_else(|| error::ErrorKind::Input("Invalid call".to_string()).into())
+}
+
+fn parse_account_id(address: &str) -> error::Result<AccountId> {
+    AccountId::from_address(&Address(address.to_string()))
+        .map(|(account_id, _hrp)| account_id)
+        .map_err(|e| error::ErrorKind::Input(format!("Invalid address: {}", e)).into())
+}
+
+fn parse_hash(input: &str) -> error::Result<H256> {
+    let hash = parse_hex(input)?;
+    if hash.len() != 32 {
+        return Err(error::ErrorKind::Input(format!("Invalid hash: {}", input)).into());
+    }
+    Ok(H256::from_slice(&hash))
+}
+
+fn parse_hex(input: &str) -> error::Result<Vec<u8>> {
+    hex::decode(input.trim_start_matches("0x"))
+        .map_err(|e| error::ErrorKind::Input(format!("Invalid hex: {}", e)).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runtime_primitives::traits::{BlockNumberToHash, Checkable, CurrentHeight, Lookup, StaticLookup};
+    use yee_runtime::{BlockNumber, Indices};
+
+    const SEED: &str = "//Alice";
+
+    /// The chain state `check` needs: the signer and the checkpoint block of the era
+    struct TestContext {
+        signer: AccountId,
+        height: BlockNumber,
+        checkpoint: (BlockNumber, H256),
+    }
+
+    impl Lookup for TestContext {
+        type Source = <Indices as StaticLookup>::Source;
+        type Target = AccountId;
+
+        fn lookup(&self, address: Self::Source) -> Result<AccountId, &'static str> {
+            if address == Indices::unlookup(self.signer.clone()) {
+                Ok(self.signer.clone())
+            } else {
+                Err("unknown address")
+            }
+        }
+    }
+
+    impl CurrentHeight for TestContext {
+        type BlockNumber = BlockNumber;
+
+        fn current_height(&self) -> BlockNumber {
+            self.height
+        }
+    }
+
+    impl BlockNumberToHash for TestContext {
+        type BlockNumber = BlockNumber;
+        type Hash = H256;
+
+        fn block_number_to_hash(&self, number: BlockNumber) -> Option<H256> {
+            if number == self.checkpoint.0 { Some(self.checkpoint.1) } else { None }
+        }
+    }
+
+    fn sign_params(nonce: Nonce) -> SignParams {
+        SignParams {
+            seed: Some(SEED.to_string()),
+            nonce,
+            genesis_hash: None,
+            era_period: None,
+            era_current: None,
+            checkpoint_hash: None,
+        }
+    }
+
+    fn round_trip(xt: UncheckedExtrinsic, context: &TestContext) -> Result<(Option<(AccountId, Nonce)>, Call), &'static str> {
+        let xt = UncheckedExtrinsic::decode(&mut &xt.encode()[..]).expect("encoded extrinsic");
+        xt.check(context).map(|checked| (checked.signed, checked.function))
+    }
+
+    #[test]
+    fn signed_extrinsics_round_trip() {
+        let signer = parse_pair(SEED).unwrap().public();
+        let call = Call::Balances(BalancesCall::transfer(Indices::unlookup(AccountId::from_raw([2; 32])), 1000));
+        let genesis_hash = H256::repeat_byte(1);
+
+        // immortal
+        let mut params = sign_params(5);
+        params.genesis_hash = Some(format!("0x{}", hex::encode(genesis_hash)));
+        let xt = sign(call.clone(), SEED, &params).unwrap();
+        let context = TestContext { signer: signer.clone(), height: 100, checkpoint: (0, genesis_hash) };
+        assert_eq!(round_trip(xt.clone(), &context), Ok((Some((signer.clone(), 5)), call.clone())));
+
+        // signed for another chain
+        let context = TestContext { signer: signer.clone(), height: 100, checkpoint: (0, H256::repeat_byte(3)) };
+        assert!(round_trip(xt, &context).is_err());
+
+        // mortal
+        let checkpoint_hash = H256::repeat_byte(4);
+        let mut params = sign_params(6);
+        params.era_period = Some(64);
+        params.era_current = Some(100);
+        params.checkpoint_hash = Some(format!("0x{}", hex::encode(checkpoint_hash)));
+        let xt = sign(call.clone(), SEED, &params).unwrap();
+        let birth = Era::mortal(64, 100).birth(100);
+        let context = TestContext { signer: signer.clone(), height: 100, checkpoint: (birth, checkpoint_hash) };
+        assert_eq!(round_trip(xt, &context), Ok((Some((signer, 6)), call)));
+    }
+
+    #[test]
+    fn long_signed_extrinsics_round_trip() {
+        // a payload over 256 bytes is signed by its hash
+        let signer = parse_pair(SEED).unwrap().public();
+        let keys = (0..10u8).map(|i| Indices::unlookup(AccountId::from_raw([i; 32]))).collect::<Vec<_>>();
+        let call = Call::Sudo(SudoCall::execute(Box::new(Call::Sudo(SudoCall::set_keys(keys)))));
+        assert!(call.encode().len() > 256);
+
+        let mut params = sign_params(0);
+        params.genesis_hash = Some(format!("0x{}", hex::encode(H256::repeat_byte(1))));
+        let xt = sign(call.clone(), SEED, &params).unwrap();
+        let context = TestContext { signer: signer.clone(), height: 0, checkpoint: (0, H256::repeat_byte(1)) };
+        assert_eq!(round_trip(xt, &context), Ok((Some((signer, 0)), call)));
+    }
+
+    #[test]
+    fn proposal_hash_matches_the_runtime() {
+        use runtime_primitives::traits::{BlakeTwo256, Hash};
+
+        let call = Call::Sudo(SudoCall::set_threshold(2));
+        assert_eq!(proposal_hash(&call), BlakeTwo256::hash_of(&Box::new(call)));
+    }
+}
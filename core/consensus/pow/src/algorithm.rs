@@ -0,0 +1,193 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+//! POW hash algorithms
+//!
+//! The algorithm of a chain is named by the `powAlgorithm` property of its chain spec
+//! (`blake2` if absent), and can be switched at the points of its `powAlgorithms` property.
+//! Other algorithms can be plugged in with `register_algorithm` before the service starts.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+use primitives::{blake2_256, H256};
+use yee_context::AlgorithmPoint;
+
+pub const DEFAULT_POW_ALGORITHM: &str = "blake2";
+
+/// Nonces tried by the node worker per job
+pub const DEFAULT_NONCE_WINDOW: u64 = 10000;
+
+/// Hash algorithm of the work, the hash being compared with the pow target
+pub trait PowAlgorithm: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Hash of the encoded work: the sealed header for nonce proofs,
+    /// `(merkle_root, nonce, extra_data)` for multi proofs
+    fn hash(&self, data: &[u8]) -> H256;
+
+    /// Nonces tried by the node worker per job, lower for slower algorithms
+    fn nonce_window(&self) -> u64 {
+        DEFAULT_NONCE_WINDOW
+    }
+}
+
+/// Blake2b-256, the hash of the headers
+pub struct Blake2;
+
+impl PowAlgorithm for Blake2 {
+    fn name(&self) -> &str {
+        DEFAULT_POW_ALGORITHM
+    }
+
+    fn hash(&self, data: &[u8]) -> H256 {
+        blake2_256(data).into()
+    }
+}
+
+/// Memory-hard blake2: fills a scratchpad with a blake2 hash chain seeded by the work,
+/// then mixes in the scratchpad items picked by the running hash
+pub struct Blake2Scratchpad {
+    /// Number of 32 bytes items of the scratchpad
+    pub items: usize,
+}
+
+/// 1 MiB scratchpad
+pub const DEFAULT_SCRATCHPAD_ITEMS: usize = 1 << 15;
+
+impl Default for Blake2Scratchpad {
+    fn default() -> Self {
+        Self { items: DEFAULT_SCRATCHPAD_ITEMS }
+    }
+}
+
+impl PowAlgorithm for Blake2Scratchpad {
+    fn name(&self) -> &str {
+        "blake2-scratchpad"
+    }
+
+    fn hash(&self, data: &[u8]) -> H256 {
+        let mut scratchpad = Vec::with_capacity(self.items);
+        let mut item = blake2_256(data);
+        for _ in 0..self.items {
+            scratchpad.push(item);
+            item = blake2_256(&item);
+        }
+
+        let mut acc = item;
+        let mut input = [0u8; 64];
+        for _ in 0..self.items {
+            let index = u32::from_le_bytes([acc[0], acc[1], acc[2], acc[3]]) as usize % self.items;
+            input[..32].copy_from_slice(&acc);
+            input[32..].copy_from_slice(&scratchpad[index]);
+            acc = blake2_256(&input);
+        }
+        acc.into()
+    }
+
+    fn nonce_window(&self) -> u64 {
+        100
+    }
+}
+
+lazy_static! {
+    static ref ALGORITHMS: RwLock<HashMap<String, Arc<dyn PowAlgorithm>>> = {
+        let mut algorithms = HashMap::<String, Arc<dyn PowAlgorithm>>::new();
+        algorithms.insert(Blake2.name().to_string(), Arc::new(Blake2));
+        let scratchpad = Blake2Scratchpad::default();
+        algorithms.insert(scratchpad.name().to_string(), Arc::new(scratchpad));
+        RwLock::new(algorithms)
+    };
+}
+
+/// Register an algorithm under its name, replacing the one of the same name
+pub fn register_algorithm(algorithm: Arc<dyn PowAlgorithm>) {
+    ALGORITHMS.write().insert(algorithm.name().to_string(), algorithm);
+}
+
+pub fn get_algorithm(name: &str) -> Result<Arc<dyn PowAlgorithm>, String> {
+    ALGORITHMS.read().get(name).cloned().ok_or_else(|| format!("Unknown pow algorithm: {}", name))
+}
+
+/// Name of the algorithm of the block `number`, the one of the last algorithm point before it
+pub fn algorithm_name(algorithm_points: &[AlgorithmPoint], shard_num: u16, genesis_algorithm: &str, number: u64) -> String {
+    algorithm_points.iter()
+        .filter(|point| point.shard_num == shard_num && number > point.number)
+        .max_by_key(|point| point.number)
+        .map(|point| point.algorithm.as_str())
+        .unwrap_or(genesis_algorithm)
+        .to_string()
+}
+
+/// Algorithm of the block `number`
+pub fn select_algorithm(algorithm_points: &[AlgorithmPoint], shard_num: u16, genesis_algorithm: &str, number: u64) -> Result<Arc<dyn PowAlgorithm>, String> {
+    get_algorithm(&algorithm_name(algorithm_points, shard_num, genesis_algorithm, number))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parity_codec::Encode;
+    use runtime_primitives::traits::{BlakeTwo256, Hash as HashT};
+
+    #[test]
+    fn blake2_matches_hashing() {
+        let source = (H256::from([1u8; 32]), 42u64, [2u8; 16]);
+        assert_eq!(Blake2.hash(&source.encode()), BlakeTwo256::hash_of(&source));
+    }
+
+    #[test]
+    fn scratchpad_is_deterministic() {
+        let algorithm = Blake2Scratchpad { items: 64 };
+        assert_eq!(algorithm.hash(b"work"), algorithm.hash(b"work"));
+        assert_ne!(algorithm.hash(b"work"), algorithm.hash(b"another work"));
+        assert_ne!(algorithm.hash(b"work"), Blake2.hash(b"work"));
+    }
+
+    #[test]
+    fn registered_algorithms() {
+        assert_eq!(get_algorithm("blake2").unwrap().name(), "blake2");
+        assert_eq!(get_algorithm("blake2-scratchpad").unwrap().name(), "blake2-scratchpad");
+        assert!(get_algorithm("unknown").is_err());
+
+        struct Test;
+        impl PowAlgorithm for Test {
+            fn name(&self) -> &str { "test" }
+            fn hash(&self, _data: &[u8]) -> H256 { H256::zero() }
+        }
+        register_algorithm(Arc::new(Test));
+        assert_eq!(get_algorithm("test").unwrap().hash(b"work"), H256::zero());
+    }
+
+    #[test]
+    fn algorithm_at_fork_points() {
+        let point = |shard_num, number, algorithm: &str| AlgorithmPoint { shard_num, number, algorithm: algorithm.to_string() };
+        let points = vec![
+            point(0, 200, "blake2"),
+            point(0, 100, "blake2-scratchpad"),
+            point(1, 50, "blake2-scratchpad"),
+        ];
+        let name = |shard_num, number| algorithm_name(&points, shard_num, "blake2", number);
+        assert_eq!(name(0, 100), "blake2");
+        assert_eq!(name(0, 101), "blake2-scratchpad");
+        assert_eq!(name(0, 201), "blake2");
+        assert_eq!(name(1, 51), "blake2-scratchpad");
+        assert_eq!(name(2, 1000), "blake2");
+        assert_eq!(algorithm_name(&[], 0, "blake2-scratchpad", 150), "blake2-scratchpad");
+    }
+}
@@ -0,0 +1,213 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+//! POW target adjustment per block
+//!
+//! From the `lwma` points of the chain spec, the pow target of each block is the average pow target
+//! of the last `LWMA_WINDOW` blocks scaled by the linearly weighted moving average of their
+//! solve times, the recent blocks weighing the most.
+//! Each solve time is clamped to plus or minus `LWMA_MAX_SOLVE_TIME_FACTOR` target block times,
+//! so that a timestamp too far ahead is compensated by the negative solve time of the next block,
+//! and out-of-order timestamps can only move the target by a bounded step.
+
+use std::cmp::{max, min};
+use pow_primitives::PowTarget;
+use yee_context::LwmaPoint;
+
+/// Number of blocks averaged
+pub const LWMA_WINDOW: usize = 60;
+
+/// Solve times are clamped to this many target block times, either way
+pub const LWMA_MAX_SOLVE_TIME_FACTOR: u64 = 6;
+
+/// The weighted solve time is at least this fraction of the expected one, bounding the step
+/// of the target when the timestamps go backwards
+const LWMA_MIN_WEIGHTED_SOLVE_TIME_DIVISOR: i64 = 10;
+
+/// Whether the pow target of the block `number` is adjusted per block
+pub fn lwma_enabled(lwma_points: &[LwmaPoint], shard_num: u16, number: u64) -> bool {
+    lwma_points.iter().any(|point| point.shard_num == shard_num && number > point.number)
+}
+
+/// Pow target of the next block
+///
+/// `samples`: (timestamp, pow_target) of the last `LWMA_WINDOW + 1` blocks, oldest first.
+/// The first sample only gives the start time of the second.
+/// `target_block_time`: in ms
+///
+/// Returns None before a full window, the pow target of the parent being kept.
+pub fn lwma_target(samples: &[(u64, PowTarget)], target_block_time: u64) -> Option<PowTarget> {
+    if samples.len() < LWMA_WINDOW + 1 {
+        return None;
+    }
+    let n = samples.len() as u64 - 1;
+    let max_solve_time = (LWMA_MAX_SOLVE_TIME_FACTOR * target_block_time) as i64;
+
+    let mut weighted_solve_time = 0i64;
+    let mut avg_target = PowTarget::zero();
+    for (i, window) in samples.windows(2).enumerate() {
+        let solve_time = window[1].0 as i64 - window[0].0 as i64;
+        let solve_time = min(max(solve_time, -max_solve_time), max_solve_time);
+
+        weighted_solve_time += (i as i64 + 1) * solve_time;
+        avg_target = avg_target.saturating_add(window[1].1 / n);
+    }
+
+    // avg_target * weighted_solve_time / k, k being weighted_solve_time at the expected pace
+    let k = n * (n + 1) / 2 * target_block_time;
+    let weighted_solve_time = max(weighted_solve_time, k as i64 / LWMA_MIN_WEIGHTED_SOLVE_TIME_DIVISOR);
+    let k = PowTarget::from(k);
+    let weighted_solve_time = PowTarget::from(weighted_solve_time as u64);
+    let pow_target = (avg_target / k).saturating_mul(weighted_solve_time)
+        .saturating_add(avg_target % k * weighted_solve_time / k);
+
+    Some(max(pow_target, PowTarget::one()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TARGET_BLOCK_TIME: u64 = 30_000;
+
+    /// xorshift64, so that the simulations are reproducible
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        /// exponential distribution of mean 1
+        fn exp(&mut self) -> f64 {
+            let u = (self.next() >> 11) as f64 / (1u64 << 53) as f64;
+            -(1.0 - u).ln()
+        }
+    }
+
+    /// Mine `blocks` blocks with the hashrate (hashes per ms) `curve(i)` for the block i,
+    /// returning the (timestamp, pow_target) of the chain
+    fn simulate<C: Fn(usize) -> u64>(curve: C, blocks: usize, genesis_pow_target: PowTarget) -> Vec<(u64, PowTarget)> {
+        let mut rng = Rng(42);
+        let mut chain = vec![(0u64, genesis_pow_target)];
+        for i in 0..blocks {
+            let window = &chain[chain.len().saturating_sub(LWMA_WINDOW + 1)..];
+            let pow_target = lwma_target(window, TARGET_BLOCK_TIME).unwrap_or(chain[chain.len() - 1].1);
+            let hashes = (PowTarget::max_value() / pow_target).low_u64();
+            let solve_time = (hashes as f64 / curve(i) as f64 * rng.exp()) as u64;
+            let timestamp = chain[chain.len() - 1].0 + solve_time;
+            chain.push((timestamp, pow_target));
+        }
+        chain
+    }
+
+    /// Mean solve time of the blocks from..to, in target block times
+    fn mean_solve_time(chain: &[(u64, PowTarget)], from: usize, to: usize) -> f64 {
+        (chain[to].0 - chain[from].0) as f64 / (to - from) as f64 / TARGET_BLOCK_TIME as f64
+    }
+
+    fn genesis_pow_target(hashrate: u64) -> PowTarget {
+        PowTarget::max_value() / (hashrate * TARGET_BLOCK_TIME)
+    }
+
+    #[test]
+    fn converges_from_genesis() {
+        // genesis target 10 times too easy
+        let chain = simulate(|_| 10_000, 600, genesis_pow_target(1_000));
+        assert!(mean_solve_time(&chain, 0, 60) < 0.5);
+        let mean = mean_solve_time(&chain, 300, 600);
+        assert!(mean > 0.9 && mean < 1.1, "mean: {}", mean);
+    }
+
+    #[test]
+    fn hashrate_drop() {
+        // scale out: the hashrate of the shard is divided by 4
+        let chain = simulate(|i| if i < 300 { 1_000 } else { 250 }, 900, genesis_pow_target(1_000));
+        let first_window = mean_solve_time(&chain, 300, 360);
+        assert!(first_window < 2.5, "first window: {}", first_window);
+        let mean = mean_solve_time(&chain, 420, 900);
+        assert!(mean > 0.9 && mean < 1.1, "mean: {}", mean);
+    }
+
+    #[test]
+    fn hashrate_rise() {
+        let chain = simulate(|i| if i < 300 { 1_000 } else { 4_000 }, 900, genesis_pow_target(1_000));
+        let first_window = mean_solve_time(&chain, 300, 360);
+        assert!(first_window > 0.35, "first window: {}", first_window);
+        let mean = mean_solve_time(&chain, 420, 900);
+        assert!(mean > 0.9 && mean < 1.1, "mean: {}", mean);
+    }
+
+    #[test]
+    fn out_of_order_timestamps() {
+        let pow_target = genesis_pow_target(1_000);
+        let mut samples = (0..=LWMA_WINDOW as u64)
+            .map(|i| (1_000_000 + i * TARGET_BLOCK_TIME, pow_target))
+            .collect::<Vec<_>>();
+        assert_eq!(lwma_target(&samples, TARGET_BLOCK_TIME), Some(pow_target / PowTarget::from(LWMA_WINDOW as u64) * PowTarget::from(LWMA_WINDOW as u64)));
+
+        // a block far in the past, then one far in the future
+        samples[30].0 = 0;
+        samples[40].0 = u64::max_value() / 2;
+        let adjusted = lwma_target(&samples, TARGET_BLOCK_TIME).unwrap();
+        assert!(adjusted < pow_target * PowTarget::from(2) && adjusted > pow_target / PowTarget::from(2));
+
+        // all the timestamps going backwards
+        let samples = (0..=LWMA_WINDOW as u64)
+            .map(|i| (10_000_000 - i * TARGET_BLOCK_TIME, pow_target))
+            .collect::<Vec<_>>();
+        let adjusted = lwma_target(&samples, TARGET_BLOCK_TIME).unwrap();
+        assert!(adjusted <= pow_target / PowTarget::from(LWMA_MIN_WEIGHTED_SOLVE_TIME_DIVISOR as u64)
+            && adjusted > pow_target / PowTarget::from(11));
+
+        assert_eq!(lwma_target(&samples[1..], TARGET_BLOCK_TIME), None);
+    }
+
+    #[test]
+    fn future_timestamp_is_compensated() {
+        let pow_target = genesis_pow_target(1_000);
+        let mut samples = (0..=LWMA_WINDOW as u64)
+            .map(|i| (1_000_000 + i * TARGET_BLOCK_TIME, pow_target))
+            .collect::<Vec<_>>();
+
+        // a block an hour ahead, followed by honest blocks
+        samples[40].0 += 120 * TARGET_BLOCK_TIME;
+        let adjusted = lwma_target(&samples, TARGET_BLOCK_TIME).unwrap();
+        assert!(adjusted > pow_target / PowTarget::from(100) * PowTarget::from(90)
+            && adjusted < pow_target / PowTarget::from(100) * PowTarget::from(110));
+
+        // the future block at the tip only eases the target by a bounded step
+        let mut samples = samples[..].to_vec();
+        samples[40].0 -= 120 * TARGET_BLOCK_TIME;
+        samples[LWMA_WINDOW].0 += 120 * TARGET_BLOCK_TIME;
+        let adjusted = lwma_target(&samples, TARGET_BLOCK_TIME).unwrap();
+        assert!(adjusted < pow_target / PowTarget::from(100) * PowTarget::from(120));
+    }
+
+    #[test]
+    fn lwma_at_fork_points() {
+        let points = [LwmaPoint { shard_num: 0, number: 100 }, LwmaPoint { shard_num: 1, number: 50 }];
+        assert!(!lwma_enabled(&points, 0, 100));
+        assert!(lwma_enabled(&points, 0, 101));
+        assert!(lwma_enabled(&points, 1, 51));
+        assert!(!lwma_enabled(&points, 2, 1000));
+        assert!(!lwma_enabled(&[], 0, 1000));
+    }
+}
@@ -15,10 +15,21 @@
 // You should have received a copy of the GNU General Public License
 // along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
 
-/// (chain_spec_id, shard_num) => (block_number, block_hash, fork_id)
-pub const FORK_CONF : [((&str, u16), (u64, &str, u32)); 4] = [
-    (("mainnet", 0),  ( 143459, "0x473de2d82aebbefe0e96888d1fde3a7c206f6842aaaafc546fecac4a62f760e1", 1)),
-    (("mainnet", 1),  ( 143514, "0x55867d252b5c8e3eaa54b1ea865e7db035adcb3b6cda8b0fce0a4964bd8a0469", 1)),
-    (("mainnet", 2),  ( 143501, "0x89846e7c1221b5b1f6b031f621e47e7e51abc95f02ffeaaebeab354c4613c837", 1)),
-    (("mainnet", 3),  ( 141721, "0xee73f763ef9f55a7e85d118026ce62a26597a9c246c914e26a29309242e8c065", 1)),
+use yee_context::ForkPoint;
+
+/// Fork points of the built-in mainnet spec, other chains define theirs in the `forks` property of the chain spec
+/// (shard_num, block_number, block_hash, fork_id)
+pub const MAINNET_FORK_CONF : [(u16, u64, &str, u32); 4] = [
+    (0,  143459, "0x473de2d82aebbefe0e96888d1fde3a7c206f6842aaaafc546fecac4a62f760e1", 1),
+    (1,  143514, "0x55867d252b5c8e3eaa54b1ea865e7db035adcb3b6cda8b0fce0a4964bd8a0469", 1),
+    (2,  143501, "0x89846e7c1221b5b1f6b031f621e47e7e51abc95f02ffeaaebeab354c4613c837", 1),
+    (3,  141721, "0xee73f763ef9f55a7e85d118026ce62a26597a9c246c914e26a29309242e8c065", 1),
 ];
+
+/// Fork id of the block `number`, the one of the last fork point before it
+pub fn fork_id(fork_points: &[ForkPoint], shard_num: u16, number: u64) -> Option<u32> {
+    fork_points.iter()
+        .filter(|point| point.shard_num == shard_num && number > point.number)
+        .max_by_key(|point| point.number)
+        .map(|point| point.fork_id)
+}
@@ -68,12 +68,14 @@ use yee_context::Context;
 use yee_merkle::MultiLayerProof;
 use yee_runtime::AccountId;
 use yee_sharding::{ScaleOutPhaseDigestItem, ShardingDigestItem};
-use yee_sr_primitives::{OriginExtrinsic, RelayParams};
+use yee_sr_primitives::{RelayCallInfo, RelayParams, relay_registry_enabled, legacy_relay_calls};
+use yee_relay_primitives::RelayApi;
 
 use crate::{CompatibleDigestItem, PowSeal, ShardExtra, WorkProof};
 use crate::pow::{calc_pow_target, check_work_proof, gen_extrinsic_proof, EXTRA_VERSION, PowSealExtra};
 use crate::verifier::check_scale;
-use crate::fork::FORK_CONF;
+use crate::fork;
+use crate::algorithm::{algorithm_name, get_algorithm};
 
 #[derive(Clone)]
 pub struct DefaultJob<B: Block, AuthorityId: Decode + Encode + Clone> {
@@ -90,6 +92,8 @@ pub struct DefaultJob<B: Block, AuthorityId: Decode + Encode + Clone> {
     pub digest_item: PowSeal<B, AuthorityId>,
     /// extrinsic proof
     pub xts_proof: Vec<u8>,
+    /// Name of the pow algorithm of the block
+    pub algorithm: String,
 }
 
 impl<B: Block, AuthorityId: Decode + Encode + Clone> Job for DefaultJob<B, AuthorityId> {
@@ -125,7 +129,6 @@ pub struct DefaultJobManager<B, C, E, AccountId, AuthorityId, I, F> where
     shard_extra: ShardExtra<AccountId>,
     context: Context<B>,
     foreign_chains: Arc<RwLock<Option<ForeignChain<F>>>>,
-    chain_spec_id: String,
     phantom: PhantomData<B>,
 }
 
@@ -152,7 +155,6 @@ impl<B, C, E, AccountId, AuthorityId, I, F> DefaultJobManager<B, C, E, AccountId
         shard_extra: ShardExtra<AccountId>,
         context: Context<B>,
         foreign_chains: Arc<RwLock<Option<ForeignChain<F>>>>,
-        chain_spec_id: String,
     ) -> Self {
         Self {
             client,
@@ -164,7 +166,6 @@ impl<B, C, E, AccountId, AuthorityId, I, F> DefaultJobManager<B, C, E, AccountId
             context,
             foreign_chains,
             phantom: PhantomData,
-            chain_spec_id,
         }
     }
 }
@@ -174,7 +175,7 @@ impl<B, C, E, AccountId, AuthorityId, I, F> JobManager for DefaultJobManager<B,
           DigestItemFor<B>: super::CompatibleDigestItem<B, AuthorityId> + ShardingDigestItem<u16> + ScaleOutPhaseDigestItem<NumberFor<B>, u16>,
           C: ChainHead<B> + Send + Sync + 'static,
           C: HeaderBackend<B> + ProvideRuntimeApi,
-          <C as ProvideRuntimeApi>::Api: YeePOWApi<B>,
+          <C as ProvideRuntimeApi>::Api: YeePOWApi<B> + RelayApi<B>,
           E: Environment<B> + 'static + Send + Sync,
           <E as Environment<B>>::Proposer: Proposer<B>,
           <E as Environment<B>>::Error: Debug,
@@ -193,17 +194,25 @@ impl<B, C, E, AccountId, AuthorityId, I, F> JobManager for DefaultJobManager<B,
 
     fn get_job(&self) -> Box<dyn Future<Item=Self::Job, Error=consensus_common::Error> + Send> {
         let get_data = || {
-            let filter_extrinsic = Arc::new(FilterExtrinsic::<_, _, AccountId>::new(self.shard_extra.clone(), self.foreign_chains.clone()));
             let chain_head = self.client.best_block_header()
                 .map_err(to_common_error)?;
+            // runtimes before the relay registry fork don't provide the registry
+            let number = chain_head.number().as_() + 1;
+            let registry = if relay_registry_enabled(fork::fork_id(&self.context.fork_points, self.shard_extra.shard_num, number)) {
+                self.client.runtime_api().relay_registry(&generic::BlockId::hash(chain_head.hash()))
+                    .map_err(to_common_error)?
+            } else {
+                legacy_relay_calls()
+            };
+            let filter_extrinsic = Arc::new(FilterExtrinsic::<_, _, AccountId>::new(self.shard_extra.clone(), self.foreign_chains.clone(), registry.clone()));
             let proposer = self.env.init(&chain_head, &vec![], Some(filter_extrinsic))
                 .map_err(to_common_error)?;
             let inherent_data = self.inherent_data_providers.create_inherent_data()
                 .map_err(to_common_error)?;
-            Ok((proposer, inherent_data))
+            Ok((proposer, inherent_data, registry))
         };
-        let (proposer, inherent_data) = match get_data() {
-            Ok((p, d)) => (p, d),
+        let (proposer, inherent_data, registry) = match get_data() {
+            Ok((p, d, r)) => (p, d, r),
             Err(e) => {
                 warn!("failed to get proposer {:?}", e);
                 return Box::new(future::err(e));
@@ -215,30 +224,24 @@ impl<B, C, E, AccountId, AuthorityId, I, F> JobManager for DefaultJobManager<B,
         let context = self.context.clone();
 
         let shard_num = self.shard_extra.shard_num;
-        let chain_spec_id = self.chain_spec_id.clone();
 
         let build_job = move |(block, exe_result): (B, Vec<bool>)| {
             let (header, body) = block.deconstruct();
             let header_num = header.number().clone();
             let header_pre_hash = header.hash();
             let timestamp = timestamp_now()?;
-            let pow_target = calc_pow_target(client, &header, timestamp, &context)?;
+            let pow_target = calc_pow_target(client, &header, timestamp, &context, shard_num)?;
             let authority_id = authority_id;
             let work_proof = WorkProof::Unknown;
+            // find first fork_id
+            let fork_id = fork::fork_id(&context.fork_points, shard_num, header_num.as_());
+            debug!("fork_id: {:?}", fork_id);
+
             // generate proof
-            let (relay_proof, proof) = gen_extrinsic_proof::<B>(&header, &body, exe_result);
+            let (relay_proof, proof) = gen_extrinsic_proof::<B>(&header, &body, exe_result, &registry, fork_id);
             debug!("height: {:?}, proof's len={:?}", header_num, proof.len());
 
-            // find first fork_id
-            let fork_id = FORK_CONF.iter()
-                .filter_map(|((conf_chain_spec_id, conf_shard_num), (block_number, block_hash, fork_id))| {
-                    if conf_chain_spec_id == &chain_spec_id && conf_shard_num == &shard_num && header_num > As::sa(*block_number) {
-                        Some(*fork_id)
-                    }else{
-                        None
-                    }
-                }).last();
-            debug!("fork_id: {:?}", fork_id);
+            let algorithm = algorithm_name(&context.algorithm_points, shard_num, &context.genesis_pow_algorithm, header_num.as_());
 
             let extra_version = EXTRA_VERSION;
             let extra = PowSealExtra {
@@ -268,6 +271,7 @@ impl<B, C, E, AccountId, AuthorityId, I, F> JobManager for DefaultJobManager<B,
                 body,
                 digest_item: pow_seal,
                 xts_proof: proof,
+                algorithm,
             })
         };
 
@@ -280,7 +284,8 @@ impl<B, C, E, AccountId, AuthorityId, I, F> JobManager for DefaultJobManager<B,
 
         let check_job = move |job: Self::Job| -> Result<<Self::Job as Job>::Hash, consensus_common::Error>{
             let number = &job.header.number().clone();
-            let (post_digest, hash) = check_work_proof(&job.header, &job.digest_item)?;
+            let algorithm = get_algorithm(&job.algorithm)?;
+            let (post_digest, hash) = check_work_proof(&job.header, &job.digest_item, &*algorithm)?;
 
             check_scale::<B, AccountId>(&job.header, self.shard_extra.clone())?;
 
@@ -318,6 +323,7 @@ struct FilterExtrinsic<EX, F, AccountId> where
     shard_extra: ShardExtra<AccountId>,
     foreign_chains: Arc<RwLock<Option<ForeignChain<F>>>>,
     phantom_data: PhantomData<(EX, AccountId)>,
+    registry: Vec<RelayCallInfo>,
     cached_proof: CHashMap<<F::Block as Block>::Hash, MultiLayerProof>,
 }
 
@@ -343,11 +349,11 @@ impl<EX, F, AccountId> Filter<EX> for FilterExtrinsic<EX, F, AccountId> where
                 debug!("Filter extrinsic check proof (in cache): hash: {}, block_hash: {}, contains: {}", hash, block_hash, contains);
                 contains
             } else {
-                let origin = match OriginExtrinsic::<AccountId, u128>::decode(rt.relay_type(), rt.origin()) {
+                let source = match rt.source::<AccountId>(&self.registry) {
                     Some(v) => v,
                     None => return false,
                 };
-                let fs = yee_sharding_primitives::utils::shard_num_for(&origin.from(), tc as u16)
+                let fs = yee_sharding_primitives::utils::shard_num_for(&source, tc as u16)
                     .expect("Internal error. Get shard num failed.");
 
                 let mut contains = false;
@@ -399,11 +405,12 @@ impl<EX, F, AccountId> FilterExtrinsic<EX, F, AccountId> where
     F: ServiceFactory + Send + Sync,
     <F as ServiceFactory>::Configuration: Send + Sync,
 {
-    pub fn new(shard_extra: ShardExtra<AccountId>, foreign_chains: Arc<RwLock<Option<ForeignChain<F>>>>) -> Self {
+    pub fn new(shard_extra: ShardExtra<AccountId>, foreign_chains: Arc<RwLock<Option<ForeignChain<F>>>>, registry: Vec<RelayCallInfo>) -> Self {
         Self {
             shard_extra,
             foreign_chains,
             phantom_data: PhantomData,
+            registry,
             cached_proof: CHashMap::with_capacity(32),
         }
     }
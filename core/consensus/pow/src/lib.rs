@@ -27,6 +27,7 @@ use {
     client::{
         ChainHead,
         blockchain::HeaderBackend,
+        block_builder::api::BlockBuilder as BlockBuilderApi,
         BlockBody,
         BlockchainEvents,
     },
@@ -56,12 +57,14 @@ use {
 };
 use {
     pow_primitives::YeePOWApi,
+    yee_relay_primitives::RelayApi,
 };
 
 pub use digest::CompatibleDigestItem;
 pub use pow::{PowSeal, WorkProof, ProofNonce, ProofMulti,
               MiningAlgorithm, MiningHash, OriginalMerkleProof, CompactMerkleProof};
 pub use job::{JobManager, DefaultJobManager, DefaultJob};
+pub use algorithm::{PowAlgorithm, DEFAULT_POW_ALGORITHM, register_algorithm, get_algorithm, select_algorithm};
 use yee_sharding::{ShardingDigestItem, ScaleOutPhaseDigestItem};
 use yee_srml_pow::RewardCondition;
 use yee_sharding_primitives::ScaleOut;
@@ -77,6 +80,8 @@ mod worker;
 mod big_array;
 mod extra_data;
 pub mod fork;
+pub mod algorithm;
+pub mod difficulty;
 // use big_array::BigArray;
 pub use extra_data::ExtraData;
 
@@ -87,7 +92,6 @@ pub struct Params<AccountId, B> where
     pub mine: bool,
     pub shard_extra: ShardExtra<AccountId>,
     pub context: Context<B>,
-    pub chain_spec_id: String,
 }
 
 pub fn start_pow<F, B, P, C, I, E, AccountId, SO, OnExit>(
@@ -106,7 +110,7 @@ pub fn start_pow<F, B, P, C, I, E, AccountId, SO, OnExit>(
     P: Pair + 'static,
     <P as Pair>::Public: Clone + Debug + Decode + Encode + Send + Sync,
     C: ChainHead<B> + HeaderBackend<B> + ProvideRuntimeApi + 'static,
-    <C as ProvideRuntimeApi>::Api: YeePOWApi<B>,
+    <C as ProvideRuntimeApi>::Api: YeePOWApi<B> + RelayApi<B>,
     I: BlockBuilder<B> + Send + Sync + 'static,
     E: Environment<B> + Send + Sync + 'static,
     <E as Environment<B>>::Error: Debug + Send,
@@ -130,7 +134,6 @@ pub fn start_pow<F, B, P, C, I, E, AccountId, SO, OnExit>(
         params.shard_extra.clone(),
         params.context.clone(),
         foreign_chains.clone(),
-        params.chain_spec_id.clone(),
     ));
 
     let mut reg_lock = job_manager.write();
@@ -184,7 +187,6 @@ pub fn import_queue<F, C, AccountId, AuthorityId>(
     foreign_chains: Arc<RwLock<Option<ForeignChain<F>>>>,
     shard_extra: ShardExtra<AccountId>,
     context: Context<F::Block>,
-    chain_spec_id: String,
     is_full: bool,
 ) -> Result<PowImportQueue<F::Block>, consensus_common::Error> where
     H256: From<<F::Block as Block>::Hash>,
@@ -196,7 +198,8 @@ pub fn import_queue<F, C, AccountId, AuthorityId>(
     C: BlockBody<<F as ServiceFactory>::Block>,
     C: BlockchainEvents<<F as ServiceFactory>::Block>,
     C: ChainHead<<F as ServiceFactory>::Block>,
-    <C as ProvideRuntimeApi>::Api: ShardingAPI<<F as ServiceFactory>::Block> + YeePOWApi<<F as ServiceFactory>::Block>,
+    <C as ProvideRuntimeApi>::Api: ShardingAPI<<F as ServiceFactory>::Block> + YeePOWApi<<F as ServiceFactory>::Block> + RelayApi<<F as ServiceFactory>::Block>
+        + BlockBuilderApi<<F as ServiceFactory>::Block>,
     AccountId: Codec + Send + Sync + Clone + Default + 'static,
     AuthorityId: Decode + Encode + Clone + Send + Sync + 'static,
     substrate_service::config::Configuration<<F as ServiceFactory>::Configuration, <F as ServiceFactory>::Genesis> : Clone,
@@ -216,7 +219,6 @@ pub fn import_queue<F, C, AccountId, AuthorityId>(
             phantom: PhantomData,
             shard_extra,
             context,
-            chain_spec_id,
         }
     );
     Ok(BasicQueue::<F::Block>::new(verifier, block_import, justification_import, network_id))
@@ -39,7 +39,7 @@ use std::hash::Hasher;
 use merkle_light::hash::Algorithm;
 use merkle_light::proof::Proof;
 use merkle_light::merkle::MerkleTree;
-use yee_runtime::{AccountId, Call, BalancesCall, AssetsCall, UncheckedExtrinsic};
+use yee_runtime::AccountId;
 use yee_sharding_primitives::utils::shard_num_for;
 use primitives::{Blake2Hasher, H256};
 use hash_db::Hasher as BlakeHasher;
@@ -47,8 +47,9 @@ use std::iter::FromIterator;
 use yee_merkle::{ProofHash, ProofAlgorithm, MultiLayerProof};
 use ansi_term::Colour;
 use yee_context::Context;
-use yee_sr_primitives::{RelayTypes, OriginExtrinsic};
-use crate::fork::FORK_CONF;
+use yee_sr_primitives::{RelayCallInfo, RelayParams, OriginExtrinsic, relay_registry_enabled};
+use crate::algorithm::PowAlgorithm;
+use crate::difficulty::{lwma_enabled, lwma_target, LWMA_WINDOW};
 
 /// Max length in bytes for pow extra data
 pub const MAX_EXTRA_DATA_LENGTH: usize = 32;
@@ -187,10 +188,10 @@ pub struct ProofMulti<B: Block> {
     pub merkle_proof: Vec<B::Hash>,
 }
 
-/// Check proof
+/// Check proof, the work being hashed by `algorithm`
 ///
 /// Returns (post_digest, hash)
-pub fn check_work_proof<B, AuthorityId>(header: &B::Header, seal: &PowSeal<B, AuthorityId>) -> Result<(DigestItemFor<B>, B::Hash), String> where
+pub fn check_work_proof<B, AuthorityId>(header: &B::Header, seal: &PowSeal<B, AuthorityId>, algorithm: &dyn PowAlgorithm) -> Result<(DigestItemFor<B>, B::Hash), String> where
     B: Block,
     AuthorityId: Decode + Encode + Clone,
     DigestFor<B>: Digest,
@@ -210,7 +211,7 @@ pub fn check_work_proof<B, AuthorityId>(header: &B::Header, seal: &PowSeal<B, Au
 
             let hash = work_header.hash();
 
-            let proof_pow_target = PowTarget::from(hash.as_ref());
+            let proof_pow_target = PowTarget::from(algorithm.hash(&work_header.encode()).as_ref());
 
             if proof_pow_target > seal.pow_target {
                 return Err(format!("Nonce proof: pow target not satisified, need {}, got {}", seal.pow_target, proof_pow_target));
@@ -283,7 +284,7 @@ pub fn check_work_proof<B, AuthorityId>(header: &B::Header, seal: &PowSeal<B, Au
 
             //diff validate
             let source = (proof_multi.merkle_root.clone(), proof_multi.nonce, proof_multi.extra_data.clone());
-            let source_hash = <B::Header as Header>::Hashing::hash_of(&source);
+            let source_hash = algorithm.hash(&source.encode());
             let source_pow_target = PowTarget::from(source_hash.as_ref());
 
             if source_pow_target > seal.pow_target {
@@ -310,7 +311,8 @@ fn to_common_error<E: Debug>(e: E) -> consensus_common::Error {
 }
 
 /// calculate pow target
-pub fn calc_pow_target<B, C, AuthorityId>(client: Arc<C>, header: &<B as Block>::Header, timestamp: u64, context: &Context<B>)
+pub fn calc_pow_target<B, C, AuthorityId>(client: Arc<C>, header: &<B as Block>::Header, timestamp: u64, context: &Context<B>,
+                                          shard_num: u16)
                                           -> Result<PowTarget, consensus_common::Error> where
     B: Block,
     NumberFor<B>: SimpleArithmetic,
@@ -327,6 +329,9 @@ pub fn calc_pow_target<B, C, AuthorityId>(client: Arc<C>, header: &<B as Block>:
         .expect("parent block must exist for sealer; qed")
         .expect("parent block must exist for sealer; qed");
     let one = <NumberFor<B> as As<u64>>::sa(1u64);
+    if lwma_enabled(&context.lwma_points, shard_num, next_num.as_()) {
+        return calc_lwma_pow_target::<B, C, AuthorityId>(client, curr_header, context);
+    }
     // not on adjustment, reuse parent pow target
     if next_num == one {
         return Ok(genesis_pow_target);
@@ -362,7 +367,7 @@ pub fn calc_pow_target<B, C, AuthorityId>(client: Arc<C>, header: &<B as Block>:
 
     let target_block_time = context.genesis_target_block_time;
     if timestamp < last_time {
-        panic!(format!("timestamp:{} < last_time: {}", timestamp, last_time))
+        return Err(to_common_error(format!("timestamp:{} < last_time: {}", timestamp, last_time)));
     }
     let time_gap = timestamp - last_time;
     info!("pow target adjustment: gap: {}, time: {}", block_gap, time_gap);
@@ -374,8 +379,42 @@ pub fn calc_pow_target<B, C, AuthorityId>(client: Arc<C>, header: &<B as Block>:
     Ok(new_pow_target)
 }
 
+/// Pow target adjusted per block by the solve times of the last blocks up to the parent
+fn calc_lwma_pow_target<B, C, AuthorityId>(client: Arc<C>, parent_header: <B as Block>::Header, context: &Context<B>)
+                                          -> Result<PowTarget, consensus_common::Error> where
+    B: Block,
+    DigestFor<B>: Digest,
+    DigestItemFor<B>: super::CompatibleDigestItem<B, AuthorityId>,
+    C: HeaderBackend<B>,
+    AuthorityId: Encode + Decode + Clone,
+{
+    let mut samples = Vec::with_capacity(LWMA_WINDOW + 1);
+    let mut header = parent_header;
+    loop {
+        // the genesis block has no seal
+        let seal = match header.digest().logs().iter().rev()
+            .filter_map(CompatibleDigestItem::as_pow_seal).next() {
+            Some(seal) => seal,
+            None => break,
+        };
+        samples.push((seal.timestamp, seal.pow_target));
+        if samples.len() == LWMA_WINDOW + 1 {
+            break;
+        }
+        header = client.header(BlockId::hash(*header.parent_hash())).map_err(to_common_error)?
+            .ok_or_else(|| to_common_error("parent block must exist for sealer"))?;
+    }
+    samples.reverse();
+
+    let parent_pow_target = samples.last().map(|(_, pow_target)| *pow_target).unwrap_or(context.genesis_pow_target);
+    let pow_target = lwma_target(&samples, context.genesis_target_block_time * 1000).unwrap_or(parent_pow_target);
+    debug!("lwma pow target: parent: {:#x}, new: {:#x}", parent_pow_target, pow_target);
+
+    Ok(pow_target)
+}
+
 /// Gen extrinsic proof for foreign chain.
-pub fn gen_extrinsic_proof<B>(header: &B::Header, body: &[B::Extrinsic], exe_result: Vec<bool>) -> (H256, ExtrinsicProof)
+pub fn gen_extrinsic_proof<B>(header: &B::Header, body: &[B::Extrinsic], exe_result: Vec<bool>, registry: &[RelayCallInfo], fork_id: Option<u32>) -> (H256, ExtrinsicProof)
     where
         B: Block,
         <<<B as Block>::Header as Header>::Digest as Digest>::Item: yee_sharding::ShardingDigestItem<u16>,
@@ -390,24 +429,19 @@ pub fn gen_extrinsic_proof<B>(header: &B::Header, body: &[B::Extrinsic], exe_res
     let mut extrinsic_shard: HashMap<u16, Vec<H256>> = HashMap::new();
     for (i, extrinsic) in body.iter().enumerate() {
         let ex_bytes = extrinsic.encode();
-        let mut bytes = ex_bytes.as_slice();
-        if let Some(ex) = Decode::decode(&mut bytes) {
-            let ex: UncheckedExtrinsic = ex;
-            if ex.signature.is_some() {
-                let hash = Blake2Hasher::hash(ex_bytes.as_slice());
-                let to = match ex.function {
-                    Call::Balances(BalancesCall::transfer(to, _)) => Some(to.clone()),
-                    Call::Assets(AssetsCall::transfer(_, _, to, _)) => Some(to.clone()),
-                    _ => None
-                };
-                to.map(|to| {
-                    if let Some(num) = shard_num_for(&to, shard_count) {
-                        if num != shard_num && exe_result[i] {
-                            let v = extrinsic_shard.entry(num).or_insert(vec![]);
-                            v.push(hash);
-                        }
-                    }
-                });
+        let hash = Blake2Hasher::hash(ex_bytes.as_slice());
+        let dest = if let Some(ex) = OriginExtrinsic::<AccountId, u128>::decode(registry, ex_bytes.clone()) {
+            Some(ex.to()).filter(|_| exe_result[i])
+        } else {
+            // proved to its origin shard, which gets a receipt
+            RelayParams::<H256>::decode(ex_bytes)
+                .filter(|rtx| relay_registry_enabled(fork_id) && rtx.relay_type().has_receipt(exe_result[i]))
+                .and_then(|rtx| rtx.source::<AccountId>(registry))
+        };
+        if let Some(num) = dest.and_then(|dest| shard_num_for(&dest, shard_count)) {
+            if num != shard_num {
+                let v = extrinsic_shard.entry(num).or_insert(vec![]);
+                v.push(hash);
             }
         }
     }
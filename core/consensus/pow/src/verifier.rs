@@ -26,12 +26,13 @@ use parking_lot::RwLock;
 use primitives::H256;
 
 use {
-    std::{marker::PhantomData, sync::Arc},
+    std::{marker::PhantomData, sync::Arc, time::{SystemTime, UNIX_EPOCH}},
 };
 use {
     client::{
         self,
         BlockBody,
+        block_builder::api::BlockBuilder as BlockBuilderApi,
         blockchain::HeaderBackend,
         BlockchainEvents,
         ChainHead,
@@ -67,13 +68,18 @@ use yee_merkle::{MultiLayerProof, ProofAlgorithm, ProofHash};
 use yee_runtime::Hash;
 use yee_sharding::{ScaleOutPhase, ScaleOutPhaseDigestItem, ShardingDigestItem};
 use yee_sharding_primitives::utils::shard_num_for;
-use yee_sr_primitives::{OriginExtrinsic, RelayParams};
+use yee_sr_primitives::{RelayParams, relay_registry_enabled, legacy_relay_calls};
+use yee_relay_primitives::RelayApi;
 
 use crate::pow::{calc_pow_target, check_work_proof, gen_extrinsic_proof, PowSeal};
 use crate::ShardExtra;
 
 use super::CompatibleDigestItem;
-use crate::fork::FORK_CONF;
+use crate::fork;
+use crate::algorithm::select_algorithm;
+
+/// Max time (ms) a block timestamp may be ahead of the local time
+pub const MAX_FUTURE_BLOCK_TIME: u64 = 120_000;
 
 /// Verifier for POW blocks.
 pub struct PowVerifier<F: ServiceFactory, C, AccountId, AuthorityId> {
@@ -83,7 +89,6 @@ pub struct PowVerifier<F: ServiceFactory, C, AccountId, AuthorityId> {
     pub phantom: PhantomData<AuthorityId>,
     pub shard_extra: ShardExtra<AccountId>,
     pub context: Context<F::Block>,
-    pub chain_spec_id: String,
 }
 
 #[forbid(deprecated)]
@@ -98,7 +103,8 @@ impl<F, C, AccountId, AuthorityId> Verifier<F::Block> for PowVerifier<F, C, Acco
     C: BlockBody<<F as ServiceFactory>::Block>,
     C: BlockchainEvents<<F as ServiceFactory>::Block>,
     C: ChainHead<<F as ServiceFactory>::Block>,
-    <C as ProvideRuntimeApi>::Api: ShardingAPI<<F as ServiceFactory>::Block> + YeePOWApi<<F as ServiceFactory>::Block>,
+    <C as ProvideRuntimeApi>::Api: ShardingAPI<<F as ServiceFactory>::Block> + YeePOWApi<<F as ServiceFactory>::Block> + RelayApi<<F as ServiceFactory>::Block>
+        + BlockBuilderApi<<F as ServiceFactory>::Block>,
     H256: From<<F::Block as Block>::Hash>,
     substrate_service::config::Configuration<<F as ServiceFactory>::Configuration, <F as ServiceFactory>::Genesis>: Clone,
     <<<F as ServiceFactory>::Block as Block>::Header as Header>::Number: From<u64>,
@@ -120,11 +126,12 @@ impl<F, C, AccountId, AuthorityId> Verifier<F::Block> for PowVerifier<F, C, Acco
                 error!("{}: {}", Colour::Red.paint("check header failed"), e);
                 e
             })?;
-        let proof_root = seal.as_pow_seal().ok_or_else(|| {
+        let pow_seal = seal.as_pow_seal().ok_or_else(|| {
             let e = format!("Header {:?} not sealed", hash);
             error!("{}: {}", Colour::Red.paint("get proof root failed"), e);
             e
-        })?.relay_proof;
+        })?;
+        let (proof_root, fork_id) = (pow_seal.relay_proof, pow_seal.extra.fork_id);
         // check proof with header's proof_root
         self.check_relay_merkle_proof(proof.clone(), proof_root)
             .map_err(|e| {
@@ -134,7 +141,7 @@ impl<F, C, AccountId, AuthorityId> Verifier<F::Block> for PowVerifier<F, C, Acco
 
         // let mut res_proof = proof;
         // check body if not none
-        self.check_body(&body, &pre_header, proof_root).map_err(|e| {
+        self.check_body(&body, &pre_header, proof_root, fork_id).map_err(|e| {
             error!("{}: {}", Colour::Red.paint("check body failed"), e);
             e
         })?;
@@ -164,22 +171,49 @@ impl<F, C, AccountId, AuthorityId> PowVerifier<F, C, AccountId, AuthorityId> whe
     C: BlockBody<<F as ServiceFactory>::Block>,
     C: BlockchainEvents<<F as ServiceFactory>::Block>,
     C: ChainHead<<F as ServiceFactory>::Block>,
+    C: ProvideRuntimeApi,
+    <C as ProvideRuntimeApi>::Api: RelayApi<<F as ServiceFactory>::Block> + BlockBuilderApi<<F as ServiceFactory>::Block>,
     H256: From<<F::Block as Block>::Hash>,
     substrate_service::config::Configuration<<F as ServiceFactory>::Configuration, <F as ServiceFactory>::Genesis>: Clone,
     <<<F as ServiceFactory>::Block as Block>::Header as Header>::Number: From<u64>,
 {
     /// check body
-    fn check_body(&self, body: &Option<Vec<<F::Block as Block>::Extrinsic>>, pre_header: &<F::Block as Block>::Header, _proof_root: H256) -> Result<(), String> {
+    fn check_body(&self, body: &Option<Vec<<F::Block as Block>::Extrinsic>>, pre_header: &<F::Block as Block>::Header, _proof_root: H256, fork_id: Option<u32>) -> Result<(), String> {
         match body.as_ref() {
             Some(exs) => {
                 // check relay extrinsic.
-                self.check_relay_transfer(pre_header.digest().logs(), exs)?;
+                self.check_relay_transfer(pre_header.parent_hash(), pre_header.digest().logs(), exs, fork_id)?;
+                self.check_inherents(pre_header, exs)?;
             }
             None => {}
         }
         Ok(())
     }
 
+    /// check the inherents against the parent state, only the sharding inherent rejects the block:
+    /// the other modules check the local configuration or are checked by the header already
+    fn check_inherents(&self, pre_header: &<F::Block as Block>::Header, exs: &[<F::Block as Block>::Extrinsic]) -> Result<(), String> {
+        let inherent_data = match self.inherent_data_providers.create_inherent_data() {
+            Ok(data) => data,
+            Err(e) => {
+                warn!("Create inherent data failed, skip checking inherents: {:?}", e);
+                return Ok(());
+            }
+        };
+        let block = <F::Block as Block>::new(pre_header.clone(), exs.to_vec());
+        let result = self.client.runtime_api()
+            .check_inherents(&generic::BlockId::hash(*pre_header.parent_hash()), block, inherent_data)
+            .map_err(|e| format!("Check inherents failed: {:?}", e))?;
+        for (identifier, error) in result.into_errors() {
+            let error = self.inherent_data_providers.error_to_string(&identifier, &error);
+            if identifier == yee_srml_sharding::INHERENT_IDENTIFIER {
+                return Err(format!("Sharding inherent rejected: {}", error));
+            }
+            debug!("Inherent {:?} not checked: {}", identifier, error);
+        }
+        Ok(())
+    }
+
     /// check relay transfer merkle proof
     fn check_relay_merkle_proof(&self, proof: Option<Proof>, p_h: H256) -> Result<(), String> {
         if let Some(proof) = proof.as_ref() {
@@ -209,7 +243,7 @@ impl<F, C, AccountId, AuthorityId> PowVerifier<F, C, AccountId, AuthorityId> whe
     }
 
     /// check relay transfer
-    fn check_relay_transfer(&self, logs: &[DigestItemFor<F::Block>], exs: &[<F::Block as Block>::Extrinsic]) -> Result<(), String> {
+    fn check_relay_transfer(&self, parent_hash: &<F::Block as Block>::Hash, logs: &[DigestItemFor<F::Block>], exs: &[<F::Block as Block>::Extrinsic], fork_id: Option<u32>) -> Result<(), String> {
         // let err_str = "Block contains invalid extrinsic.";
         let shard_info: Option<(u16, u16)> = logs.iter().rev()
             .filter_map(ShardingDigestItem::as_sharding_info)
@@ -219,6 +253,12 @@ impl<F, C, AccountId, AuthorityId> PowVerifier<F, C, AccountId, AuthorityId> whe
             None => { return Err("Can't get shard info in header".to_string()); }
         };
 
+        let registry = if relay_registry_enabled(fork_id) {
+            self.client.runtime_api().relay_registry(&generic::BlockId::hash(*parent_hash))
+                .map_err(|e| format!("Get relay registry failed: {:?}", e))?
+        } else {
+            legacy_relay_calls()
+        };
         let mut cached_proof = HashMap::<<F::Block as Block>::Hash, MultiLayerProof>::with_capacity(32);
 
         for tx in exs {
@@ -237,11 +277,11 @@ impl<F, C, AccountId, AuthorityId> PowVerifier<F, C, AccountId, AuthorityId> whe
                         contains
                     },
                     Entry::Vacant(entry) => {
-                        let origin = match OriginExtrinsic::<AccountId, u128>::decode(rt.relay_type(), rt.origin()){
+                        let source = match rt.source::<AccountId>(&registry) {
                             Some(v) => v,
                             None => return Err("Decode origin extrinsic failed".to_string())
                         };
-                        let fs = yee_sharding_primitives::utils::shard_num_for(&origin.from(), tc as u16)
+                        let fs = yee_sharding_primitives::utils::shard_num_for(&source, tc as u16)
                             .expect("Internal error. Get shard num failed.");
 
                         let mut contains = false;
@@ -297,13 +337,17 @@ impl<F, C, AccountId, AuthorityId> PowVerifier<F, C, AccountId, AuthorityId> whe
 
         self.check_fork_id(&header, &seal)?;
 
+        self.check_timestamp(&seal)?;
+
         self.check_pow_target(&header, &seal)?;
 
         self.check_shard_info(&header)?;
 
         // self.check_other_logs(&header)?;
 
-        check_work_proof(&header, &seal)?;
+        let algorithm = select_algorithm(&self.context.algorithm_points, self.shard_extra.shard_num,
+                                         &self.context.genesis_pow_algorithm, header.number().as_())?;
+        check_work_proof(&header, &seal, &*algorithm)?;
 
         Ok((header, digest_item))
     }
@@ -312,19 +356,11 @@ impl<F, C, AccountId, AuthorityId> PowVerifier<F, C, AccountId, AuthorityId> whe
     fn check_fork_id(&self, header: &<F::Block as Block>::Header, seal: &PowSeal<F::Block, AuthorityId>) -> Result<(), String> {
 
         let header_num = header.number().clone();
-        let chain_spec_id = self.chain_spec_id.clone();
         let shard_num = self.shard_extra.shard_num;
 
         // check fork_id
         let fork_id = seal.extra.fork_id;
-        let expect_fork_id =  FORK_CONF.iter()
-            .filter_map(|((conf_chain_spec_id, conf_shard_num), (block_number, block_hash, fork_id))| {
-                if conf_chain_spec_id == &chain_spec_id && conf_shard_num == &shard_num && header_num > As::sa(*block_number) {
-                    Some(*fork_id)
-                }else{
-                    None
-                }
-            }).last();
+        let expect_fork_id = fork::fork_id(&self.context.fork_points, shard_num, header_num.as_());
 
         debug!("Verify fork_id: {:?}, expected: {:?}", fork_id, expect_fork_id);
 
@@ -335,9 +371,20 @@ impl<F, C, AccountId, AuthorityId> PowVerifier<F, C, AccountId, AuthorityId> whe
         }
     }
 
+    /// check the timestamp in seal is not too far ahead, which would ease the pow target of the next blocks
+    fn check_timestamp(&self, seal: &PowSeal<F::Block, AuthorityId>) -> Result<(), String> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map_err(|e| format!("{:?}", e))?.as_millis() as u64;
+        if seal.timestamp > now + MAX_FUTURE_BLOCK_TIME {
+            return Err(format!("check_timestamp failed, timestamp {} ahead of local time {}", seal.timestamp, now));
+        }
+        Ok(())
+    }
+
     /// check pow_target in seal
     fn check_pow_target(&self, header: &<F::Block as Block>::Header, seal: &PowSeal<F::Block, AuthorityId>) -> Result<(), String> {
-        let pow_target = calc_pow_target(self.client.clone(), header, seal.timestamp, &self.context).map_err(|e| format!("{:?}", e))?;
+        let pow_target = calc_pow_target(self.client.clone(), header, seal.timestamp, &self.context,
+                                         self.shard_extra.shard_num).map_err(|e| format!("{:?}", e))?;
         if seal.pow_target != pow_target {
             return Err("check_pow_target failed, pow target not match.".to_string());
         }
@@ -49,6 +49,7 @@ use super::{
 };
 use crate::job::{JobManager, DefaultJob};
 use crate::pow::check_work_proof;
+use crate::algorithm::get_algorithm;
 use yee_sharding::{ShardingDigestItem, ScaleOutPhaseDigestItem};
 use crate::ShardExtra;
 use crate::verifier::check_scale;
@@ -143,20 +144,21 @@ impl<B, I, JM, AccountId, AuthorityId> PowWorker<JM> for DefaultWorker<B, I, JM,
             let digest_item = job.digest_item;
             let pow_target = digest_item.pow_target;
             let xts_proof = job.xts_proof.clone();
+            let algorithm = get_algorithm(&job.algorithm)?;
 
-            info!("block template {} @ {:?}, pow target: {:#x}", header_num, header_pre_hash, pow_target);
+            info!("block template {} @ {:?}, pow target: {:#x}, algorithm: {}", header_num, header_pre_hash, pow_target, algorithm.name());
 
             // TODO: remove hardcoded
             const PREFIX: &str = "yeeroot-";
 
-            for i in 0_u64..iter {
+            for i in 0_u64..iter.min(algorithm.nonce_window()) {
 
                 let shard_extra = shard_extra.clone();
                 let proof = WorkProof::Nonce(ProofNonce::get_with_prefix_len(PREFIX, 12, i));
                 let mut seal = digest_item.clone();
                 seal.work_proof = proof;
 
-                if let Ok((post_digest, hash)) = check_work_proof(&header, &seal){
+                if let Ok((post_digest, hash)) = check_work_proof(&header, &seal, &*algorithm){
 
                     check_scale::<B, AccountId>(&header, shard_extra)?;
 
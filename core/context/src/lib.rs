@@ -16,6 +16,7 @@
 // along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
 
 use pow_primitives::PowTarget;
+use primitives::H256;
 use {
 	runtime_primitives::{
 		traits::{NumberFor, Block as BlockT},
@@ -31,4 +32,49 @@ pub struct Context<Block> where
 	pub genesis_target_block_time: u64,
 	pub genesis_shard_count: u16,
 	pub genesis_scale_out_observe_blocks: NumberFor<Block>,
+	/// Pow algorithm named by the chain spec
+	pub genesis_pow_algorithm: String,
+	/// Fork points of the chain spec
+	pub fork_points: Vec<ForkPoint>,
+	/// CRFG skip points of the chain spec
+	pub skip_points: Vec<SkipPoint>,
+	/// Pow algorithm switches of the chain spec
+	pub algorithm_points: Vec<AlgorithmPoint>,
+	/// Per block pow target adjustment points of the chain spec
+	pub lwma_points: Vec<LwmaPoint>,
+}
+
+/// The blocks of the shard after `number` are sealed with `fork_id`,
+/// the block `number` being expected to be `hash`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ForkPoint {
+	pub shard_num: u16,
+	pub number: u64,
+	pub hash: H256,
+	pub fork_id: u32,
+}
+
+/// The CRFG finalization of the shard skips to `skip_number` at the block `number`,
+/// the block being expected to be `hash`
+#[derive(Clone, Debug, PartialEq)]
+pub struct SkipPoint {
+	pub shard_num: u16,
+	pub number: u64,
+	pub hash: H256,
+	pub skip_number: u64,
+}
+
+/// The blocks of the shard after `number` are sealed with the pow `algorithm`
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlgorithmPoint {
+	pub shard_num: u16,
+	pub number: u64,
+	pub algorithm: String,
+}
+
+/// The pow target of the blocks of the shard after `number` is adjusted per block
+#[derive(Clone, Debug, PartialEq)]
+pub struct LwmaPoint {
+	pub shard_num: u16,
+	pub number: u64,
 }
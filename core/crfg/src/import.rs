@@ -17,7 +17,7 @@
 use std::{sync::Arc, collections::HashMap};
 
 use log::{debug, trace, info, warn};
-use parity_codec::{Encode, Decode};
+use parity_codec::Encode;
 use futures::sync::mpsc;
 use parking_lot::{RwLockWriteGuard, RwLock};
 
@@ -50,8 +50,7 @@ use crate::digest::{CrfgChangeDigestItem, CrfgForceChangeDigestItem, CrfgSkipDig
 use runtime_primitives::traits::Digest;
 use std::time;
 use std::cell::Cell;
-use yee_consensus_pow::fork::FORK_CONF;
-use crate::skip::SKIP_CONF;
+use yee_context::{ForkPoint, SkipPoint};
 use std::ops::Add;
 use std::fmt::Debug;
 
@@ -79,7 +78,8 @@ pub struct CrfgBlockImport<B, E, Block: BlockT<Hash=H256>, RA, PRA> {
 	import_until: Option<NumberFor<Block>>,
 	import_leading: Option<NumberFor<Block>>,
 	pending_skip: SharedPendingSkip<Block::Hash, NumberFor<Block>>,
-	chain_spec_id: String,
+	fork_points: Vec<ForkPoint>,
+	skip_points: Vec<SkipPoint>,
 	shard_num: u16,
 }
 
@@ -335,14 +335,9 @@ impl<B, E, Block: BlockT<Hash=H256>, RA, PRA> CrfgBlockImport<B, E, Block, RA, P
 			},
 			None => {
 				let block_number = header.number().clone();
-				let skip = SKIP_CONF.get(&(&self.chain_spec_id, self.shard_num, As::as_(block_number)))
-					.map(|(block_hash, skip_number)| {
-
-						let block_hash = hex::decode(block_hash.trim_start_matches("0x")).expect("qed");
-						let block_hash = Decode::decode(&mut &block_hash[..]).expect("qed");
-
-						(block_hash, block_number, As::sa(*skip_number))
-					});
+				let skip = self.skip_points.iter()
+					.find(|point| point.shard_num == self.shard_num && point.number == As::as_(block_number))
+					.map(|point| (point.hash, block_number, As::sa(point.skip_number)));
 				skip
 			}
 		};
@@ -565,24 +560,16 @@ impl<B, E, Block: BlockT<Hash=H256>, RA, PRA> BlockImport<Block>
 		let pending_changes = self.make_authorities_changes(&mut block, hash)?;
 
 		// check fork
-		let chain_spec_id  = self.chain_spec_id.clone();
 		let shard_num = self.shard_num;
-		let maybe_fork = FORK_CONF.iter()
-			.find_map(|((conf_chain_spec_id, conf_shard_num), (block_number, block_hash, fork_id))| {
-				let fork_next_number : NumberFor<Block>  = As::sa(*block_number + 1);
-				if conf_chain_spec_id == &chain_spec_id
-					&& conf_shard_num == &shard_num
-					&& number == fork_next_number {
-					let block_hash = hex::decode(block_hash.trim_start_matches("0x")).expect("");
-					let block_number : NumberFor<Block>  = As::sa(*block_number);
-					Some((block_number, block_hash))
-				}else{
-					None
-				}
+		let maybe_fork = self.fork_points.iter()
+			.find(|point| point.shard_num == shard_num && number == As::sa(point.number + 1))
+			.map(|point| {
+				let block_number : NumberFor<Block>  = As::sa(point.number);
+				(block_number, point.hash)
 			});
 		debug!(target: "afg", "Maybe fork: {:?}", maybe_fork);
 		if let Some((fork_block_number, fork_block_hash)) = &maybe_fork {
-			if parent_hash.as_ref() != fork_block_hash.as_slice() {
+			if &parent_hash != fork_block_hash {
 				return Err(ConsensusErrorKind::ClientImport("fork hash not match".to_string()).into());
 			}
 		}
@@ -625,7 +612,7 @@ impl<B, E, Block: BlockT<Hash=H256>, RA, PRA> BlockImport<Block>
 
 		// fork
 		if let Some((fork_block_number, fork_block_hash)) = maybe_fork{
-			if parent_hash.as_ref() != fork_block_hash.as_slice() {
+			if parent_hash != fork_block_hash {
 				return Err(ConsensusErrorKind::ClientImport("fork hash not match".to_string()).into());
 			}
 			// skip from finalized_number + 1 to fork_block_number
@@ -782,7 +769,8 @@ impl<B, E, Block: BlockT<Hash=H256>, RA, PRA> CrfgBlockImport<B, E, Block, RA, P
 		import_until: Option<NumberFor<Block>>,
 		import_leading: Option<NumberFor<Block>>,
 		pending_skip: SharedPendingSkip<Block::Hash, NumberFor<Block>>,
-		chain_spec_id: String,
+		fork_points: Vec<ForkPoint>,
+		skip_points: Vec<SkipPoint>,
 		shard_num: u16,
 		crfg_state_providers: Arc<RwLock<HashMap<u16, Arc<dyn CrfgStateProvider<Block::Hash, NumberFor<Block>>>>>>,
 	) -> CrfgBlockImport<B, E, Block, RA, PRA> {
@@ -804,7 +792,8 @@ impl<B, E, Block: BlockT<Hash=H256>, RA, PRA> CrfgBlockImport<B, E, Block, RA, P
 			import_until,
 			import_leading,
 			pending_skip,
-			chain_spec_id,
+			fork_points,
+			skip_points,
 			shard_num,
 		}
 	}
@@ -101,7 +101,7 @@ mod justification;
 mod until_imported;
 mod digest;
 pub use digest::CrfgChangeDigestItem;
-mod skip;
+pub mod skip;
 
 #[cfg(feature="service-integration")]
 mod service_integration;
@@ -118,6 +118,7 @@ use until_imported::UntilCommitBlocksImported;
 use ed25519::{Public as AuthorityId, Signature as AuthoritySignature};
 use parking_lot::RwLock;
 use crate::consensus_changes::SharedPendingSkip;
+use yee_context::{ForkPoint, SkipPoint};
 
 #[cfg(test)]
 mod tests;
@@ -668,7 +669,8 @@ pub fn block_import<B, E, Block: BlockT<Hash=H256>, RA, PRA>(
 	validator: bool,
 	import_until: Option<NumberFor<Block>>,
 	import_leading: Option<NumberFor<Block>>,
-	chain_spec_id: String,
+	fork_points: Vec<ForkPoint>,
+	skip_points: Vec<SkipPoint>,
 	shard_num: u16,
 	crfg_state_providers: Arc<RwLock<HashMap<u16, Arc<dyn CrfgStateProvider<Block::Hash, NumberFor<Block>>>>>>,
 ) -> Result<(CrfgBlockImport<B, E, Block, RA, PRA>, LinkHalf<B, E, Block, RA>), ClientError>
@@ -711,7 +713,8 @@ pub fn block_import<B, E, Block: BlockT<Hash=H256>, RA, PRA>(
 			import_until,
 			import_leading,
 			persistent_data.pending_skip.clone(),
-			chain_spec_id,
+			fork_points,
+			skip_points,
 			shard_num,
 			crfg_state_providers,
 		),
@@ -15,8 +15,8 @@
 // You should have received a copy of the GNU General Public License
 // along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
 
-use bech32::{ToBase32, Error, FromBase32};
-use std::fmt;
+use bech32::{ToBase32, FromBase32};
+use std::{error, fmt};
 use serde::Deserialize;
 use parity_codec::{Encode, Decode};
 use parity_codec::alloc::collections::HashMap;
@@ -25,6 +25,8 @@ use parity_codec::alloc::collections::HashMap;
 pub enum Hrp {
 	MAINNET,
 	TESTNET,
+	/// Prefix of a private network
+	Custom(String),
 }
 
 impl Default for Hrp{
@@ -37,20 +39,64 @@ impl Default for Hrp{
 #[derive(Clone, Eq, PartialEq, Debug, Deserialize)]
 pub struct Address(pub String);
 
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum AddressError {
+	/// Prefix other than the one of the network
+	InvalidHrp { expected: Hrp, actual: Hrp },
+	/// Data of another length than the one of the decoded type
+	InvalidLength { expected: usize, actual: usize },
+	InvalidChecksum,
+	/// Other bech32 encoding errors
+	InvalidEncoding(bech32::Error),
+}
+
+impl From<bech32::Error> for AddressError {
+	fn from(e: bech32::Error) -> Self {
+		match e {
+			bech32::Error::InvalidChecksum => AddressError::InvalidChecksum,
+			e => AddressError::InvalidEncoding(e),
+		}
+	}
+}
+
+impl fmt::Display for AddressError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			AddressError::InvalidHrp { expected, actual } => write!(f, "invalid hrp: expected {}, got {}", expected, actual),
+			AddressError::InvalidLength { expected, actual } => write!(f, "invalid length: expected {} bytes, got {}", expected, actual),
+			AddressError::InvalidChecksum => write!(f, "invalid checksum"),
+			AddressError::InvalidEncoding(e) => write!(f, "invalid encoding: {}", e),
+		}
+	}
+}
+
+impl error::Error for AddressError {}
+
 pub trait AddressCodec: Sized {
 	/// Decode from address format
-	fn from_address(address: &Address) -> Result<(Self, Hrp), Error>;
+	fn from_address(address: &Address) -> Result<(Self, Hrp), AddressError>;
 	/// Encode to address format
-	fn to_address(&self, hrp: Hrp) -> Result<Address, Error>;
+	fn to_address(&self, hrp: Hrp) -> Result<Address, AddressError>;
+	/// Decode from address format, the address being of the network of `hrp`
+	fn from_address_with_hrp(address: &Address, hrp: &Hrp) -> Result<Self, AddressError> {
+		let (res, actual) = Self::from_address(address)?;
+		if &actual != hrp {
+			return Err(AddressError::InvalidHrp { expected: hrp.clone(), actual });
+		}
+		Ok(res)
+	}
 }
 
 impl<T: AsMut<[u8]> + AsRef<[u8]> + Default> AddressCodec for T {
-	fn from_address(address: &Address) -> Result<(Self, Hrp), Error> {
+	fn from_address(address: &Address) -> Result<(Self, Hrp), AddressError> {
 
 		let (hrp_str, buf) = bech32::decode(&address.0)?;
 		let buf = Vec::from_base32(&buf)?;
 
 		let mut res = T::default();
+		if res.as_ref().len() != buf.len() {
+			return Err(AddressError::InvalidLength { expected: res.as_ref().len(), actual: buf.len() });
+		}
 		res.as_mut().copy_from_slice(&buf);
 
 		let hrp : Hrp = hrp_str.into();
@@ -58,14 +104,14 @@ impl<T: AsMut<[u8]> + AsRef<[u8]> + Default> AddressCodec for T {
 		Ok((res, hrp))
 
 	}
-	fn to_address(&self, hrp: Hrp) -> Result<Address, Error> {
+	fn to_address(&self, hrp: Hrp) -> Result<Address, AddressError> {
 		let buf = self.to_base32();
 
 		let hrp_str : String  = hrp.into();
 		bech32::encode(
 			&hrp_str,
 			buf,
-		).map(|s| Address(s))
+		).map(|s| Address(s)).map_err(AddressError::from)
 	}
 }
 
@@ -86,6 +132,7 @@ impl Into<String> for Hrp{
 		match self{
 			Hrp::MAINNET => YEE.to_string(),
 			Hrp::TESTNET => TYEE.to_string(),
+			Hrp::Custom(s) => s,
 		}
 	}
 }
@@ -96,19 +143,29 @@ impl From<String> for Hrp{
 		match s{
 			YEE => Hrp::MAINNET,
 			TYEE => Hrp::TESTNET,
-			_ => unreachable!(),
+			_ => Hrp::Custom(s.to_string()),
 		}
 	}
 }
 
+impl fmt::Display for Hrp {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let s : String = self.clone().into();
+		write!(f, "{}", s)
+	}
+}
+
 #[derive(Clone, Debug)]
 pub struct Config{
 	pub shards: HashMap<String, Shard>,
+	/// Prefix of the addresses accepted, any if None
+	pub hrp: Option<Hrp>,
 }
 
 #[derive(Clone, Debug)]
 pub struct Shard {
 	pub rpc: Vec<String>,
+	pub ws: Vec<String>,
 }
 
 impl Config{
@@ -126,6 +183,7 @@ pub struct RecommitRelay<Hash> {
 #[cfg(test)]
 mod tests {
 	use crate::AddressCodec;
+	use crate::AddressError;
 	use crate::Hrp;
 	use crate::Address;
 	use substrate_primitives::sr25519::Public;
@@ -166,6 +224,43 @@ mod tests {
 
 	}
 
+	#[test]
+	fn test_custom_hrp() {
+
+		let public = [1u8; 32];
+
+		let hrp = Hrp::from("pyee".to_string());
+
+		assert_eq!(hrp, Hrp::Custom("pyee".to_string()));
+
+		let address = public.to_address(hrp.clone()).expect("qed");
+
+		assert_eq!(<[u8; 32]>::from_address(&address), Ok((public, hrp.clone())));
+
+		assert_eq!(<[u8; 32]>::from_address_with_hrp(&address, &hrp), Ok(public));
+
+	}
+
+	#[test]
+	fn test_from_address_errors() {
+
+		let address = Address("tyee1qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqs0a78ky".to_string());
+
+		assert_eq!(<[u8; 32]>::from_address_with_hrp(&address, &Hrp::MAINNET),
+			Err(AddressError::InvalidHrp { expected: Hrp::MAINNET, actual: Hrp::TESTNET }));
+
+		assert_eq!(<[u8; 20]>::from_address(&address), Err(AddressError::InvalidLength { expected: 20, actual: 32 }));
+
+		let address = Address("tyee1qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqs0a78kz".to_string());
+
+		assert_eq!(<[u8; 32]>::from_address(&address), Err(AddressError::InvalidChecksum));
+
+		let address = Address("not an address".to_string());
+
+		assert!(<[u8; 32]>::from_address(&address).is_err());
+
+	}
+
 	#[test]
 	fn test_ss58_to_address() {
 
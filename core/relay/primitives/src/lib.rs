@@ -0,0 +1,38 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+///! Primitives for Yee Relay
+
+use {
+    rstd::vec::Vec,
+    substrate_client::decl_runtime_apis,
+    runtime_primitives::traits::Block as BlockT,
+    yee_sr_primitives::RelayCallInfo,
+};
+
+decl_runtime_apis! {
+    pub trait RelayApi {
+        /// get the calls which are relayed to another shard, declared by the runtime
+        fn relay_registry() -> Vec<RelayCallInfo>;
+
+        /// whether the origin shard of a relayed extrinsic gets a receipt: a delivered message
+        /// or a failed transfer, by the hash of the origin extrinsic
+        fn relay_receipt(hash: <Block as BlockT>::Hash) -> bool;
+    }
+}
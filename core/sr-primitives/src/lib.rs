@@ -9,6 +9,73 @@ pub const SHARD_CODE_SIZE: usize = 2;
 
 pub const MAX_STORAGE_SIZE: usize = 1 * 1024 * 1024;    // 1 Mb
 
+pub const MAX_MESSAGE_SIZE: usize = 16 * 1024;    // 16 Kb
+
+/// A call which is relayed to another shard when its dest belongs to that shard
+#[derive(PartialEq, Eq, Clone, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct RelayCallInfo {
+    /// module index in construct_runtime
+    pub module: u8,
+    /// call index in the module
+    pub call: u8,
+    /// how the destination shard handles the relayed call
+    pub relay_type: RelayTypes,
+    /// whether an owner account, whose funds are moved instead of the signer's, precedes dest
+    pub delegated: bool,
+}
+
+/// The relay calls declared by a runtime
+pub trait RelayRegistry {
+    fn relay_calls() -> Vec<RelayCallInfo>;
+}
+
+impl RelayRegistry for () {
+    fn relay_calls() -> Vec<RelayCallInfo> {
+        Vec::new()
+    }
+}
+
+/// The fork id from which relay calls are classified by the runtime registry, and delivered
+/// messages and failed relays are added to the relay proof for receipts.
+/// The runtime dispatches relays by its registry from spec_version 6, so the fork point of
+/// every shard must be set after that runtime upgrade, and told to its runtime
+/// (see `RelayRegistryActivation`).
+pub const RELAY_REGISTRY_FORK_ID: u32 = 2;
+
+/// Whether the current block is past the `RELAY_REGISTRY_FORK_ID` fork point, as known on chain.
+/// Calls only the registry relays correctly must not reach another shard before.
+pub trait RelayRegistryActivation {
+    fn relay_registry_active() -> bool;
+}
+
+impl RelayRegistryActivation for () {
+    fn relay_registry_active() -> bool {
+        false
+    }
+}
+
+/// Whether blocks sealed with `fork_id` follow the relay registry rules
+pub fn relay_registry_enabled(fork_id: Option<u32>) -> bool {
+    fork_id.map(|id| id >= RELAY_REGISTRY_FORK_ID).unwrap_or(false)
+}
+
+/// The relay calls before `RELAY_REGISTRY_FORK_ID`: any call of the balances (4) or assets (8) module
+pub fn legacy_relay_calls() -> Vec<RelayCallInfo> {
+    let mut calls = Vec::with_capacity(512);
+    for &(module, relay_type) in [(4u8, RelayTypes::Balance), (8u8, RelayTypes::Assets)].iter() {
+        for call in 0..=255u8 {
+            calls.push(RelayCallInfo { module, call, relay_type: relay_type.clone(), delegated: false });
+        }
+    }
+    calls
+}
+
+/// Find the relay call declared for (module, call)
+pub fn find_relay_call(registry: &[RelayCallInfo], module: u8, call: u8) -> Option<&RelayCallInfo> {
+    registry.iter().find(|x| x.module == module && x.call == call)
+}
+
 #[derive(PartialEq, Eq, Clone)]
 #[cfg_attr(feature = "std", derive(Debug))]
 pub struct RelayParams<Hash> where
@@ -54,6 +121,33 @@ impl<Hash> RelayParams<Hash> where
         self.parent_hash.clone()
     }
 
+    /// The account whose shard executed the relayed extrinsic, and so holds its proof:
+    /// the sender of an origin extrinsic, or the dest of the relay a receipt is for
+    pub fn source<AccountId>(&self, registry: &[RelayCallInfo]) -> Option<AccountId> where
+        AccountId: Codec + Clone + Default,
+    {
+        match self.relay_type {
+            RelayTypes::Receipt => {
+                let relay = RelayParams::<Hash>::decode(self.origin.clone())?;
+                if relay.relay_type == RelayTypes::Receipt {
+                    return None;
+                }
+                let origin = OriginExtrinsic::<AccountId, u128>::decode(registry, relay.origin)?;
+                if origin.relay_type() != relay.relay_type {
+                    return None;
+                }
+                Some(origin.to())
+            }
+            _ => {
+                let origin = OriginExtrinsic::<AccountId, u128>::decode(registry, self.origin.clone())?;
+                if origin.relay_type() != self.relay_type {
+                    return None;
+                }
+                Some(origin.from())
+            }
+        }
+    }
+
     /// decode from input
     pub fn decode(input: Vec<u8>) -> Option<Self> {
         let mut input = input.as_slice();
@@ -122,6 +216,22 @@ impl<Hash> RelayParams<Hash> where
 pub enum RelayTypes {
     Balance,
     Assets,
+    /// A cross shard message, delivered to the message handler of the destination shard
+    Message,
+    /// The receipt of a delivered message or a failed transfer, relayed back to the origin shard
+    Receipt,
+}
+
+impl RelayTypes {
+    /// Whether a relay extrinsic of this type, executed with `success`, is proved back to its
+    /// origin shard for a receipt: a delivered message, or a failed transfer to refund
+    pub fn has_receipt(&self, success: bool) -> bool {
+        match self {
+            RelayTypes::Message => success,
+            RelayTypes::Balance | RelayTypes::Assets => !success,
+            RelayTypes::Receipt => false,
+        }
+    }
 }
 
 /// OriginAsset for asset transfer
@@ -129,6 +239,7 @@ pub struct OriginExtrinsic<AccountId, Balance> where
     AccountId: Codec + Clone + Default,
     Balance: Codec + Clone,
 {
+    relay_type: RelayTypes,
     shard: Vec<u8>,
     id: Option<u32>,
     sender: AccountId,
@@ -137,13 +248,14 @@ pub struct OriginExtrinsic<AccountId, Balance> where
     _era: Era,
     dest: AccountId,
     amount: Balance,
+    data: Vec<u8>,
 }
 
 impl<AccountId, Balance> OriginExtrinsic<AccountId, Balance> where
     AccountId: Codec + Clone + Default,
     Balance: Codec + Clone,
 {
-    pub fn decode(relay_type: RelayTypes, input: Vec<u8>) -> Option<OriginExtrinsic<AccountId, Balance>> {
+    pub fn decode(registry: &[RelayCallInfo], input: Vec<u8>) -> Option<OriginExtrinsic<AccountId, Balance>> {
         let mut input = input.as_slice();
         if input.len() < 64 + 1 + 1 {
             return None;
@@ -205,23 +317,25 @@ impl<AccountId, Balance> OriginExtrinsic<AccountId, Balance> where
             (AccountId::default(), Vec::new(), Compact(0u64), Era::Immortal)
         };
 
-        if input.len() < 2 + 32 + 1 {
-            return None;
-        }
         // module
-        let _module: u8 = match input.read_byte() {
+        let module: u8 = match input.read_byte() {
             Some(m) => m,
             None => return None
         };
         // function
-        let _func: u8 = match input.read_byte() {
+        let func: u8 = match input.read_byte() {
             Some(f) => f,
             None => return None
         };
+        let (relay_type, delegated) = match find_relay_call(registry, module, func) {
+            Some(info) => (info.relay_type.clone(), info.delegated),
+            None => return None
+        };
         // shard code
         let mut shard_code: Vec<u8> = Vec::new();
         // AssetId
         let mut id: Compact<u32> = Compact(0u32);
+        let mut sender = sender;
         if relay_type == RelayTypes::Assets {
             shard_code = match Decode::decode(&mut input) {
                 Some(shard) => shard,
@@ -232,6 +346,19 @@ impl<AccountId, Balance> OriginExtrinsic<AccountId, Balance> where
                 None => return None
             };
         }
+        // delegated transfer moves the owner's funds, not the signer's
+        if delegated {
+            // owner AccountId type
+            let _type: u8 = match input.read_byte() {
+                Some(t) => t,
+                None => return None
+            };
+            // owner AccountId
+            sender = match Decode::decode(&mut input) {
+                Some(addr) => addr,
+                None => return None
+            };
+        }
         // dest AccountId type
         let _type: u8 = match input.read_byte() {
             Some(t) => t,
@@ -242,28 +369,33 @@ impl<AccountId, Balance> OriginExtrinsic<AccountId, Balance> where
             Some(addr) => addr,
             None => return None
         };
-        // amount
-        let amount = match Decode::decode(&mut input) {
-            Some(a) => {
-                let a_c: Compact<u128> = a;
-                let buf = a_c.0.encode();
-                match Decode::decode(&mut buf.as_slice()) {
-                    Some(am) => am,
-                    None => return None
-                }
+        // message data, a message moves no funds
+        let mut data: Vec<u8> = Vec::new();
+        let amount: Compact<u128> = if relay_type == RelayTypes::Message {
+            data = match Decode::decode(&mut input) {
+                Some(d) => d,
+                None => return None
+            };
+            Compact(0u128)
+        } else {
+            match Decode::decode(&mut input) {
+                Some(a) => a,
+                None => return None
             }
+        };
+        // amount
+        let amount = match Decode::decode(&mut amount.0.encode().as_slice()) {
+            Some(am) => am,
             None => return None
         };
-        if relay_type == RelayTypes::Assets {
-            Some(Self { shard: shard_code, id: Some(id.0), sender, _signature: signature, _index: index, _era: era, dest, amount })
-        } else if relay_type == RelayTypes::Balance {
-            Some(Self { shard: shard_code, id: None, sender, _signature: signature, _index: index, _era: era, dest, amount })
-        } else {
-            None
-        }
+        let id = match relay_type {
+            RelayTypes::Assets => Some(id.0),
+            _ => None,
+        };
+        Some(Self { relay_type, shard: shard_code, id, sender, _signature: signature, _index: index, _era: era, dest, amount, data })
     }
 
-    pub fn decode_type(input: Vec<u8>) -> Option<RelayTypes> {
+    pub fn decode_type(registry: &[RelayCallInfo], input: Vec<u8>) -> Option<RelayTypes> {
         let mut input = input.as_slice();
         // length
         let _len: Vec<()> = Decode::decode(&mut input)?;
@@ -296,14 +428,16 @@ impl<AccountId, Balance> OriginExtrinsic<AccountId, Balance> where
 
         // module
         let module: u8 = input.read_byte()?;
-        match module {
-            4u8 => Some(RelayTypes::Balance),
-            8u8 => Some(RelayTypes::Assets),
-            _ => None,
-        }
+        // function
+        let func: u8 = input.read_byte()?;
+        find_relay_call(registry, module, func).map(|info| info.relay_type.clone())
+    }
 
+    pub fn relay_type(&self) -> RelayTypes {
+        self.relay_type.clone()
     }
 
+    /// The account whose funds were moved, i.e. the owner for a delegated transfer
     pub fn from(&self) -> AccountId {
         self.sender.clone()
     }
@@ -321,4 +455,8 @@ impl<AccountId, Balance> OriginExtrinsic<AccountId, Balance> where
     pub fn asset_id(&self) -> Option<u32> {
         self.id.clone()
     }
+
+    pub fn data(&self) -> Vec<u8> {
+        self.data.clone()
+    }
 }
@@ -0,0 +1,33 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+///! Primitives for Yee Storage
+
+use {
+    rstd::vec::Vec,
+    runtime_primitives::traits::Block as BlockT,
+    substrate_client::decl_runtime_apis,
+};
+
+decl_runtime_apis! {
+    pub trait YeeStorageApi {
+        /// get a stored blob by its blake2 hash
+        fn get_blob(hash: <Block as BlockT>::Hash) -> Option<Vec<u8>>;
+    }
+}
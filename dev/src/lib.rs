@@ -82,6 +82,7 @@ pub struct RunParams{
 pub struct SwitchParams{
     pub shard_num: u16,
     pub rpc_port: u16,
+    pub ws_port: u16,
 }
 
 pub struct BootnodesRouterParams{
@@ -133,7 +134,7 @@ pub fn get_switch_params(shard_count: Option<u16>) -> error::Result<Vec<SwitchPa
     let shard_conf_map: HashMap<u16, (&str, u16, u16, u16, &str, u16)> = list
         .iter().cloned().collect();
 
-    Ok(shard_conf_map.iter().map(|(k, v)| SwitchParams{shard_num: *k, rpc_port: (*v).1} ).collect())
+    Ok(shard_conf_map.iter().map(|(k, v)| SwitchParams{shard_num: *k, rpc_port: (*v).1, ws_port: (*v).2} ).collect())
 }
 
 pub fn get_bootnodes_router_params(shard_count: Option<u16>)-> error::Result<Vec<BootnodesRouterParams>>{
@@ -37,6 +37,7 @@ use substrate_client::{
     blockchain::HeaderBackend,
     BlockchainEvents,
     ChainHead,
+    runtime_api::ApiExt,
 };
 use substrate_primitives::{H256, hexdisplay::HexDisplay};
 use substrate_service::{
@@ -51,19 +52,18 @@ use transaction_pool::txpool::{self, Pool as TransactionPool};
 use finality_tracker::FinalityTrackerDigestItem;
 use foreign_chain::{ForeignChain, ForeignChainConfig};
 use foreign_network::{message::generic::OutMessage, SyncProvider};
-use yee_assets::Call as AssetsCall;
-use yee_balances::Call as BalancesCall;
 // use yee_merkle::{MultiLayerProof, ProofAlgorithm, ProofHash};
 use yee_primitives::RecommitRelay;
 use yee_relay::Call as RelayCall;
 use yee_runtime::{
-    // AccountId,
+    AccountId,
     Call,
     Hash as RuntimeHash,
     UncheckedExtrinsic,
 };
 use yee_sharding_primitives::ShardingAPI;
-use yee_sr_primitives::{RelayParams, RelayTypes};
+use yee_relay_primitives::RelayApi;
+use yee_sr_primitives::{OriginExtrinsic, RelayCallInfo, RelayParams, RelayTypes, legacy_relay_calls};
 
 pub fn start_relay_transfer<F, C, A>(
     client: Arc<C>,
@@ -78,7 +78,7 @@ pub fn start_relay_transfer<F, C, A>(
           C: HeaderBackend<FactoryBlock<F>> + BlockBody<FactoryBlock<F>>,
           C: BlockchainEvents<FactoryBlock<F>> + ChainHead<<F as ServiceFactory>::Block>,
           C: ProvideRuntimeApi,
-          <C as ProvideRuntimeApi>::Api: ShardingAPI<FactoryBlock<F>>,
+          <C as ProvideRuntimeApi>::Api: ShardingAPI<FactoryBlock<F>> + RelayApi<FactoryBlock<F>>,
           A: txpool::ChainApi + 'static,
           <F as ServiceFactory>::Configuration: ForeignChainConfig + Send + Sync,
           Configuration<<F as ServiceFactory>::Configuration, <F as ServiceFactory>::Genesis>: Clone,
@@ -100,10 +100,18 @@ pub fn start_relay_transfer<F, C, A>(
                     if let Ok(Some(body)) = client_notify.block_body(&block_id) {
                         let api = client_notify.runtime_api();
                         let tc = api.get_shard_count(&block_id).expect("can't get shard count");    // total count
+                        let registry = match relay_registry(&*api, &block_id) {
+                            Ok(registry) => registry,
+                            Err(e) => {
+                                warn!(target: "foreign-relay", "can't get relay registry: {:?}", e);
+                                return Ok(());
+                            }
+                        };
+                        let has_receipt = |h: &RuntimeHash| api.relay_receipt(&block_id, *h).unwrap_or(false);
                         if let Ok(Some(cs)) = api.get_curr_shard(&block_id) {
                             for tx in &body {
                                 let ec = tx.encode();
-                                process_relay_extrinsic(ec, &header, hash, foreign_network.clone(), tc as u16, cs as u16);
+                                process_relay_extrinsic(ec, &header, hash, foreign_network.clone(), tc as u16, cs as u16, &registry, &has_receipt);
                             }
                         }
                     }
@@ -167,8 +175,16 @@ pub fn start_relay_transfer<F, C, A>(
 
         let api = client_recommit.runtime_api();
         let tc = api.get_shard_count(&block_id).expect("can't get shard count");    // total count
+        let registry = match relay_registry(&*api, &block_id) {
+            Ok(registry) => registry,
+            Err(e) => {
+                warn!(target: "foreign-relay", "can't get relay registry: {:?}", e);
+                return Ok(());
+            }
+        };
+        let has_receipt = |h: &RuntimeHash| api.relay_receipt(&block_id, *h).unwrap_or(false);
         if let Ok(Some(cs)) = api.get_curr_shard(&block_id) {
-            process_relay_extrinsic(ec, &header, hash, recommit_network.clone(), tc as u16, cs as u16);
+            process_relay_extrinsic(ec, &header, hash, recommit_network.clone(), tc as u16, cs as u16, &registry, &has_receipt);
         }
         Ok(())
     });
@@ -179,8 +195,19 @@ pub fn start_relay_transfer<F, C, A>(
     Ok(())
 }
 
+/// Relay calls declared by the runtime at `block_id`, the legacy ones if its runtime predates `RelayApi`
+fn relay_registry<Block, Api>(api: &Api, block_id: &BlockId<Block>) -> substrate_client::error::Result<Vec<RelayCallInfo>> where
+    Block: BlockT,
+    Api: RelayApi<Block>,
+{
+    if api.has_api::<dyn RelayApi<Block>>(block_id)? {
+        api.relay_registry(block_id)
+    } else {
+        Ok(legacy_relay_calls())
+    }
+}
 
-fn process_relay_extrinsic<Block>(ec: Vec<u8>, header: &<Block as BlockT>::Header, hash: <<Block as BlockT>::Header as Header>::Hash, network_send: Arc<dyn SyncProvider<Block, <<Block as BlockT>::Header as Header>::Hash>>, tc: u16, cs: u16) -> bool where
+fn process_relay_extrinsic<Block>(ec: Vec<u8>, header: &<Block as BlockT>::Header, hash: <<Block as BlockT>::Header as Header>::Hash, network_send: Arc<dyn SyncProvider<Block, <<Block as BlockT>::Header as Header>::Hash>>, tc: u16, cs: u16, registry: &[RelayCallInfo], has_receipt: &dyn Fn(&RuntimeHash) -> bool) -> bool where
     Block: BlockT<Hash=H256>,
     <<Block as BlockT>::Header as Header>::Number: From<u64>,
     u64: From<<<Block as BlockT>::Header as Header>::Number>,
@@ -192,57 +219,65 @@ fn process_relay_extrinsic<Block>(ec: Vec<u8>, header: &<Block as BlockT>::Heade
         None => return false
     };
     match &ex.signature {
-        None => return result,
+        // a delivered message or a failed transfer, send the receipt back to its origin shard
+        None => return process_relay_receipt(ec, header, hash, network_send, tc, cs, registry, has_receipt),
         _ => {}
     }
-    match ex.function {
-        Call::Balances(BalancesCall::transfer(dest, value)) => {
-            let ds = match yee_sharding_primitives::utils::shard_num_for(&dest, tc) {
-                Some(v) => v,
-                None => return result
-            };
-            if cs == ds {
-                return result;
-            }
+    let origin = match OriginExtrinsic::<AccountId, u128>::decode(registry, ec.clone()) {
+        Some(v) => v,
+        None => return result
+    };
+    let ds = match yee_sharding_primitives::utils::shard_num_for(&origin.to(), tc) {
+        Some(v) => v,
+        None => return result
+    };
+    if cs == ds {
+        return result;
+    }
 
-            // create relay transfer
-            let h: Compact<u64> = Compact((*header.number()).into());
-            let function = Call::Relay(RelayCall::transfer(RelayTypes::Balance, ec, h, hash, *header.parent_hash()));
-            let relay = UncheckedExtrinsic::new_unsigned(function);
-            let buf = relay.encode();
-            if let Some(relay) = Decode::decode(&mut buf.as_slice()) {
-                let relay_hash = <<Block as BlockT>::Header as Header>::Hashing::hash(buf.as_slice());
-                info!(target: "foreign-relay", "{}: shard: {}, height: {}, amount: {}, hash:{:?}, encode: {}",
-                      Colour::Green.paint("Send Balance-relay-transaction"), ds, h.0, value, relay_hash, HexDisplay::from(&buf));
+    info!(target: "foreign-relay", "{}: shard: {}, type: {:?}, AssetID: {:?}, amount: {}",
+          Colour::Green.paint("Send relay-transaction"), ds, origin.relay_type(), origin.asset_id(), origin.amount());
+    send_relay_extrinsic(origin.relay_type(), ec, header, hash, network_send, ds);
+    return true;
+}
 
-                // broadcast relay transfer
-                network_send.on_relay_extrinsics(ds, vec![(relay_hash, relay)]);
-            };
-        }
-        Call::Assets(AssetsCall::transfer(_shard_code, id, dest, value)) => {
-            let ds = match yee_sharding_primitives::utils::shard_num_for(&dest, tc as u16) {
-                Some(v) => v,
-                None => return result
-            };
-            if cs == ds {
-                return result;
-            }
+fn process_relay_receipt<Block>(ec: Vec<u8>, header: &<Block as BlockT>::Header, hash: <<Block as BlockT>::Header as Header>::Hash, network_send: Arc<dyn SyncProvider<Block, <<Block as BlockT>::Header as Header>::Hash>>, tc: u16, cs: u16, registry: &[RelayCallInfo], has_receipt: &dyn Fn(&RuntimeHash) -> bool) -> bool where
+    Block: BlockT<Hash=H256>,
+    <<Block as BlockT>::Header as Header>::Number: From<u64>,
+    u64: From<<<Block as BlockT>::Header as Header>::Number>,
+{
+    let rtx = match RelayParams::<RuntimeHash>::decode(ec.clone()) {
+        Some(ref v) if v.relay_type() != RelayTypes::Receipt && has_receipt(&v.hash()) => v.clone(),
+        _ => return false
+    };
+    let ds = match rtx.source::<AccountId>(registry).and_then(|from| yee_sharding_primitives::utils::shard_num_for(&from, tc)) {
+        Some(v) => v,
+        None => return false
+    };
+    if cs == ds {
+        return false;
+    }
 
-            // create relay transfer
-            let h: Compact<u64> = Compact((*header.number()).into());
-            let function = Call::Relay(RelayCall::transfer(RelayTypes::Assets, ec, h, hash, *header.parent_hash()));
-            let relay = UncheckedExtrinsic::new_unsigned(function);
-            let buf = relay.encode();
-            if let Some(relay) = Decode::decode(&mut buf.as_slice()) {
-                let relay_hash = <<Block as BlockT>::Header as Header>::Hashing::hash(buf.as_slice());
-                info!(target: "foreign-relay", "{}: shard: {}, height: {}, AssetID: {}, amount: {}, hash:{:?}, encode: {}",
-                      Colour::Green.paint("Send Asset-relay-transaction"), ds, h.0, id, value, relay_hash, HexDisplay::from(&buf));
+    info!(target: "foreign-relay", "{}: shard: {}, type: {:?}, origin: {:?}", Colour::Green.paint("Send relay-receipt"), ds, rtx.relay_type(), rtx.hash());
+    send_relay_extrinsic(RelayTypes::Receipt, ec, header, hash, network_send, ds);
+    return true;
+}
 
-                // broadcast relay transfer
-                network_send.on_relay_extrinsics(ds, vec![(relay_hash, relay)]);
-            }
-        }
-        _ => {}
+fn send_relay_extrinsic<Block>(relay_type: RelayTypes, ec: Vec<u8>, header: &<Block as BlockT>::Header, hash: <<Block as BlockT>::Header as Header>::Hash, network_send: Arc<dyn SyncProvider<Block, <<Block as BlockT>::Header as Header>::Hash>>, ds: u16) where
+    Block: BlockT<Hash=H256>,
+    <<Block as BlockT>::Header as Header>::Number: From<u64>,
+    u64: From<<<Block as BlockT>::Header as Header>::Number>,
+{
+    // create relay transfer
+    let h: Compact<u64> = Compact((*header.number()).into());
+    let function = Call::Relay(RelayCall::transfer(relay_type, ec, h, hash, *header.parent_hash()));
+    let relay = UncheckedExtrinsic::new_unsigned(function);
+    let buf = relay.encode();
+    if let Some(relay) = Decode::decode(&mut buf.as_slice()) {
+        let relay_hash = <<Block as BlockT>::Header as Header>::Hashing::hash(buf.as_slice());
+        info!(target: "foreign-relay", "shard: {}, height: {}, hash:{:?}, encode: {}", ds, h.0, relay_hash, HexDisplay::from(&buf));
+
+        // broadcast relay transfer
+        network_send.on_relay_extrinsics(ds, vec![(relay_hash, relay)]);
     }
-    return true;
-}
\ No newline at end of file
+}
@@ -43,6 +43,10 @@ error_chain! {
 			description("recommit relay extrinsic failed"),
 			display("Recommit relay extrinsic failed"),
 		}
+		InvalidExtrinsic {
+			description("invalid extrinsic"),
+			display("Invalid extrinsic"),
+		}
 	}
 }
 
@@ -65,6 +69,11 @@ impl From<Error> for rpc::Error {
 				message: "Job not found".into(),
 				data: None,
 			},
+			Error(ErrorKind::InvalidExtrinsic, _) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(1),
+				message: "Invalid extrinsic".into(),
+				data: None,
+			},
 			Error(ErrorKind::Consensus(_), _) => rpc::Error {
 				code: rpc::ErrorCode::ServerError(1),
 				message: "Consensus error".into(),
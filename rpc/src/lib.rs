@@ -45,6 +45,7 @@ use futures::sync::mpsc;
 use yee_primitives::{RecommitRelay, Hrp};
 use crfg::CrfgStateProvider;
 use yee_foreign_network::SyncProvider;
+use yee_context::ForkPoint;
 use serde::Serialize;
 use parity_codec::alloc::collections::HashMap;
 
@@ -75,6 +76,8 @@ pub struct Config {
     pub shard_count: u16,
     pub coinbase: Option<String>,
     pub job_cache_size: Option<u32>,
+    #[serde(skip)]
+    pub fork_points: Vec<ForkPoint>,
 }
 
 #[derive(Clone)]
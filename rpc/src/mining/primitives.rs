@@ -31,6 +31,13 @@ pub struct Job<Hash, Number: SerdeHex, AuthorityId> where
     pub hash: Hash,
     pub header: Header<Hash, Number>,
     pub digest_item: DigestItem<Hash, AuthorityId>,
+    /// Name of the pow algorithm of the block
+    #[serde(default = "default_algorithm")]
+    pub algorithm: String,
+}
+
+fn default_algorithm() -> String {
+    yee_consensus_pow::DEFAULT_POW_ALGORITHM.to_string()
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
@@ -105,6 +112,7 @@ impl<B, AuthorityId> From<DefaultJob<B, AuthorityId>> for Job<B::Hash, <B::Heade
             hash: j.hash,
             header: j.header.into(),
             digest_item: j.digest_item.into(),
+            algorithm: j.algorithm,
         }
     }
 }
@@ -0,0 +1,186 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+use num_bigint::BigUint;
+use parity_codec::Decode;
+use serde::{Serialize, Deserialize};
+use yee_runtime::{AccountId, UncheckedExtrinsic};
+use yee_serde_hex::Hex;
+use yee_sharding_primitives::utils::shard_num_for;
+use yee_sr_primitives::{OriginExtrinsic, RelayCallInfo, RelayTypes};
+
+/// Storage keys of the `yee-balances` fees
+pub const TRANSACTION_BASE_FEE_KEY: &[u8] = b"Balances TransactionBaseFee";
+pub const TRANSACTION_BYTE_FEE_KEY: &[u8] = b"Balances TransactionByteFee";
+pub const TRANSFER_FEE_KEY: &[u8] = b"Balances TransferFee";
+pub const CREATION_FEE_KEY: &[u8] = b"Balances CreationFee";
+
+/// The fees of `yee-balances` at a block
+pub struct FeeConstants {
+	pub transaction_base_fee: u128,
+	pub transaction_byte_fee: u128,
+	pub transfer_fee: u128,
+	pub creation_fee: u128,
+}
+
+/// Fee breakdown of an extrinsic
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeDetail {
+	/// Encoded length of the extrinsic
+	pub len: u64,
+	/// `TransactionBaseFee`
+	pub base_fee: Hex<BigUint>,
+	/// `TransactionByteFee * len`
+	pub byte_fee: Hex<BigUint>,
+	/// `TransferFee`, charged by balance transfers
+	pub transfer_fee: Hex<BigUint>,
+	/// `CreationFee` of the chain, not part of `origin_fee`:
+	/// the transfers of `yee-balances` create the destination account without charging it
+	pub creation_fee: Hex<BigUint>,
+	/// Total charged to the sender on its shard
+	pub origin_fee: Hex<BigUint>,
+	/// Destination shard of a cross shard transfer
+	pub dest_shard_num: Option<u16>,
+	/// Charged on the destination shard of a cross shard transfer
+	pub dest_fee: Option<Hex<BigUint>>,
+}
+
+/// Fee of the encoded `extrinsic` sent from shard `shard_num`, none if it can't be decoded
+pub fn estimate(constants: &FeeConstants, registry: &[RelayCallInfo], extrinsic: Vec<u8>, shard_num: u16, shard_count: u16) -> Option<FeeDetail> {
+	let ex: UncheckedExtrinsic = Decode::decode(&mut extrinsic.as_slice())?;
+	let len = extrinsic.len() as u64;
+
+	// unsigned extrinsics, inherents and relay extrinsics, pay no fee
+	if ex.signature.is_none() {
+		return Some(FeeDetail {
+			len,
+			base_fee: Hex(0u32.into()),
+			byte_fee: Hex(0u32.into()),
+			transfer_fee: Hex(0u32.into()),
+			creation_fee: Hex(constants.creation_fee.into()),
+			origin_fee: Hex(0u32.into()),
+			dest_shard_num: None,
+			dest_fee: None,
+		});
+	}
+
+	let base_fee = constants.transaction_base_fee;
+	let byte_fee = constants.transaction_byte_fee.saturating_mul(len as u128);
+
+	let origin = OriginExtrinsic::<AccountId, u128>::decode(registry, extrinsic);
+	let transfer_fee = match origin {
+		Some(ref origin) if origin.relay_type() == RelayTypes::Balance => constants.transfer_fee,
+		_ => 0,
+	};
+	let dest_shard_num = origin.as_ref()
+		.and_then(|origin| shard_num_for(&origin.to(), shard_count))
+		.filter(|dest| *dest != shard_num);
+
+	Some(FeeDetail {
+		len,
+		base_fee: Hex(base_fee.into()),
+		byte_fee: Hex(byte_fee.into()),
+		transfer_fee: Hex(transfer_fee.into()),
+		creation_fee: Hex(constants.creation_fee.into()),
+		origin_fee: Hex(base_fee.saturating_add(byte_fee).saturating_add(transfer_fee).into()),
+		dest_shard_num,
+		// the relay extrinsic executing the transfer on the destination shard is unsigned
+		dest_fee: dest_shard_num.map(|_| Hex(0u32.into())),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use parity_codec::Encode;
+	use runtime_primitives::generic::Era;
+	use substrate_primitives::sr25519;
+	use yee_runtime::{BalancesCall, Call};
+
+	const CONSTANTS: FeeConstants = FeeConstants {
+		transaction_base_fee: 10,
+		transaction_byte_fee: 1,
+		transfer_fee: 100,
+		creation_fee: 1000,
+	};
+
+	/// Account of the shard `shard_num` of 4 shards
+	fn account(shard_num: u8) -> AccountId {
+		let mut raw = [1u8; 32];
+		raw[31] = shard_num;
+		sr25519::Public::from_raw(raw)
+	}
+
+	fn transfer(dest: AccountId) -> Call {
+		Call::Balances(BalancesCall::transfer(dest.into(), 500))
+	}
+
+	fn registry() -> Vec<RelayCallInfo> {
+		let encoded = transfer(Default::default()).encode();
+		vec![RelayCallInfo { module: encoded[0], call: encoded[1], relay_type: RelayTypes::Balance, delegated: false }]
+	}
+
+	fn signed(call: Call) -> Vec<u8> {
+		let signature = sr25519::Signature::from_raw([0u8; 64]);
+		UncheckedExtrinsic::new_signed(0, call, account(0).into(), signature, Era::Immortal).encode()
+	}
+
+	#[test]
+	fn signed_local_transfer() {
+		let extrinsic = signed(transfer(account(0)));
+		let len = extrinsic.len() as u128;
+		let detail = estimate(&CONSTANTS, &registry(), extrinsic, 0, 4).unwrap();
+
+		assert_eq!(detail.len as u128, len);
+		assert_eq!(detail.base_fee.0, 10u32.into());
+		assert_eq!(detail.byte_fee.0, len.into());
+		assert_eq!(detail.transfer_fee.0, 100u32.into());
+		assert_eq!(detail.creation_fee.0, 1000u32.into());
+		assert_eq!(detail.origin_fee.0, (110 + len).into());
+		assert_eq!(detail.dest_shard_num, None);
+		assert!(detail.dest_fee.is_none());
+	}
+
+	#[test]
+	fn cross_shard_transfer() {
+		let extrinsic = signed(transfer(account(2)));
+		let len = extrinsic.len() as u128;
+		let detail = estimate(&CONSTANTS, &registry(), extrinsic, 0, 4).unwrap();
+
+		assert_eq!(detail.origin_fee.0, (110 + len).into());
+		assert_eq!(detail.dest_shard_num, Some(2));
+		assert_eq!(detail.dest_fee.map(|fee| fee.0), Some(0u32.into()));
+	}
+
+	#[test]
+	fn unsigned_extrinsic() {
+		let extrinsic = UncheckedExtrinsic::new_unsigned(transfer(account(2))).encode();
+		let detail = estimate(&CONSTANTS, &registry(), extrinsic, 0, 4).unwrap();
+
+		assert_eq!(detail.base_fee.0, 0u32.into());
+		assert_eq!(detail.byte_fee.0, 0u32.into());
+		assert_eq!(detail.transfer_fee.0, 0u32.into());
+		assert_eq!(detail.origin_fee.0, 0u32.into());
+		assert_eq!(detail.dest_shard_num, None);
+	}
+
+	#[test]
+	fn invalid_extrinsic() {
+		assert!(estimate(&CONSTANTS, &registry(), vec![1, 2, 3], 0, 4).is_none());
+	}
+}
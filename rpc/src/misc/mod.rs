@@ -16,12 +16,18 @@ use std::time::Duration;
 use substrate_primitives::{Bytes, H256, Blake2Hasher};
 use transaction_pool::txpool::{Pool, ChainApi as PoolChainApi};
 use yee_foreign_network::{SyncProvider, NetworkState};
-use runtime_primitives::traits::{Block as BlockT, NumberFor};
+use runtime_primitives::traits::{Block as BlockT, NumberFor, As};
 use runtime_primitives::generic::BlockId;
 use std::fmt::Debug;
 use client::Client;
 use crate::misc::types::ForeignStatus;
 use parity_codec::alloc::collections::HashMap;
+use substrate_primitives::{twox_128, NeverOffchainExt, storage::StorageKey};
+use client::ExecutionStrategy;
+use yee_sr_primitives::{relay_registry_enabled, legacy_relay_calls};
+use yee_consensus_pow::fork;
+
+pub mod fee;
 
 #[rpc]
 pub trait MiscApi<Hash, Number> {
@@ -52,8 +58,9 @@ pub trait MiscApi<Hash, Number> {
 	#[rpc(name = "chain_getRelayProof")]
 	fn get_relay_proof(&self, hash: Option<Hash>) -> errors::Result<Option<Bytes>>;
 
-
-
+	/// Fee breakdown of an encoded extrinsic sent from this shard
+	#[rpc(name = "author_estimateFee")]
+	fn estimate_fee(&self, extrinsic: Bytes, hash: Option<Hash>) -> errors::Result<fee::FeeDetail>;
 }
 
 pub struct Misc<P: PoolChainApi, B: BlockT, H, Backend, E, RA> {
@@ -103,6 +110,11 @@ where
 			Some(hash) => hash,
 		})
 	}
+
+	fn storage<T: Decode>(&self, at: &BlockId<B>, key: &[u8]) -> errors::Result<Option<T>> {
+		let data = self.client.storage(at, &StorageKey(twox_128(key).to_vec()))?;
+		Ok(data.and_then(|data| Decode::decode(&mut &data.0[..])))
+	}
 }
 
 impl<P, B, H, Backend, E, RA> MiscApi<B::Hash, NumberFor<B>> for Misc<P, B, H, Backend, E, RA> where
@@ -204,6 +216,35 @@ impl<P, B, H, Backend, E, RA> MiscApi<B::Hash, NumberFor<B>> for Misc<P, B, H, B
 
 		Ok(())
 	}
+
+	fn estimate_fee(&self, extrinsic: Bytes, hash: Option<B::Hash>) -> errors::Result<fee::FeeDetail> {
+		let at = BlockId::Hash(self.unwrap_or_best(hash)?);
+
+		let constants = fee::FeeConstants {
+			transaction_base_fee: self.storage(&at, fee::TRANSACTION_BASE_FEE_KEY)?.unwrap_or(0),
+			transaction_byte_fee: self.storage(&at, fee::TRANSACTION_BYTE_FEE_KEY)?.unwrap_or(0),
+			transfer_fee: self.storage(&at, fee::TRANSFER_FEE_KEY)?.unwrap_or(0),
+			creation_fee: self.storage(&at, fee::CREATION_FEE_KEY)?.unwrap_or(0),
+		};
+
+		// shard info of the block, the config is stale after scaling out
+		let (shard_num, shard_count) = self.storage::<(u16, u16)>(&at, b"Sharding CurrentShardInfo")?
+			.unwrap_or((self.config.shard_num, self.config.shard_count));
+
+		// the extrinsic goes in the next block, relayed by the registry from its fork id as in the job manager
+		let number = self.client.block_number_from_id(&at)?.map(|number| number.as_()).unwrap_or(0u64);
+		let registry = if relay_registry_enabled(fork::fork_id(&self.config.fork_points, shard_num, number + 1)) {
+			let registry = self.client.executor().call(
+				&at, "RelayApi_relay_registry", &[], ExecutionStrategy::NativeElseWasm, NeverOffchainExt::new(),
+			)?;
+			Decode::decode(&mut &registry[..]).unwrap_or_else(legacy_relay_calls)
+		} else {
+			legacy_relay_calls()
+		};
+
+		fee::estimate(&constants, &registry, extrinsic.0, shard_num, shard_count)
+			.ok_or(errors::Error::from(errors::ErrorKind::InvalidExtrinsic))
+	}
 }
 
 mod types {
@@ -22,6 +22,7 @@ use client::{
 	runtime_api, impl_runtime_apis
 };
 use sharding_primitives::ShardingInfo;
+use yee_sr_primitives::{RelayCallInfo, RelayRegistry};
 use version::RuntimeVersion;
 #[cfg(feature = "std")]
 use version::NativeVersion;
@@ -38,6 +39,7 @@ pub use timestamp::Call as TimestampCall;
 pub use relay::Call as RelayCall;
 pub use balances::Call as BalancesCall;
 pub use assets::Call as AssetsCall;
+pub use sudo::Call as SudoCall;
 pub use runtime_primitives::{Permill, Perbill};
 pub use timestamp::BlockPeriod;
 pub use support::{StorageValue, construct_runtime};
@@ -106,8 +108,8 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("yee"),
 	impl_name: create_runtime_str!("yee-rs"),
 	authoring_version: 4,
-	spec_version: 5,
-	impl_version: 5,
+	spec_version: 6,
+	impl_version: 6,
 	apis: RUNTIME_API_VERSIONS,
 };
 
@@ -151,14 +153,19 @@ impl assets::Trait for Runtime {
 	type Balance = u128;
 
 	type Sharding = sharding::Module<Runtime>;
+	type RelayActivation = relay::Module<Runtime>;
 }
 
 impl relay::Trait for Runtime {
-	type Runtime = Runtime;
+	type Event = Event;
+	type Registry = yee::RelayRegistry;
+	type Sharding = sharding::Module<Runtime>;
+	type MessageHandler = ();
 }
 
 impl storage::Trait for Runtime {
 	type Event = Event;
+	type Currency = balances::Module<Self>;
 }
 
 impl pow::Trait for Runtime {
@@ -173,6 +180,11 @@ impl pow::Trait for Runtime {
 
 	type Sharding = sharding::Module<Runtime>;
 
+	type AuthorityId = AuthorityId;
+
+	type AuthoritySignature = AuthoritySignature;
+
+	type BlockAuthority = yee::CrfgBlockAuthority;
 }
 
 impl consensus::Trait for Runtime {
@@ -259,8 +271,8 @@ construct_runtime!(
 		Crfg: crfg::{Module, Call, Storage, Config<T>, Log(), Event<T>, Inherent},
 		FinalityTracker: finality_tracker::{Module, Call, Log(), Inherent},
 		Assets: assets::{Module, Call, Storage, Config<T>, Event<T>},
-		Relay: relay::{Module, Call},
-		Storage: storage::{Module, Call, Event<T>},
+		Relay: relay::{Module, Call, Storage, Event<T>},
+		Storage: storage::{Module, Call, Storage, Event<T>},
 		Sudo: sudo,
 	}
 );
@@ -280,7 +292,7 @@ pub type UncheckedExtrinsic = generic::UncheckedMortalCompactExtrinsic<Address,
 /// Extrinsic type that has already been checked.
 pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Nonce, Call>;
 /// Executive: handles dispatch to the various modules.
-pub type Executive = executive::Executive<Runtime, Block, Context, Balances, AllModules>;
+pub type Executive = executive::Executive<Runtime, Block, Context, Balances, AllModules, yee::RelayRegistry>;
 
 #[cfg(feature = "std")]
 impl Clone for GenesisConfig {
@@ -359,6 +371,22 @@ impl_runtime_apis! {
         }
 	}
 
+	impl storage_primitives::YeeStorageApi<Block> for Runtime {
+		fn get_blob(hash: <Block as BlockT>::Hash) -> Option<Vec<u8>> {
+			Storage::get_blob(hash)
+		}
+	}
+
+	impl relay_primitives::RelayApi<Block> for Runtime {
+		fn relay_registry() -> Vec<RelayCallInfo> {
+			<yee::RelayRegistry as RelayRegistry>::relay_calls()
+		}
+
+		fn relay_receipt(hash: <Block as BlockT>::Hash) -> bool {
+			Relay::relay_receipt(hash)
+		}
+	}
+
 	impl offchain_primitives::OffchainWorkerApi<Block> for Runtime {
 		fn offchain_worker(n: NumberFor<Block>) {
 			Executive::offchain_worker(n)
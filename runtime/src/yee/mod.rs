@@ -14,3 +14,60 @@
 //
 // You should have received a copy of the GNU General Public License
 // along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+use rstd::prelude::*;
+use parity_codec::Encode;
+use runtime_primitives::traits::CurrentHeight;
+use yee_sr_primitives::{RelayCallInfo, RelayTypes};
+use crate::{AuthorityId, Address, Assets, AssetsCall, Balances, BalancesCall, Call, Crfg, RelayCall, Runtime};
+
+/// Block producer key, as registered by the block's `update_authorities` inherent
+pub struct CrfgBlockAuthority;
+
+impl pow::BlockAuthority<AuthorityId> for CrfgBlockAuthority {
+    fn block_authority() -> Option<AuthorityId> {
+        let now = system::ChainContext::<Runtime>::default().current_height();
+        Crfg::pending_change()
+            .filter(|change| change.scheduled_at == now)
+            .and_then(|change| change.next_authorities.last().map(|(key, _)| key.clone()))
+    }
+}
+
+/// The calls relayed to the destination shard. Module and call indices are read from the
+/// encoded `Call`, so they follow the construct_runtime ordering.
+pub struct RelayRegistry;
+
+fn relay_call(call: Call, relay_type: RelayTypes, delegated: bool) -> RelayCallInfo {
+    let encoded = call.encode();
+    RelayCallInfo { module: encoded[0], call: encoded[1], relay_type, delegated }
+}
+
+impl yee_sr_primitives::RelayRegistry for RelayRegistry {
+    fn relay_calls() -> Vec<RelayCallInfo> {
+        let dest = || -> Address { indices::address::Address::Id(Default::default()) };
+        vec![
+            relay_call(Call::Balances(BalancesCall::transfer(dest(), 0)), RelayTypes::Balance, false),
+            relay_call(Call::Assets(AssetsCall::transfer(Vec::new(), 0, dest(), 0)), RelayTypes::Assets, false),
+            relay_call(Call::Assets(AssetsCall::transfer_from(Vec::new(), 0, dest(), dest(), 0)), RelayTypes::Assets, true),
+            relay_call(Call::Relay(RelayCall::send_message(dest(), Vec::new())), RelayTypes::Message, false),
+        ]
+    }
+}
+
+impl relay::RelayHandler for RelayRegistry {
+    fn relay_transfer(relay_type: RelayTypes, registry: &[RelayCallInfo], tx: Vec<u8>) -> support::dispatch::Result {
+        match relay_type {
+            RelayTypes::Balance => Balances::relay_transfer(registry, tx),
+            RelayTypes::Assets => Assets::relay_transfer(registry, tx),
+            RelayTypes::Message | RelayTypes::Receipt => Err("relay type is handled by the relay module."),
+        }
+    }
+
+    fn relay_refund(relay_type: RelayTypes, registry: &[RelayCallInfo], tx: Vec<u8>) -> support::dispatch::Result {
+        match relay_type {
+            RelayTypes::Balance => Balances::relay_refund(registry, tx),
+            RelayTypes::Assets => Assets::relay_refund(registry, tx),
+            RelayTypes::Message | RelayTypes::Receipt => Err("relay type is not refundable."),
+        }
+    }
+}
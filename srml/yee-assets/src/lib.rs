@@ -21,12 +21,12 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use srml_support::{StorageValue, StorageMap, Parameter, decl_module, decl_event, decl_storage, ensure, dispatch::Result};
-use primitives::{traits::{Member, SimpleArithmetic, As, Zero, StaticLookup, CheckedAdd}};
+use primitives::{traits::{Member, SimpleArithmetic, As, Zero, StaticLookup, CheckedAdd, CheckedSub, Hash}};
 use sharding_primitives::ShardingInfo;
 use parity_codec::{Encode};
 use system::ensure_signed;
 use rstd::prelude::Vec;
-use yee_sr_primitives::{RelayTypes, OriginExtrinsic, SHARD_CODE_SIZE};
+use yee_sr_primitives::{RelayCallInfo, RelayRegistryActivation, OriginExtrinsic, SHARD_CODE_SIZE};
 
 pub trait Trait: sharding::Trait {
 	/// The overarching event type.
@@ -36,6 +36,9 @@ pub trait Trait: sharding::Trait {
 	type Balance: Member + Parameter + SimpleArithmetic + Default + Copy;
 
 	type Sharding: ShardingInfo<Self::ShardNum>;
+
+	/// Whether the nodes relay `transfer_from` by the relay registry
+	type RelayActivation: RelayRegistryActivation;
 }
 
 type AssetId = u32;
@@ -68,32 +71,190 @@ decl_module! {
 			#[compact] id: AssetId,
 			target: <T::Lookup as StaticLookup>::Source,
 			#[compact] amount: T::Balance
+		) {
+			let origin = ensure_signed(origin)?;
+			let target = T::Lookup::lookup(target)?;
+			Self::transfer_asset(shard_code, id, origin, target, amount)?;
+		}
+
+		/// Issue `amount` new units of asset `id` to `target`. Only the asset's issuer may mint,
+		/// and the minted units are added to the asset's `TotalSupply`.
+		fn mint(origin,
+			#[compact] id: AssetId,
+			target: <T::Lookup as StaticLookup>::Source,
+			#[compact] amount: T::Balance
+		) {
+			let origin = ensure_signed(origin)?;
+			let shard_code = Self::ensure_issuer(&origin, id)?;
+			ensure!(!amount.is_zero(), "mint amount should be non-zero");
+
+			let target = T::Lookup::lookup(target)?;
+			ensure!(Self::is_in_current_shard(&target), "mint target must be in current shard");
+
+			let new_total = match Self::total_supply(id).checked_add(&amount) {
+				Some(b) => b,
+				None => return Err("total supply too high to mint value"),
+			};
+			let to_account = (shard_code.clone(), id, target.clone());
+			let new_to_balance = match <Balances<T>>::get(&to_account).checked_add(&amount) {
+				Some(b) => b,
+				None => return Err("destination balance too high to receive value"),
+			};
+
+			<Balances<T>>::insert(to_account, new_to_balance);
+			<TotalSupply<T>>::insert(id, new_total);
+
+			// event
+			Self::deposit_event(RawEvent::Minted(shard_code, id, origin, target, amount));
+		}
+
+		/// Destroy `amount` units of asset `(shard_code, id)` held by the origin, reducing its
+		/// `TotalSupply`. Only assets issued on this shard may be burned, since the `TotalSupply`
+		/// of a foreign asset lives on its issuing shard: transfer the units back there first.
+		fn burn(origin,
+			shard_code: Vec<u8>,
+			#[compact] id: AssetId,
+			#[compact] amount: T::Balance
 		) {
 			let origin = ensure_signed(origin)?;
 			let origin_account = (shard_code.clone(), id, origin.clone());
 			let origin_balance = <Balances<T>>::get(&origin_account);
 
-			ensure!(!amount.is_zero(), "transfer amount should be non-zero");
-			ensure!(origin_balance >= amount, "origin account balance must be greater than or equal to the transfer amount");
+			ensure!(!amount.is_zero(), "burn amount should be non-zero");
+			ensure!(Self::is_native_asset(&shard_code, id), "only assets issued on this shard can be burned");
+			ensure!(origin_balance >= amount, "origin account balance must be greater than or equal to the burn amount");
+			Self::ensure_not_frozen(&shard_code, id, &origin)?;
 
-			// change amount about origin account
+			let new_total = match Self::total_supply(id).checked_sub(&amount) {
+				Some(b) => b,
+				None => return Err("burn amount exceeds total supply"),
+			};
+			<TotalSupply<T>>::insert(id, new_total);
 			<Balances<T>>::insert(origin_account, origin_balance - amount);
-			let target = T::Lookup::lookup(target)?;
 
-			let (cn, c) = (T::Sharding::get_curr_shard().expect("can't get current shard num").as_() as u16, T::Sharding::get_shard_count().as_() as u16);
-			let dn = sharding_primitives::utils::shard_num_for(&target, c).expect("can't get target shard num");
-			// in same sharding
-			if cn == dn {
-				let to_account =(shard_code.clone(), id, target.clone());
-				let to_balance = <Balances<T>>::get(&to_account);
-				let new_to_balance = match to_balance.checked_add(&amount) {
-					Some(b) => b,
-					None => return Err("destination balance too high to receive value"),
-				};
-				<Balances<T>>::insert(to_account, new_to_balance);
-			}
 			// event
-			Self::deposit_event(RawEvent::Transferred(shard_code, id, origin, target, amount));
+			Self::deposit_event(RawEvent::Burned(shard_code, id, origin, amount));
+		}
+
+		/// Allow `spender` to move up to `amount` units of the origin's asset `(shard_code, id)`,
+		/// replacing any previous allowance.
+		fn approve(origin,
+			shard_code: Vec<u8>,
+			#[compact] id: AssetId,
+			spender: <T::Lookup as StaticLookup>::Source,
+			#[compact] amount: T::Balance
+		) {
+			let origin = ensure_signed(origin)?;
+			let spender = T::Lookup::lookup(spender)?;
+			Self::set_allowance(shard_code, id, origin, spender, amount);
+		}
+
+		/// Raise the allowance of `spender` over the origin's asset `(shard_code, id)` by `amount`.
+		fn increase_allowance(origin,
+			shard_code: Vec<u8>,
+			#[compact] id: AssetId,
+			spender: <T::Lookup as StaticLookup>::Source,
+			#[compact] amount: T::Balance
+		) {
+			let origin = ensure_signed(origin)?;
+			let spender = T::Lookup::lookup(spender)?;
+			let allowance = Self::allowance(shard_code.clone(), id, origin.clone(), spender.clone());
+			let new_allowance = match allowance.checked_add(&amount) {
+				Some(b) => b,
+				None => return Err("allowance too high to increase"),
+			};
+			Self::set_allowance(shard_code, id, origin, spender, new_allowance);
+		}
+
+		/// Lower the allowance of `spender` over the origin's asset `(shard_code, id)` by `amount`.
+		fn decrease_allowance(origin,
+			shard_code: Vec<u8>,
+			#[compact] id: AssetId,
+			spender: <T::Lookup as StaticLookup>::Source,
+			#[compact] amount: T::Balance
+		) {
+			let origin = ensure_signed(origin)?;
+			let spender = T::Lookup::lookup(spender)?;
+			let allowance = Self::allowance(shard_code.clone(), id, origin.clone(), spender.clone());
+			let new_allowance = match allowance.checked_sub(&amount) {
+				Some(b) => b,
+				None => return Err("allowance below zero"),
+			};
+			Self::set_allowance(shard_code, id, origin, spender, new_allowance);
+		}
+
+		/// Move some of `owner`'s assets to `target` on behalf of the owner, spending the
+		/// allowance the owner granted to the origin. A `target` in another shard is credited
+		/// through the relay, just like `transfer`, once the nodes relay by the relay registry:
+		/// the legacy relay calls read the owner as the dest, so the target would never be credited.
+		fn transfer_from(origin,
+			shard_code: Vec<u8>,
+			#[compact] id: AssetId,
+			owner: <T::Lookup as StaticLookup>::Source,
+			target: <T::Lookup as StaticLookup>::Source,
+			#[compact] amount: T::Balance
+		) {
+			let origin = ensure_signed(origin)?;
+			let owner = T::Lookup::lookup(owner)?;
+			let target = T::Lookup::lookup(target)?;
+
+			ensure!(Self::is_in_current_shard(&owner), "owner must be in current shard");
+			ensure!(Self::is_in_current_shard(&target) || T::RelayActivation::relay_registry_active(),
+				"target must be in current shard before the relay registry fork");
+			let allowance_key = (shard_code.clone(), id, owner.clone(), origin);
+			let allowance = <Allowances<T>>::get(&allowance_key);
+			ensure!(allowance >= amount, "allowance must be greater than or equal to the transfer amount");
+
+			Self::transfer_asset(shard_code, id, owner, target, amount)?;
+			<Allowances<T>>::insert(allowance_key, allowance - amount);
+		}
+
+		/// Stop `who`, who must be in the current shard, from moving the origin's asset `id`.
+		/// Only the asset's issuer may freeze.
+		fn freeze(origin,
+			#[compact] id: AssetId,
+			who: <T::Lookup as StaticLookup>::Source
+		) {
+			let origin = ensure_signed(origin)?;
+			let shard_code = Self::ensure_issuer(&origin, id)?;
+			let who = T::Lookup::lookup(who)?;
+			ensure!(Self::is_in_current_shard(&who), "frozen account must be in current shard");
+
+			<FrozenAccounts<T>>::insert((shard_code.clone(), id, who.clone()), true);
+			Self::deposit_event(RawEvent::Frozen(shard_code, id, who));
+		}
+
+		/// Allow a frozen `who` to move the origin's asset `id` again.
+		fn thaw(origin,
+			#[compact] id: AssetId,
+			who: <T::Lookup as StaticLookup>::Source
+		) {
+			let origin = ensure_signed(origin)?;
+			let shard_code = Self::ensure_issuer(&origin, id)?;
+			let who = T::Lookup::lookup(who)?;
+
+			<FrozenAccounts<T>>::remove((shard_code.clone(), id, who.clone()));
+			Self::deposit_event(RawEvent::Thawed(shard_code, id, who));
+		}
+
+		/// Stop every holder in the current shard from moving the origin's asset `id`.
+		///
+		/// Freezing is shard local: units already relayed to other shards can still be moved there.
+		fn freeze_asset(origin, #[compact] id: AssetId) {
+			let origin = ensure_signed(origin)?;
+			let shard_code = Self::ensure_issuer(&origin, id)?;
+
+			<FrozenAssets<T>>::insert((shard_code.clone(), id), true);
+			Self::deposit_event(RawEvent::AssetFrozen(shard_code, id));
+		}
+
+		/// Resume transfers of the origin's asset `id`.
+		fn thaw_asset(origin, #[compact] id: AssetId) {
+			let origin = ensure_signed(origin)?;
+			let shard_code = Self::ensure_issuer(&origin, id)?;
+
+			<FrozenAssets<T>>::remove((shard_code.clone(), id));
+			Self::deposit_event(RawEvent::AssetThawed(shard_code, id));
 		}
 	}
 }
@@ -104,6 +265,26 @@ decl_event!(
 		Issued(Vec<u8>, u32, Vec<u8>, AccountId, Balance, u16),
 		/// Some assets were transferred.
 		Transferred(Vec<u8>, u32, AccountId, AccountId, Balance),
+		/// Some assets were minted by the issuer (shard_code, id, issuer, target, amount).
+		Minted(Vec<u8>, u32, AccountId, AccountId, Balance),
+		/// Some assets were burned by their holder (shard_code, id, holder, amount).
+		Burned(Vec<u8>, u32, AccountId, Balance),
+		/// An allowance was set (shard_code, id, owner, spender, allowance).
+		Approved(Vec<u8>, u32, AccountId, AccountId, Balance),
+		/// An account was frozen by the issuer (shard_code, id, who).
+		Frozen(Vec<u8>, u32, AccountId),
+		/// An account was thawed by the issuer (shard_code, id, who).
+		Thawed(Vec<u8>, u32, AccountId),
+		/// All transfers of an asset were paused (shard_code, id).
+		AssetFrozen(Vec<u8>, u32),
+		/// Transfers of an asset were resumed (shard_code, id).
+		AssetThawed(Vec<u8>, u32),
+		/// A relayed transfer credited a frozen account (shard_code, id, from, to, amount).
+		/// The units stay with the target and can only be moved after a thaw.
+		FrozenReceived(Vec<u8>, u32, AccountId, AccountId, Balance),
+		/// A cross shard transfer failed on the destination shard and was refunded
+		/// (shard_code, id, from, to, amount).
+		Refunded(Vec<u8>, u32, AccountId, AccountId, Balance),
 	}
 );
 
@@ -121,6 +302,14 @@ decl_storage! {
 		AssetsDecimals: map AssetId => Decimals;
 		/// The asset's issuer.
 		AssetsIssuer: map AssetId => T::AccountId;
+		/// The units of an owner's assets a spender may transfer: (shard_code, id, owner, spender).
+		Allowances: map (Vec<u8>, AssetId, T::AccountId, T::AccountId) => T::Balance;
+		/// The accounts which may not move an asset: (shard_code, id, who).
+		FrozenAccounts: map (Vec<u8>, AssetId, T::AccountId) => bool;
+		/// The assets which may not be moved at all: (shard_code, id).
+		FrozenAssets: map (Vec<u8>, AssetId) => bool;
+		/// Cross shard transfers already refunded, keyed by the hash of the origin extrinsic.
+		RelayRefunds get(relay_refunded): map T::Hash => bool;
 	}
 }
 
@@ -130,8 +319,7 @@ impl<T: Trait> Module<T> {
 		let id = Self::next_asset_id();
 		<NextAssetId<T>>::mutate(|id| *id += 1);
 
-		let issuer = origin.encode();
-		let shard_code = issuer[issuer.len() - SHARD_CODE_SIZE..].to_vec();
+		let shard_code = Self::shard_code_of(&origin);
 
 		<Balances<T>>::insert((shard_code.clone(), id, origin.clone()), total.clone());
 		<TotalSupply<T>>::insert(id, total.clone());
@@ -162,13 +350,102 @@ impl<T: Trait> Module<T> {
 	/// Get the issuer of an asset `id`
 	pub fn issuer(id: AssetId) -> T::AccountId { <AssetsIssuer<T>>::get(id) }
 
+	/// Get the units of `owner`'s asset `(shard_code, id)` that `spender` may transfer
+	pub fn allowance(shard_code: Vec<u8>, id: AssetId, owner: T::AccountId, spender: T::AccountId) -> T::Balance {
+		<Allowances<T>>::get((shard_code, id, owner, spender))
+	}
+
+	fn set_allowance(shard_code: Vec<u8>, id: AssetId, owner: T::AccountId, spender: T::AccountId, amount: T::Balance) {
+		<Allowances<T>>::insert((shard_code.clone(), id, owner.clone(), spender.clone()), amount);
+
+		// event
+		Self::deposit_event(RawEvent::Approved(shard_code, id, owner, spender, amount));
+	}
+
+	/// Move `amount` of asset `(shard_code, id)` from `from` to `target`. A `target` in another
+	/// shard is only debited here; it is credited by `relay_transfer` on its own shard.
+	fn transfer_asset(shard_code: Vec<u8>, id: AssetId, from: T::AccountId, target: T::AccountId, amount: T::Balance) -> Result {
+		let from_account = (shard_code.clone(), id, from.clone());
+		let from_balance = <Balances<T>>::get(&from_account);
+
+		ensure!(!amount.is_zero(), "transfer amount should be non-zero");
+		ensure!(from_balance >= amount, "origin account balance must be greater than or equal to the transfer amount");
+		Self::ensure_not_frozen(&shard_code, id, &from)?;
+
+		// in same sharding
+		if Self::is_in_current_shard(&target) {
+			let to_account = (shard_code.clone(), id, target.clone());
+			let to_balance = if to_account == from_account { from_balance - amount } else { <Balances<T>>::get(&to_account) };
+			let new_to_balance = match to_balance.checked_add(&amount) {
+				Some(b) => b,
+				None => return Err("destination balance too high to receive value"),
+			};
+			<Balances<T>>::insert(&from_account, from_balance - amount);
+			<Balances<T>>::insert(to_account, new_to_balance);
+		} else {
+			<Balances<T>>::insert(from_account, from_balance - amount);
+		}
+
+		// event
+		Self::deposit_event(RawEvent::Transferred(shard_code, id, from, target, amount));
+		Ok(())
+	}
+
+	/// Whether `who` may not move asset `(shard_code, id)`
+	pub fn is_frozen(shard_code: Vec<u8>, id: AssetId, who: T::AccountId) -> bool {
+		<FrozenAssets<T>>::get((shard_code.clone(), id)) || <FrozenAccounts<T>>::get((shard_code, id, who))
+	}
+
+	fn ensure_not_frozen(shard_code: &Vec<u8>, id: AssetId, who: &T::AccountId) -> Result {
+		ensure!(!<FrozenAssets<T>>::get((shard_code.clone(), id)), "asset is frozen");
+		ensure!(!<FrozenAccounts<T>>::get((shard_code.clone(), id, who.clone())), "account is frozen");
+		Ok(())
+	}
+
+	/// Check `who` issued asset `id` on this shard, returning the asset's shard code
+	fn ensure_issuer(who: &T::AccountId, id: AssetId) -> rstd::result::Result<Vec<u8>, &'static str> {
+		ensure!(<AssetsIssuer<T>>::exists(id), "asset not exists");
+		ensure!(&Self::issuer(id) == who, "only the issuer can manage the asset");
+		Ok(Self::shard_code_of(who))
+	}
+
+	/// Get the shard code of an account
+	fn shard_code_of(who: &T::AccountId) -> Vec<u8> {
+		let encoded = who.encode();
+		encoded[encoded.len() - SHARD_CODE_SIZE..].to_vec()
+	}
+
+	/// Whether the asset `(shard_code, id)` was issued on this shard
+	fn is_native_asset(shard_code: &Vec<u8>, id: AssetId) -> bool {
+		<AssetsIssuer<T>>::exists(id) && &Self::shard_code_of(&Self::issuer(id)) == shard_code
+	}
+
+	/// Check the asset `(shard_code, id)` of a relayed transfer exists, failing the relay so that
+	/// it is refunded. Only the assets issued on this shard are known here, the ones of other
+	/// shards are accepted as they are.
+	fn ensure_known_asset(shard_code: &Vec<u8>, id: AssetId) -> Result {
+		ensure!(shard_code.len() == SHARD_CODE_SIZE, "invalid shard code");
+		let (cn, c) = (T::Sharding::get_curr_shard().expect("can't get current shard num").as_() as u16, T::Sharding::get_shard_count().as_() as u16);
+		if sharding_primitives::utils::shard_num_for_bytes(shard_code, c) == Some(cn) {
+			ensure!(Self::is_native_asset(shard_code, id), "asset is unknown");
+		}
+		Ok(())
+	}
+
+	/// Whether `who` belongs to the current shard
+	fn is_in_current_shard(who: &T::AccountId) -> bool {
+		let (cn, c) = (T::Sharding::get_curr_shard().expect("can't get current shard num").as_() as u16, T::Sharding::get_shard_count().as_() as u16);
+		sharding_primitives::utils::shard_num_for(who, c) == Some(cn)
+	}
+
 	/// relay transfer
-	pub fn relay_transfer(input: Vec<u8>) -> srml_support::dispatch::Result {
-		if let Some(tx) = OriginExtrinsic::<T::AccountId, T::Balance>::decode(RelayTypes::Assets, input) {
+	pub fn relay_transfer(registry: &[RelayCallInfo], input: Vec<u8>) -> srml_support::dispatch::Result {
+		if let Some(tx) = OriginExtrinsic::<T::AccountId, T::Balance>::decode(registry, input) {
 			let asset_id =  match tx.asset_id(){
 				Some(asset_id) => asset_id,
 				None => return Err("invalid asset id"),
 			};
+			Self::ensure_known_asset(&tx.shard_code(), asset_id)?;
 
 			let to_account =(tx.shard_code(), asset_id, tx.to());
 			let to_balance = <Balances<T>>::get(&to_account);
@@ -178,12 +455,44 @@ impl<T: Trait> Module<T> {
 			};
 			<Balances<T>>::insert(to_account, new_to_balance);
 
-			Self::deposit_event(RawEvent::Transferred(tx.shard_code(), asset_id, tx.from(), tx.to(), tx.amount()));
+			// The origin shard has already debited the sender, so a frozen target is still
+			// credited; the freeze keeps the units in place until the issuer thaws them.
+			if Self::is_frozen(tx.shard_code(), asset_id, tx.to()) {
+				Self::deposit_event(RawEvent::FrozenReceived(tx.shard_code(), asset_id, tx.from(), tx.to(), tx.amount()));
+			} else {
+				Self::deposit_event(RawEvent::Transferred(tx.shard_code(), asset_id, tx.from(), tx.to(), tx.amount()));
+			}
 			Ok(())
 		} else{
 			Err("transfer is invalid.")
 		}
 	}
+
+	/// Return the units of a cross shard transfer which failed on the destination shard.
+	/// A `transfer_from` is refunded to the owner, its spent allowance is not restored.
+	pub fn relay_refund(registry: &[RelayCallInfo], input: Vec<u8>) -> Result {
+		let hash = T::Hashing::hash(&input);
+		ensure!(!Self::relay_refunded(&hash), "transfer is already refunded.");
+		let tx = match OriginExtrinsic::<T::AccountId, T::Balance>::decode(registry, input) {
+			Some(tx) => tx,
+			None => return Err("transfer is invalid."),
+		};
+		let asset_id = match tx.asset_id() {
+			Some(asset_id) => asset_id,
+			None => return Err("invalid asset id"),
+		};
+
+		let from_account = (tx.shard_code(), asset_id, tx.from());
+		let new_from_balance = match <Balances<T>>::get(&from_account).checked_add(&tx.amount()) {
+			Some(b) => b,
+			None => return Err("origin balance too high to receive refund"),
+		};
+		<Balances<T>>::insert(from_account, new_from_balance);
+		<RelayRefunds<T>>::insert(hash, true);
+
+		Self::deposit_event(RawEvent::Refunded(tx.shard_code(), asset_id, tx.from(), tx.to(), tx.amount()));
+		Ok(())
+	}
 }
 
 #[cfg(test)]
@@ -193,6 +502,9 @@ mod tests {
 	use runtime_io::with_externalities;
 	use srml_support::{impl_outer_origin, assert_ok, assert_noop};
 	use substrate_primitives::{H256, Blake2Hasher};
+	use std::cell::Cell;
+	use parity_codec::Compact;
+	use yee_sr_primitives::RelayTypes;
 	// The testing primitives are very useful for avoiding having to work with signatures
 	// or public keys. `u64` is used as the `AccountId` and no `Signature`s are required.
 	use primitives::{
@@ -223,10 +535,43 @@ mod tests {
 		type Event = ();
 		type Log = DigestItem;
 	}
+
+	/// Sharding logs are never deposited in these tests.
+	pub struct TestLog;
+	impl From<sharding::Log<Test>> for TestLog {
+		fn from(_: sharding::Log<Test>) -> Self { TestLog }
+	}
+	impl From<TestLog> for DigestItem {
+		fn from(_: TestLog) -> Self { DigestItem::Other(Vec::new()) }
+	}
+	impl sharding::Trait for Test {
+		type ShardNum = u16;
+		type Log = TestLog;
+	}
+
+	thread_local! {
+		static SHARD_COUNT: Cell<u16> = Cell::new(1);
+		static REGISTRY_ACTIVE: Cell<bool> = Cell::new(false);
+	}
+
+	/// Shard 0 of a single shard network, unless a test sets `SHARD_COUNT`.
+	pub struct TestSharding;
+	impl ShardingInfo<u16> for TestSharding {
+		fn get_genesis_shard_count() -> u16 { 1 }
+		fn get_curr_shard() -> Option<u16> { Some(0) }
+		fn get_shard_count() -> u16 { SHARD_COUNT.with(|c| c.get()) }
+	}
+
+	pub struct TestRelayActivation;
+	impl RelayRegistryActivation for TestRelayActivation {
+		fn relay_registry_active() -> bool { REGISTRY_ACTIVE.with(|a| a.get()) }
+	}
+
 	impl Trait for Test {
 		type Event = ();
 		type Balance = u64;
-		type Sharding = ();
+		type Sharding = TestSharding;
+		type RelayActivation = TestRelayActivation;
 	}
 	type Assets = Module<Test>;
 
@@ -236,27 +581,36 @@ mod tests {
 		system::GenesisConfig::<Test>::default().build_storage().unwrap().0.into()
 	}
 
+	/// The shard code of the `u64` test accounts used below.
+	fn code() -> Vec<u8> {
+		vec![0, 0]
+	}
+
+	fn issue() {
+		assert_ok!(Assets::issue(Origin::signed(1), b"TEST".to_vec(), 100, 2));
+	}
+
 	#[test]
 	fn issuing_asset_units_to_issuer_should_work() {
 		with_externalities(&mut new_test_ext(), || {
-			assert_ok!(Assets::issue(Origin::signed(1), 100));
-			assert_eq!(Assets::balance(0, 1), 100);
+			assert_ok!(Assets::issue(Origin::signed(1), b"TEST".to_vec(), 100, 2));
+			assert_eq!(Assets::balance(code(), 0, 1), 100);
 		});
 	}
 
 	#[test]
 	fn querying_total_supply_should_work() {
 		with_externalities(&mut new_test_ext(), || {
-			assert_ok!(Assets::issue(Origin::signed(1), 100));
-			assert_eq!(Assets::balance(0, 1), 100);
-			assert_ok!(Assets::transfer(Origin::signed(1), 0, 2, 50));
-			assert_eq!(Assets::balance(0, 1), 50);
-			assert_eq!(Assets::balance(0, 2), 50);
-			assert_ok!(Assets::transfer(Origin::signed(2), 0, 3, 31));
-			assert_eq!(Assets::balance(0, 1), 50);
-			assert_eq!(Assets::balance(0, 2), 19);
-			assert_eq!(Assets::balance(0, 3), 31);
-			assert_ok!(Assets::destroy(Origin::signed(3), 0));
+			assert_ok!(Assets::issue(Origin::signed(1), b"TEST".to_vec(), 100, 2));
+			assert_eq!(Assets::balance(code(), 0, 1), 100);
+			assert_ok!(Assets::transfer(Origin::signed(1), code(), 0, 2, 50));
+			assert_eq!(Assets::balance(code(), 0, 1), 50);
+			assert_eq!(Assets::balance(code(), 0, 2), 50);
+			assert_ok!(Assets::transfer(Origin::signed(2), code(), 0, 3, 31));
+			assert_eq!(Assets::balance(code(), 0, 1), 50);
+			assert_eq!(Assets::balance(code(), 0, 2), 19);
+			assert_eq!(Assets::balance(code(), 0, 3), 31);
+			assert_ok!(Assets::burn(Origin::signed(3), code(), 0, 31));
 			assert_eq!(Assets::total_supply(0), 69);
 		});
 	}
@@ -264,61 +618,292 @@ mod tests {
 	#[test]
 	fn transferring_amount_above_available_balance_should_work() {
 		with_externalities(&mut new_test_ext(), || {
-			assert_ok!(Assets::issue(Origin::signed(1), 100));
-			assert_eq!(Assets::balance(0, 1), 100);
-			assert_ok!(Assets::transfer(Origin::signed(1), 0, 2, 50));
-			assert_eq!(Assets::balance(0, 1), 50);
-			assert_eq!(Assets::balance(0, 2), 50);
+			assert_ok!(Assets::issue(Origin::signed(1), b"TEST".to_vec(), 100, 2));
+			assert_eq!(Assets::balance(code(), 0, 1), 100);
+			assert_ok!(Assets::transfer(Origin::signed(1), code(), 0, 2, 50));
+			assert_eq!(Assets::balance(code(), 0, 1), 50);
+			assert_eq!(Assets::balance(code(), 0, 2), 50);
 		});
 	}
 
 	#[test]
 	fn transferring_amount_less_than_available_balance_should_not_work() {
 		with_externalities(&mut new_test_ext(), || {
-			assert_ok!(Assets::issue(Origin::signed(1), 100));
-			assert_eq!(Assets::balance(0, 1), 100);
-			assert_ok!(Assets::transfer(Origin::signed(1), 0, 2, 50));
-			assert_eq!(Assets::balance(0, 1), 50);
-			assert_eq!(Assets::balance(0, 2), 50);
-			assert_ok!(Assets::destroy(Origin::signed(1), 0));
-			assert_eq!(Assets::balance(0, 1), 0);
-			assert_noop!(Assets::transfer(Origin::signed(1), 0, 1, 50), "origin account balance must be greater than or equal to the transfer amount");
+			assert_ok!(Assets::issue(Origin::signed(1), b"TEST".to_vec(), 100, 2));
+			assert_eq!(Assets::balance(code(), 0, 1), 100);
+			assert_ok!(Assets::transfer(Origin::signed(1), code(), 0, 2, 50));
+			assert_eq!(Assets::balance(code(), 0, 1), 50);
+			assert_eq!(Assets::balance(code(), 0, 2), 50);
+			assert_ok!(Assets::burn(Origin::signed(1), code(), 0, 50));
+			assert_eq!(Assets::balance(code(), 0, 1), 0);
+			assert_noop!(Assets::transfer(Origin::signed(1), code(), 0, 1, 50), "origin account balance must be greater than or equal to the transfer amount");
 		});
 	}
 
 	#[test]
 	fn transferring_less_than_one_unit_should_not_work() {
 		with_externalities(&mut new_test_ext(), || {
-			assert_ok!(Assets::issue(Origin::signed(1), 100));
-			assert_eq!(Assets::balance(0, 1), 100);
-			assert_noop!(Assets::transfer(Origin::signed(1), 0, 2, 0), "transfer amount should be non-zero");
+			assert_ok!(Assets::issue(Origin::signed(1), b"TEST".to_vec(), 100, 2));
+			assert_eq!(Assets::balance(code(), 0, 1), 100);
+			assert_noop!(Assets::transfer(Origin::signed(1), code(), 0, 2, 0), "transfer amount should be non-zero");
 		});
 	}
 
 	#[test]
 	fn transferring_more_units_than_total_supply_should_not_work() {
 		with_externalities(&mut new_test_ext(), || {
-			assert_ok!(Assets::issue(Origin::signed(1), 100));
-			assert_eq!(Assets::balance(0, 1), 100);
-			assert_noop!(Assets::transfer(Origin::signed(1), 0, 2, 101), "origin account balance must be greater than or equal to the transfer amount");
+			assert_ok!(Assets::issue(Origin::signed(1), b"TEST".to_vec(), 100, 2));
+			assert_eq!(Assets::balance(code(), 0, 1), 100);
+			assert_noop!(Assets::transfer(Origin::signed(1), code(), 0, 2, 101), "origin account balance must be greater than or equal to the transfer amount");
 		});
 	}
 
 	#[test]
 	fn destroying_asset_balance_with_positive_balance_should_work() {
 		with_externalities(&mut new_test_ext(), || {
-			assert_ok!(Assets::issue(Origin::signed(1), 100));
-			assert_eq!(Assets::balance(0, 1), 100);
-			assert_ok!(Assets::destroy(Origin::signed(1), 0));
+			assert_ok!(Assets::issue(Origin::signed(1), b"TEST".to_vec(), 100, 2));
+			assert_eq!(Assets::balance(code(), 0, 1), 100);
+			assert_ok!(Assets::burn(Origin::signed(1), code(), 0, 100));
 		});
 	}
 
 	#[test]
 	fn destroying_asset_balance_with_zero_balance_should_not_work() {
 		with_externalities(&mut new_test_ext(), || {
-			assert_ok!(Assets::issue(Origin::signed(1), 100));
-			assert_eq!(Assets::balance(0, 2), 0);
-			assert_noop!(Assets::destroy(Origin::signed(2), 0), "origin balance should be non-zero");
+			assert_ok!(Assets::issue(Origin::signed(1), b"TEST".to_vec(), 100, 2));
+			assert_eq!(Assets::balance(code(), 0, 2), 0);
+			assert_noop!(Assets::burn(Origin::signed(2), code(), 0, 1), "origin account balance must be greater than or equal to the burn amount");
+		});
+	}
+
+	#[test]
+	fn minting_by_issuer_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			issue();
+			assert_ok!(Assets::mint(Origin::signed(1), 0, 2, 40));
+			assert_eq!(Assets::balance(code(), 0, 2), 40);
+			assert_eq!(Assets::total_supply(0), 140);
+		});
+	}
+
+	#[test]
+	fn minting_by_non_issuer_should_not_work() {
+		with_externalities(&mut new_test_ext(), || {
+			issue();
+			assert_noop!(Assets::mint(Origin::signed(2), 0, 2, 40), "only the issuer can manage the asset");
+			assert_noop!(Assets::mint(Origin::signed(1), 1, 2, 40), "asset not exists");
+			assert_noop!(Assets::mint(Origin::signed(1), 0, 2, 0), "mint amount should be non-zero");
+		});
+	}
+
+	#[test]
+	fn minting_beyond_max_supply_should_not_work() {
+		with_externalities(&mut new_test_ext(), || {
+			issue();
+			assert_noop!(Assets::mint(Origin::signed(1), 0, 2, u64::max_value()), "total supply too high to mint value");
+		});
+	}
+
+	#[test]
+	fn burning_should_reduce_total_supply() {
+		with_externalities(&mut new_test_ext(), || {
+			issue();
+			assert_ok!(Assets::burn(Origin::signed(1), code(), 0, 40));
+			assert_eq!(Assets::balance(code(), 0, 1), 60);
+			assert_eq!(Assets::total_supply(0), 60);
+			assert_noop!(Assets::burn(Origin::signed(1), code(), 0, 0), "burn amount should be non-zero");
+		});
+	}
+
+	#[test]
+	fn burning_foreign_asset_should_not_work() {
+		with_externalities(&mut new_test_ext(), || {
+			issue();
+			// asset 0 relayed in from a shard with another shard code
+			let foreign = vec![1, 0];
+			<Balances<Test>>::insert((foreign.clone(), 0, 2), 30);
+			assert_noop!(Assets::burn(Origin::signed(2), foreign.clone(), 0, 10), "only assets issued on this shard can be burned");
+			assert_eq!(Assets::balance(foreign, 0, 2), 30);
+			assert_eq!(Assets::total_supply(0), 100);
+		});
+	}
+
+	#[test]
+	fn approving_and_adjusting_allowance_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			issue();
+			assert_ok!(Assets::approve(Origin::signed(1), code(), 0, 2, 30));
+			assert_eq!(Assets::allowance(code(), 0, 1, 2), 30);
+			assert_ok!(Assets::increase_allowance(Origin::signed(1), code(), 0, 2, 20));
+			assert_eq!(Assets::allowance(code(), 0, 1, 2), 50);
+			assert_ok!(Assets::decrease_allowance(Origin::signed(1), code(), 0, 2, 45));
+			assert_eq!(Assets::allowance(code(), 0, 1, 2), 5);
+			assert_noop!(Assets::decrease_allowance(Origin::signed(1), code(), 0, 2, 6), "allowance below zero");
+			assert_ok!(Assets::approve(Origin::signed(1), code(), 0, 2, 0));
+			assert_eq!(Assets::allowance(code(), 0, 1, 2), 0);
+		});
+	}
+
+	#[test]
+	fn transferring_from_within_allowance_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			issue();
+			assert_ok!(Assets::approve(Origin::signed(1), code(), 0, 2, 30));
+			assert_ok!(Assets::transfer_from(Origin::signed(2), code(), 0, 1, 3, 20));
+			assert_eq!(Assets::balance(code(), 0, 1), 80);
+			assert_eq!(Assets::balance(code(), 0, 2), 0);
+			assert_eq!(Assets::balance(code(), 0, 3), 20);
+			assert_eq!(Assets::allowance(code(), 0, 1, 2), 10);
+		});
+	}
+
+	#[test]
+	fn transferring_from_beyond_allowance_should_not_work() {
+		with_externalities(&mut new_test_ext(), || {
+			issue();
+			assert_ok!(Assets::approve(Origin::signed(1), code(), 0, 2, 30));
+			assert_noop!(Assets::transfer_from(Origin::signed(2), code(), 0, 1, 3, 31), "allowance must be greater than or equal to the transfer amount");
+			assert_noop!(Assets::transfer_from(Origin::signed(3), code(), 0, 1, 3, 1), "allowance must be greater than or equal to the transfer amount");
+		});
+	}
+
+	#[test]
+	fn transferring_from_beyond_owner_balance_should_not_spend_allowance() {
+		with_externalities(&mut new_test_ext(), || {
+			issue();
+			assert_ok!(Assets::approve(Origin::signed(1), code(), 0, 2, 500));
+			assert_noop!(Assets::transfer_from(Origin::signed(2), code(), 0, 1, 3, 101), "origin account balance must be greater than or equal to the transfer amount");
+			assert_eq!(Assets::allowance(code(), 0, 1, 2), 500);
+		});
+	}
+
+	#[test]
+	fn frozen_account_should_not_transfer() {
+		with_externalities(&mut new_test_ext(), || {
+			issue();
+			assert_ok!(Assets::transfer(Origin::signed(1), code(), 0, 2, 50));
+			assert_noop!(Assets::freeze(Origin::signed(2), 0, 2), "only the issuer can manage the asset");
+			assert_ok!(Assets::freeze(Origin::signed(1), 0, 2));
+			assert!(Assets::is_frozen(code(), 0, 2));
+			assert_noop!(Assets::transfer(Origin::signed(2), code(), 0, 3, 10), "account is frozen");
+			assert_noop!(Assets::burn(Origin::signed(2), code(), 0, 10), "account is frozen");
+			// a frozen account can still receive
+			assert_ok!(Assets::transfer(Origin::signed(1), code(), 0, 2, 10));
+			assert_eq!(Assets::balance(code(), 0, 2), 60);
+			assert_ok!(Assets::thaw(Origin::signed(1), 0, 2));
+			assert_ok!(Assets::transfer(Origin::signed(2), code(), 0, 3, 10));
+			assert_eq!(Assets::balance(code(), 0, 3), 10);
+		});
+	}
+
+	#[test]
+	fn frozen_owner_should_not_transfer_from() {
+		with_externalities(&mut new_test_ext(), || {
+			issue();
+			assert_ok!(Assets::approve(Origin::signed(1), code(), 0, 2, 30));
+			assert_ok!(Assets::freeze(Origin::signed(1), 0, 1));
+			assert_noop!(Assets::transfer_from(Origin::signed(2), code(), 0, 1, 3, 10), "account is frozen");
+			assert_eq!(Assets::allowance(code(), 0, 1, 2), 30);
+		});
+	}
+
+	#[test]
+	fn transferring_from_to_other_shard_should_wait_for_relay_registry() {
+		with_externalities(&mut new_test_ext(), || {
+			SHARD_COUNT.with(|c| c.set(2));
+			// the last two bytes of the account give its shard
+			let foreign = 1u64 << 56;
+			issue();
+			assert_ok!(Assets::approve(Origin::signed(1), code(), 0, 2, 30));
+			assert_noop!(Assets::transfer_from(Origin::signed(2), code(), 0, 1, foreign, 10),
+				"target must be in current shard before the relay registry fork");
+			assert_eq!(Assets::balance(code(), 0, 1), 100);
+			assert_eq!(Assets::allowance(code(), 0, 1, 2), 30);
+			assert_ok!(Assets::transfer_from(Origin::signed(2), code(), 0, 1, 3, 10));
+
+			REGISTRY_ACTIVE.with(|a| a.set(true));
+			assert_ok!(Assets::transfer_from(Origin::signed(2), code(), 0, 1, foreign, 10));
+			assert_eq!(Assets::balance(code(), 0, 1), 80);
+			assert_eq!(Assets::balance(code(), 0, 3), 10);
+			assert_eq!(Assets::allowance(code(), 0, 1, 2), 10);
+		});
+	}
+
+	#[test]
+	fn frozen_asset_should_not_transfer() {
+		with_externalities(&mut new_test_ext(), || {
+			issue();
+			assert_ok!(Assets::transfer(Origin::signed(1), code(), 0, 2, 50));
+			assert_ok!(Assets::freeze_asset(Origin::signed(1), 0));
+			assert!(Assets::is_frozen(code(), 0, 3));
+			assert_noop!(Assets::transfer(Origin::signed(1), code(), 0, 3, 10), "asset is frozen");
+			assert_noop!(Assets::transfer(Origin::signed(2), code(), 0, 3, 10), "asset is frozen");
+			assert_ok!(Assets::thaw_asset(Origin::signed(1), 0));
+			assert_ok!(Assets::transfer(Origin::signed(2), code(), 0, 3, 10));
+		});
+	}
+
+	/// The relay call of `transfer` in the tests' registry
+	fn registry() -> Vec<RelayCallInfo> {
+		vec![RelayCallInfo { module: 8, call: 1, relay_type: RelayTypes::Assets, delegated: false }]
+	}
+
+	/// A signed `transfer` of `amount` units of asset 0 from `from` to `to`, as relayed
+	fn origin_transfer(from: u64, to: u64, amount: u64) -> Vec<u8> {
+		let mut tx = vec![0b1000_0001, 0xff];
+		from.encode_to(&mut tx);
+		tx.extend_from_slice(&[0u8; 64]);
+		Compact(0u64).encode_to(&mut tx);
+		tx.push(0);
+		tx.extend_from_slice(&[8, 1]);
+		code().encode_to(&mut tx);
+		Compact(0u32).encode_to(&mut tx);
+		tx.push(0xff);
+		to.encode_to(&mut tx);
+		Compact(amount as u128).encode_to(&mut tx);
+		tx.encode()
+	}
+
+	#[test]
+	fn relaying_transfer_of_unknown_asset_should_not_work() {
+		with_externalities(&mut new_test_ext(), || {
+			let tx = origin_transfer(1, 2, 40);
+			assert_noop!(Assets::relay_transfer(&registry(), tx.clone()), "asset is unknown");
+			issue();
+			assert_ok!(Assets::relay_transfer(&registry(), tx));
+			assert_eq!(Assets::balance(code(), 0, 2), 40);
+		});
+	}
+
+	#[test]
+	fn refunding_failed_relay_transfer_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			issue();
+			let tx = origin_transfer(1, 2, 40);
+			assert_ok!(Assets::relay_refund(&registry(), tx.clone()));
+			assert_eq!(Assets::balance(code(), 0, 1), 140);
+			assert!(Assets::relay_refunded(BlakeTwo256::hash(&tx)));
+		});
+	}
+
+	#[test]
+	fn refunding_relay_transfer_twice_should_not_work() {
+		with_externalities(&mut new_test_ext(), || {
+			issue();
+			let tx = origin_transfer(1, 2, 40);
+			assert_ok!(Assets::relay_refund(&registry(), tx.clone()));
+			assert_noop!(Assets::relay_refund(&registry(), tx), "transfer is already refunded.");
+			assert_eq!(Assets::balance(code(), 0, 1), 140);
+		});
+	}
+
+	#[test]
+	fn refunding_unknown_relay_call_should_not_work() {
+		with_externalities(&mut new_test_ext(), || {
+			issue();
+			let tx = origin_transfer(1, 2, 40);
+			assert_noop!(Assets::relay_refund(&[], tx.clone()), "transfer is invalid.");
+			assert!(!Assets::relay_refunded(BlakeTwo256::hash(&tx)));
 		});
 	}
 }
@@ -185,7 +185,7 @@ use srml_support::dispatch::Result;
 use primitives::{
     traits::{
         Zero, SimpleArithmetic, As, StaticLookup, Member, CheckedAdd, CheckedSub,
-        MaybeSerializeDebug, Saturating,
+        MaybeSerializeDebug, Saturating, Hash,
     }};
 use system::{IsDeadAccount, OnNewAccount, ensure_signed};
 use {
@@ -198,7 +198,7 @@ mod mock;
 mod tests;
 
 //use decode::OriginTransfer;
-use yee_sr_primitives::{OriginExtrinsic, RelayTypes};
+use yee_sr_primitives::{OriginExtrinsic, RelayCallInfo};
 
 pub use self::imbalances::{PositiveImbalance, NegativeImbalance};
 
@@ -271,6 +271,8 @@ decl_event!(
 		ReapedAccount(AccountId),
 		/// Transfer succeeded (from, to, value, fees).
 		Transfer(AccountId, AccountId, Balance, Balance),
+		/// A cross shard transfer failed on the destination shard and was refunded (from, to, value).
+		Refunded(AccountId, AccountId, Balance),
 	}
 );
 
@@ -371,6 +373,9 @@ decl_storage! {
 
 		/// Any liquidity locks on some account balances.
 		pub Locks get(locks): map T::AccountId => Vec<BalanceLock<T::Balance, T::BlockNumber>>;
+
+		/// Cross shard transfers already refunded, keyed by the hash of the origin extrinsic.
+		pub RelayRefunds get(relay_refunded): map T::Hash => bool;
 	}
 	add_extra_genesis {
 		config(balances): Vec<(T::AccountId, T::Balance)>;
@@ -513,16 +518,19 @@ impl<T: Trait<I>, I: Instance> Module<T, I> {
     }
 
     /// execute relay transfer
-    pub fn relay_transfer(transfer: Vec<u8>) -> Result {
-        let tx: OriginExtrinsic<T::AccountId, T::Balance> = OriginExtrinsic::decode(RelayTypes::Balance, transfer).unwrap();
-        if !<FreeBalance<T, I>>::exists(tx.to()) {
-            Self::new_account(&tx.to(), tx.amount());
-        }
+    pub fn relay_transfer(registry: &[RelayCallInfo], transfer: Vec<u8>) -> Result {
+        let tx: OriginExtrinsic<T::AccountId, T::Balance> = match OriginExtrinsic::decode(registry, transfer) {
+            Some(tx) => tx,
+            None => return Err("transfer is invalid."),
+        };
         let to_balance = Self::free_balance(&tx.to());
         let to_balance = match to_balance.checked_add(&tx.amount()) {
             Some(b) => b,
             None => return Err("destination balance too high to receive value"),
         };
+        if !<FreeBalance<T, I>>::exists(tx.to()) {
+            Self::new_account(&tx.to(), tx.amount());
+        }
         Self::set_free_balance(&tx.to(), to_balance);
         Self::deposit_event(RawEvent::Transfer(
             tx.from(),
@@ -532,6 +540,30 @@ impl<T: Trait<I>, I: Instance> Module<T, I> {
         ));
         Ok(())
     }
+
+    /// Return the value of a cross shard transfer which failed on the destination shard
+    pub fn relay_refund(registry: &[RelayCallInfo], transfer: Vec<u8>) -> Result {
+        let hash = T::Hashing::hash(&transfer);
+        if Self::relay_refunded(&hash) {
+            return Err("transfer is already refunded.");
+        }
+        let tx: OriginExtrinsic<T::AccountId, T::Balance> = match OriginExtrinsic::decode(registry, transfer) {
+            Some(tx) => tx,
+            None => return Err("transfer is invalid."),
+        };
+        let from_balance = match Self::free_balance(&tx.from()).checked_add(&tx.amount()) {
+            Some(b) => b,
+            None => return Err("origin balance too high to receive refund"),
+        };
+        if !<FreeBalance<T, I>>::exists(tx.from()) {
+            Self::new_account(&tx.from(), tx.amount());
+        }
+        Self::set_free_balance(&tx.from(), from_balance);
+        <RelayRefunds<T, I>>::insert(hash, true);
+
+        Self::deposit_event(RawEvent::Refunded(tx.from(), tx.to(), tx.amount()));
+        Ok(())
+    }
 }
 
 // wrapping these imbalanes in a private module is necessary to ensure absolute privacy
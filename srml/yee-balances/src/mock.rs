@@ -20,7 +20,8 @@
 #![cfg(test)]
 
 use primitives::BuildStorage;
-use primitives::{traits::IdentityLookup, testing::{Digest, DigestItem, Header}};
+use primitives::{traits::{IdentityLookup, StaticLookup}, testing::{Digest, DigestItem, Header}};
+use parity_codec::{Encode, Decode};
 use substrate_primitives::{H256, Blake2Hasher};
 use runtime_io;
 use srml_support::impl_outer_origin;
@@ -30,6 +31,10 @@ impl_outer_origin! {
 	pub enum Origin for Runtime {}
 }
 
+impl_outer_origin! {
+	pub enum RelayOrigin for RelayRuntime {}
+}
+
 // Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Runtime;
@@ -59,6 +64,59 @@ impl Trait for Runtime {
     type Sharding = Module<Runtime>;
 }
 
+/// `Runtime` with the address format of the chain, to build the calls relayed from other shards
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RelayRuntime;
+
+/// Address of the chain: `0xff ++ account id`
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub enum Address {
+    #[codec(index = "255")]
+    Id(u64),
+}
+
+pub struct AddressLookup;
+
+impl StaticLookup for AddressLookup {
+    type Source = Address;
+    type Target = u64;
+
+    fn lookup(address: Address) -> Result<u64, &'static str> {
+        match address {
+            Address::Id(id) => Ok(id),
+        }
+    }
+
+    fn unlookup(id: u64) -> Address {
+        Address::Id(id)
+    }
+}
+
+impl system::Trait for RelayRuntime {
+    type Origin = RelayOrigin;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = ::primitives::traits::BlakeTwo256;
+    type Digest = Digest;
+    type AccountId = u64;
+    type Lookup = AddressLookup;
+    type Header = Header;
+    type Event = ();
+    type Log = DigestItem;
+}
+
+impl Trait for RelayRuntime {
+    type Balance = u64;
+    type OnFreeBalanceZero = ();
+    type OnNewAccount = ();
+    type Event = ();
+    type TransactionPayment = ();
+    type DustRemoval = ();
+    type TransferPayment = ();
+    type Sharding = Module<RelayRuntime>;
+}
+
 pub struct ExtBuilder {
     transaction_base_fee: u64,
     transaction_byte_fee: u64,
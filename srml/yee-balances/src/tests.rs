@@ -20,7 +20,9 @@
 #![cfg(test)]
 
 use super::*;
-use mock::{Balances, ExtBuilder, Runtime, System};
+use mock::{Address, Balances, ExtBuilder, RelayRuntime, Runtime, System};
+use primitives::generic::{Era, UncheckedMortalCompactExtrinsic};
+use substrate_primitives::H512;
 use runtime_io::with_externalities;
 use srml_support::{
     assert_noop, assert_ok, assert_err,
@@ -622,3 +624,44 @@ fn extra_balance_should_transfer() {
         },
     );
 }
+
+/// The relay call of `transfer` in the tests' registry
+fn registry() -> Vec<RelayCallInfo> {
+    vec![RelayCallInfo { module: 4, call: 0, relay_type: yee_sr_primitives::RelayTypes::Balance, delegated: false }]
+}
+
+/// A signed `transfer` of `value` from `from` to `to`, as relayed
+fn origin_transfer(from: u64, to: u64, value: u64) -> Vec<u8> {
+    // the balances module is the 5th module of the runtime
+    let call = (4u8, Call::<RelayRuntime>::transfer(Address::Id(to), value));
+    UncheckedMortalCompactExtrinsic::<Address, u64, _, H512>::new_signed(0, call, Address::Id(from), H512::zero(), Era::Immortal).encode()
+}
+
+#[test]
+fn refunding_failed_relay_transfer_should_work() {
+    with_externalities(&mut ExtBuilder::default().existential_deposit(1).monied(true).build(), || {
+        let tx = origin_transfer(1, 2, 5);
+        assert_ok!(Balances::relay_refund(&registry(), tx.clone()));
+        assert_eq!(Balances::free_balance(&1), 15);
+        assert!(Balances::relay_refunded(<Runtime as system::Trait>::Hashing::hash(&tx)));
+    });
+}
+
+#[test]
+fn refunding_relay_transfer_twice_should_not_work() {
+    with_externalities(&mut ExtBuilder::default().existential_deposit(1).monied(true).build(), || {
+        let tx = origin_transfer(1, 2, 5);
+        assert_ok!(Balances::relay_refund(&registry(), tx.clone()));
+        assert_noop!(Balances::relay_refund(&registry(), tx), "transfer is already refunded.");
+        assert_eq!(Balances::free_balance(&1), 15);
+    });
+}
+
+#[test]
+fn refunding_relay_transfer_to_new_account_should_work() {
+    with_externalities(&mut ExtBuilder::default().existential_deposit(1).build(), || {
+        let tx = origin_transfer(5, 2, 5);
+        assert_ok!(Balances::relay_refund(&registry(), tx));
+        assert_eq!(Balances::free_balance(&5), 5);
+    });
+}
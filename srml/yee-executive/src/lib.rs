@@ -32,7 +32,7 @@ use parity_codec::{Codec, Encode, Decode, Compact};
 use system::extrinsics_root;
 use primitives::{ApplyOutcome, ApplyError};
 use primitives::transaction_validity::{TransactionValidity, TransactionPriority, TransactionLongevity};
-use yee_sr_primitives::{RelayParams, OriginExtrinsic, };
+use yee_sr_primitives::{RelayParams, OriginExtrinsic, RelayRegistry, relay_registry_enabled, legacy_relay_calls};
 use hash_db::Hasher;
 use substrate_primitives::{H256, Blake2Hasher, U256};
 use merkle_light::merkle::MerkleTree;
@@ -63,8 +63,8 @@ pub trait ExecuteBlock<Block: BlockT> {
 	fn execute_block(block: Block, extra: Option<Vec<u8>>) -> Vec<u8>;
 }
 
-pub struct Executive<System, Block, Context, Payment, AllModules>(
-	PhantomData<(System, Block, Context, Payment, AllModules)>
+pub struct Executive<System, Block, Context, Payment, AllModules, Relay>(
+	PhantomData<(System, Block, Context, Payment, AllModules, Relay)>
 );
 
 impl<
@@ -73,14 +73,15 @@ impl<
 	Context: Default,
 	Payment: MakePayment<System::AccountId>,
 	AllModules: OnInitialize<System::BlockNumber> + OnFinalize<System::BlockNumber> + OffchainWorker<System::BlockNumber>,
-> ExecuteBlock<Block> for Executive<System, Block, Context, Payment, AllModules> where
+	Relay: RelayRegistry,
+> ExecuteBlock<Block> for Executive<System, Block, Context, Payment, AllModules, Relay> where
 	Block::Extrinsic: Checkable<Context> + Codec,
 	<Block::Extrinsic as Checkable<Context>>::Checked: Applyable<Index=System::Index, AccountId=System::AccountId>,
 	<<Block::Extrinsic as Checkable<Context>>::Checked as Applyable>::Call: Dispatchable,
 	<<<Block::Extrinsic as Checkable<Context>>::Checked as Applyable>::Call as Dispatchable>::Origin: From<Option<System::AccountId>>
 {
 	fn execute_block(block: Block, extra: Option<Vec<u8>>) -> Vec<u8> {
-		Executive::<System, Block, Context, Payment, AllModules>::execute_block(block, extra)
+		Executive::<System, Block, Context, Payment, AllModules, Relay>::execute_block(block, extra)
 	}
 }
 
@@ -90,7 +91,8 @@ impl<
 	Context: Default,
 	Payment: MakePayment<System::AccountId>,
 	AllModules: OnInitialize<System::BlockNumber> + OnFinalize<System::BlockNumber> + OffchainWorker<System::BlockNumber>,
-> Executive<System, Block, Context, Payment, AllModules> where
+	Relay: RelayRegistry,
+> Executive<System, Block, Context, Payment, AllModules, Relay> where
 	Block::Extrinsic: Checkable<Context> + Codec,
 	<Block::Extrinsic as Checkable<Context>>::Checked: Applyable<Index=System::Index, AccountId=System::AccountId>,
 	<<Block::Extrinsic as Checkable<Context>>::Checked as Applyable>::Call: Dispatchable,
@@ -139,7 +141,7 @@ impl<
 
 		let proof = match (shard_item, pow_seal_item){
 			(Some(shard_item), Some(pow_seal_item)) => {
-				Self::execute_extrinsics_with_book_keeping_with_proof(extrinsics, *header.number(), pow_seal_item.relay_proof, shard_item.shard_num, shard_item.shard_count)
+				Self::execute_extrinsics_with_book_keeping_with_proof(extrinsics, *header.number(), pow_seal_item.relay_proof, pow_seal_item.fork_id, shard_item.shard_num, shard_item.shard_count)
 			},
 			_ => {
 				Self::execute_extrinsics_with_book_keeping(extrinsics, *header.number());
@@ -162,31 +164,41 @@ impl<
 	}
 
     /// Execute given extrinsics and take care of post-extrinsics book-keeping
-    fn execute_extrinsics_with_book_keeping_with_proof(extrinsics: Vec<Block::Extrinsic>, block_number: NumberFor<Block>, proof: H256, cur_shard: u16, shard_count: u16) -> Vec<u8> {
+    fn execute_extrinsics_with_book_keeping_with_proof(extrinsics: Vec<Block::Extrinsic>, block_number: NumberFor<Block>, proof: H256, fork_id: Option<u32>, cur_shard: u16, shard_count: u16) -> Vec<u8> {
         // let mut exe_result = vec![];
         // let mut hashs = vec![];
         let mut extrinsic_shard: HashMap<u16, Vec<H256>> = HashMap::new();
+        // blocks before the relay registry fork keep their original proof
+        let with_receipt = relay_registry_enabled(fork_id);
+        let registry = if with_receipt { Relay::relay_calls() } else { legacy_relay_calls() };
         for tx in extrinsics {
             let bytes = tx.encode();
             let is_signed = tx.is_signed();
             let hash = Blake2Hasher::hash(bytes.as_slice());
             match Self::apply_extrinsic_no_note_with_proof(tx) {
-                Ok(ApplyOutcome::Success) => {
-                    if is_signed == Some(true) {
-                        let ex_type = OriginExtrinsic::<H256, u128>::decode_type(bytes.clone());
-						if let Some(ex_type) = ex_type {
-							let ex = OriginExtrinsic::<H256, u128>::decode(ex_type, bytes);
-							if let Some(ex) = ex {
-								let to = ex.to();
-								if let Some(num) = shard_num_for(&to, shard_count) {
-									if num != cur_shard {
-										let v = extrinsic_shard.entry(num).or_insert(vec![]);
-										v.push(hash);
-									}
+                Ok(outcome) => {
+                    if is_signed == Some(true) && outcome == ApplyOutcome::Success {
+						let ex = OriginExtrinsic::<H256, u128>::decode(&registry, bytes);
+						if let Some(ex) = ex {
+							let to = ex.to();
+							if let Some(num) = shard_num_for(&to, shard_count) {
+								if num != cur_shard {
+									let v = extrinsic_shard.entry(num).or_insert(vec![]);
+									v.push(hash);
 								}
 							}
 						}
-                    }
+                    } else if let Some(rtx) = RelayParams::<H256>::decode(bytes).filter(|_| with_receipt) {
+						// proved to its origin shard, which gets a receipt
+						if rtx.relay_type().has_receipt(outcome == ApplyOutcome::Success) {
+							if let Some(num) = rtx.source::<H256>(&registry).and_then(|from| shard_num_for(&from, shard_count)) {
+								if num != cur_shard {
+									let v = extrinsic_shard.entry(num).or_insert(vec![]);
+									v.push(hash);
+								}
+							}
+						}
+					}
                 },
                 _ => ()
             }
@@ -425,11 +437,11 @@ impl<
 		if used == 1u16 {
 			return TransactionValidity::Invalid(ApplyError::Stale as i8);
 		}
-		let origin = match OriginExtrinsic::<System::AccountId, u128>::decode(rtx.relay_type(), rtx.origin()) {
-			Some(tx) => tx,
+		let source = match rtx.source::<System::AccountId>(&Relay::relay_calls()) {
+			Some(source) => source,
 			None => return TransactionValidity::Invalid(127i8)
 		};
-		let shard_num = yee_sharding_primitives::utils::shard_num_for(&origin.from(), shard_count).unwrap();
+		let shard_num = yee_sharding_primitives::utils::shard_num_for(&source, shard_count).unwrap();
 		let requires = (Compact(shard_num), Compact(rtx.number()), rtx.block_hash().as_ref().to_vec(), rtx.parent_hash().as_ref().to_vec()).encode();
 		TransactionValidity::Valid {
 			priority: 0u64 as TransactionPriority,
@@ -471,7 +483,8 @@ impl<
 					let seal = decode_pow_seal(&data);
 					if let Some(seal) = seal {
 						pow_seal = Some(PowSealItem {
-							relay_proof: seal.relay_proof
+							relay_proof: seal.relay_proof,
+							fork_id: seal.extra.fork_id,
 						});
 					}
 				},
@@ -492,6 +505,7 @@ struct ShardDigestItem {
 
 struct PowSealItem {
 	relay_proof: H256,
+	fork_id: Option<u32>,
 }
 
 #[derive(Decode)]
@@ -651,7 +665,7 @@ mod tests {
 	}
 
 	type TestXt = primitives::testing::TestXt<Call<Runtime>>;
-	type Executive = super::Executive<Runtime, Block<TestXt>, system::ChainContext<Runtime>, balances::Module<Runtime>, ()>;
+	type Executive = super::Executive<Runtime, Block<TestXt>, system::ChainContext<Runtime>, balances::Module<Runtime>, (), ()>;
 
 	#[test]
 	fn balance_transfer_dispatch_works() {
@@ -32,9 +32,9 @@ use {
         MakeFatalError, ProvideInherent, RuntimeString,
     },
     srml_support::{
-        decl_module, decl_storage, decl_event,
+        decl_module, decl_storage, decl_event, ensure,
         Parameter,
-        storage::StorageValue,
+        storage::{StorageValue, StorageMap},
         traits::{
             Currency, OnUnbalanced,
         }
@@ -44,16 +44,19 @@ use {
             Codec, Decode, Encode,
         },
         traits::{
-            As,
+            As, Verify, Zero,
         }
     },
-    system::ensure_inherent,
+    system::{ensure_inherent, ensure_signed, ensure_root},
 };
 use rstd::{result, prelude::*};
 use yee_srml_sharding::{self as sharding};
 use yee_sharding_primitives::ShardingInfo;
 use yee_sharding_primitives::utils::shard_num_for;
 
+mod mock;
+mod tests;
+
 type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
 type PositiveImbalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::PositiveImbalance;
 
@@ -72,6 +75,58 @@ pub trait Trait: system::Trait + sharding::Trait {
 
     type Sharding: ShardingInfo<Self::ShardNum>;
 
+    /// The key a block producer signs CRFG votes with.
+    type AuthorityId: Parameter + Default;
+
+    /// The signature of a CRFG vote.
+    type AuthoritySignature: Parameter + Verify<Signer=Self::AuthorityId>;
+
+    /// Provider of the current block producer's CRFG key.
+    type BlockAuthority: BlockAuthority<Self::AuthorityId>;
+}
+
+pub trait BlockAuthority<AuthorityId> {
+
+    /// The CRFG key of the current block's producer
+    fn block_authority() -> Option<AuthorityId>;
+}
+
+impl<AuthorityId> BlockAuthority<AuthorityId> for () {
+    fn block_authority() -> Option<AuthorityId> {
+        None
+    }
+}
+
+/// Percentage of a reward plan slashed for a CRFG vote equivocation
+pub const EQUIVOCATION_SLASH_PERCENT: u32 = 100;
+
+/// Two conflicting CRFG votes signed by one key in the same round and set.
+///
+/// Each vote is the SCALE encoded CRFG message, the key signed `(message, round, set_id)`.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct VoteEquivocation<Signature> {
+    pub round: u64,
+    pub set_id: u64,
+    pub first: (Vec<u8>, Signature),
+    pub second: (Vec<u8>, Signature),
+}
+
+impl<Signature: Verify> VoteEquivocation<Signature> {
+    /// Whether both votes are signed by `signer` and conflict with each other
+    pub fn is_valid(&self, signer: &Signature::Signer) -> bool {
+        let (first, second) = (&self.first.0, &self.second.0);
+        // same vote stage (prevote or precommit) with different targets
+        if first.is_empty() || second.is_empty() || first[0] != second[0] || first == second {
+            return false;
+        }
+        let verify = |(message, signature): &(Vec<u8>, Signature)| {
+            let mut payload = message.clone();
+            (self.round, self.set_id).using_encoded(|x| payload.extend_from_slice(x));
+            signature.verify(payload.as_slice(), signer)
+        };
+        verify(&self.first) && verify(&self.second)
+    }
 }
 
 pub trait OnFeeWithdrawn<Amount> {
@@ -117,6 +172,15 @@ decl_storage! {
 
         /// Chain id
         pub ChainId get(chain_id) config(): Vec<u8>;
+
+        /// CRFG key of the producer of a block with a pending reward plan
+        pub PlanAuthorities get(plan_authority): map T::BlockNumber => Option<T::AuthorityId>;
+
+        /// Percentage of a pending reward plan that is slashed
+        pub SlashedPlans get(slashed_plan): map T::BlockNumber => Option<u32>;
+
+        /// Account receiving slashed rewards, slashed rewards are not minted if unset
+        pub SlashSink get(slash_sink): Option<T::AccountId>;
     }
 }
 
@@ -163,6 +227,10 @@ decl_module! {
                     fee_reward,
                 };
 
+                if let Some(authority) = T::BlockAuthority::block_authority() {
+                    <PlanAuthorities<T>>::insert(block_number, authority);
+                }
+
                 let reward_block_number = if block_number > Self::block_reward_latency() {
                     block_number - Self::block_reward_latency()
                 } else{
@@ -176,6 +244,8 @@ decl_module! {
                         let reward = x.block_number <= reward_block_number;
                         if reward{
                             Self::reward(x, coinbase.clone(), reward_condition.clone());
+                            <PlanAuthorities<T>>::remove(x.block_number);
+                            <SlashedPlans<T>>::remove(x.block_number);
                         }
                         !reward
                     });
@@ -184,12 +254,51 @@ decl_module! {
 
         }
 
+        /// Slash the pending reward plan of `block_number` whose producer signed conflicting
+        /// CRFG votes.
+        fn report_equivocation(origin, block_number: T::BlockNumber, equivocation: VoteEquivocation<T::AuthoritySignature>) {
+            let _reporter = ensure_signed(origin)?;
+
+            let authority = match Self::plan_authority(block_number) {
+                Some(a) => a,
+                None => return Err("no pending reward plan for the block"),
+            };
+            ensure!(equivocation.is_valid(&authority), "invalid equivocation");
+
+            Self::slash_plan(block_number, EQUIVOCATION_SLASH_PERCENT)?;
+        }
+
+        /// Slash `percent` of the pending reward plan of `block_number`, e.g. for a block
+        /// which was later reverted.
+        fn slash(origin, block_number: T::BlockNumber, percent: u32) {
+            ensure_root(origin)?;
+
+            ensure!(percent > 0 && percent <= 100, "slash percent should be in 1..=100");
+            Self::slash_plan(block_number, percent)?;
+        }
+
+        /// Set the account receiving slashed rewards.
+        fn set_slash_sink(origin, sink: Option<T::AccountId>) {
+            ensure_root(origin)?;
+
+            if let Some(sink) = &sink {
+                let shard_num = T::Sharding::get_curr_shard().expect("qed").as_() as u16;
+                let shard_count = T::Sharding::get_shard_count().as_() as u16;
+                ensure!(shard_num_for(sink, shard_count) == Some(shard_num), "slash sink must be in current shard");
+            }
+            <SlashSink<T>>::mutate(|orig| *orig = sink);
+        }
+
     }
 }
 
 decl_event!(
 	pub enum Event<T> where Balance = BalanceOf<T>, N = <T as system::Trait>::BlockNumber, <T as system::Trait>::AccountId {
 		Reward(RewardPlan<N, AccountId, Balance>),
+		/// A pending reward plan was slashed (block number, total slashed percent).
+		Slashed(N, u32),
+		/// Slashed reward of a plan was paid to the slash sink.
+		SlashPaid(N, AccountId, Balance),
 	}
 );
 
@@ -207,18 +316,49 @@ impl<T: Trait> Module<T> {
                 RewardCondition::Normal => &reward_plan.coinbase,
                 RewardCondition::Slash => &current_coinbase,
             };
-            let reward_amount = reward_plan.block_reward + reward_plan.fee_reward;
-            let imbalance = T::Currency::deposit_creating(reward_target, reward_amount);
-            T::Reward::on_unbalanced(imbalance);
+            let percent = Self::slashed_plan(reward_plan.block_number).unwrap_or(0);
+            let (block_reward, block_slashed) = Self::split_slashed(reward_plan.block_reward, percent);
+            let (fee_reward, fee_slashed) = Self::split_slashed(reward_plan.fee_reward, percent);
+
+            let reward_amount = block_reward + fee_reward;
+            if !reward_amount.is_zero() {
+                let imbalance = T::Currency::deposit_creating(reward_target, reward_amount);
+                T::Reward::on_unbalanced(imbalance);
+            }
+
+            let slashed_amount = block_slashed + fee_slashed;
+            if let (Some(sink), false) = (Self::slash_sink(), slashed_amount.is_zero()) {
+                let imbalance = T::Currency::deposit_creating(&sink, slashed_amount);
+                T::Reward::on_unbalanced(imbalance);
+                Self::deposit_event(RawEvent::SlashPaid(reward_plan.block_number, sink, slashed_amount));
+            }
 
             Self::deposit_event(RawEvent::Reward(RewardPlan {
                 block_number: reward_plan.block_number.clone(),
                 coinbase: reward_target.clone(),
-                block_reward: reward_plan.block_reward,
-                fee_reward: reward_plan.fee_reward,
+                block_reward,
+                fee_reward,
             }));
         }
     }
+
+    /// Mark `percent` of a pending reward plan as slashed, keeping the highest percent reported
+    fn slash_plan(block_number: T::BlockNumber, percent: u32) -> Result<(), &'static str> {
+        ensure!(Self::reward_plans().iter().any(|x| x.block_number == block_number), "no pending reward plan for the block");
+
+        let percent = Self::slashed_plan(block_number).map_or(percent, |p| p.max(percent));
+        <SlashedPlans<T>>::insert(block_number, percent);
+        Self::deposit_event(RawEvent::Slashed(block_number, percent));
+        Ok(())
+    }
+
+    /// Split `amount` into the paid and the slashed part
+    fn split_slashed(amount: BalanceOf<T>, percent: u32) -> (BalanceOf<T>, BalanceOf<T>) {
+        let hundred = <BalanceOf<T> as As<u64>>::sa(100);
+        let percent = <BalanceOf<T> as As<u64>>::sa(percent.min(100) as u64);
+        let slashed = amount / hundred * percent + amount % hundred * percent / hundred;
+        (amount - slashed, slashed)
+    }
 }
 
 pub const INHERENT_IDENTIFIER: InherentIdentifier = *b"YeePow00";
@@ -238,7 +378,9 @@ pub struct PowInfo<AccountId> {
 #[cfg_attr(feature = "std", derive(Debug, Serialize))]
 pub enum RewardCondition {
     Normal,
-    Slash,//TODO: provide slash reason
+    /// Pays the matured reward to the current coinbase, misbehaviour is handled by
+    /// `report_equivocation` and `slash` instead
+    Slash,
 }
 
 pub trait PowInherentData<AccountId> {
@@ -319,7 +461,6 @@ impl<T: Trait> ProvideInherent for Module<T> {
             _ => return Ok(())
         };
 
-        //TODO: check slash reason
         if d.reward_condition != RewardCondition::Normal {
             return Err(RuntimeString::from("allow normal reward condition only").into());
         }
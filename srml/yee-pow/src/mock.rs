@@ -0,0 +1,126 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Test utilities
+
+#![cfg(test)]
+
+use primitives::BuildStorage;
+use primitives::{traits::IdentityLookup, testing::{Digest, DigestItem, Header}};
+use substrate_primitives::{H256, Blake2Hasher, ed25519, Pair as PairT};
+use runtime_io;
+use srml_support::impl_outer_origin;
+use yee_srml_sharding::{self as sharding};
+use yee_sharding_primitives::ShardingInfo;
+use crate::{GenesisConfig, Module, Trait, BlockAuthority};
+
+impl_outer_origin! {
+	pub enum Origin for Runtime {}
+}
+
+// Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Runtime;
+
+impl system::Trait for Runtime {
+    type Origin = Origin;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = ::primitives::traits::BlakeTwo256;
+    type Digest = Digest;
+    type AccountId = u64;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = ();
+    type Log = DigestItem;
+}
+
+impl balances::Trait for Runtime {
+    type Balance = u64;
+    type OnFreeBalanceZero = ();
+    type OnNewAccount = ();
+    type Event = ();
+    type TransactionPayment = ();
+    type DustRemoval = ();
+    type TransferPayment = ();
+}
+
+/// Sharding logs are never deposited in these tests.
+pub struct TestLog;
+impl From<sharding::Log<Runtime>> for TestLog {
+    fn from(_: sharding::Log<Runtime>) -> Self { TestLog }
+}
+impl From<TestLog> for DigestItem {
+    fn from(_: TestLog) -> Self { DigestItem::Other(Vec::new()) }
+}
+impl sharding::Trait for Runtime {
+    type ShardNum = u16;
+    type Log = TestLog;
+}
+
+/// A single shard network: every account belongs to shard 0.
+pub struct TestSharding;
+impl ShardingInfo<u16> for TestSharding {
+    fn get_genesis_shard_count() -> u16 { 1 }
+    fn get_curr_shard() -> Option<u16> { Some(0) }
+    fn get_shard_count() -> u16 { 1 }
+}
+
+/// Every block is produced by the `authority()` key.
+pub struct TestBlockAuthority;
+impl BlockAuthority<ed25519::Public> for TestBlockAuthority {
+    fn block_authority() -> Option<ed25519::Public> {
+        Some(authority().public())
+    }
+}
+
+impl Trait for Runtime {
+    type PowTarget = u64;
+    type Currency = balances::Module<Runtime>;
+    type Reward = ();
+    type Event = ();
+    type Sharding = TestSharding;
+    type AuthorityId = ed25519::Public;
+    type AuthoritySignature = ed25519::Signature;
+    type BlockAuthority = TestBlockAuthority;
+}
+
+pub const BLOCK_REWARD: u64 = 1000;
+pub const BLOCK_REWARD_LATENCY: u64 = 2;
+
+/// The key producing the test blocks
+pub fn authority() -> ed25519::Pair {
+    ed25519::Pair::from_string("//Alice", None).expect("static values are valid; qed")
+}
+
+pub fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+    let mut t = system::GenesisConfig::<Runtime>::default().build_storage().unwrap().0;
+    t.extend(GenesisConfig::<Runtime> {
+        genesis_pow_target: 0,
+        pow_target_adj: 10,
+        target_block_time: 30,
+        block_reward: BLOCK_REWARD,
+        block_reward_latency: BLOCK_REWARD_LATENCY,
+        chain_id: b"test".to_vec(),
+    }.build_storage().unwrap().0);
+    t.into()
+}
+
+pub type System = system::Module<Runtime>;
+pub type Balances = balances::Module<Runtime>;
+pub type Pow = Module<Runtime>;
@@ -0,0 +1,146 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Tests for the module.
+
+#![cfg(test)]
+
+use super::*;
+use mock::{System, Balances, Pow, Origin, authority, new_test_ext, BLOCK_REWARD};
+use runtime_io::with_externalities;
+use srml_support::{assert_ok, assert_noop};
+use substrate_primitives::{ed25519, Pair as PairT};
+use primitives::traits::OnFinalize;
+
+/// Produce block `number` with `coinbase`, paying the matured reward plans
+fn produce_block(number: u64, coinbase: u64) {
+    System::set_block_number(number);
+    assert_ok!(Pow::set_pow_info(Origin::INHERENT, PowInfo {
+        coinbase,
+        reward_condition: RewardCondition::Normal,
+    }));
+    <Pow as OnFinalize<u64>>::on_finalize(number);
+}
+
+fn vote(pair: &ed25519::Pair, message: Vec<u8>, round: u64, set_id: u64) -> (Vec<u8>, ed25519::Signature) {
+    let mut payload = message.clone();
+    (round, set_id).using_encoded(|x| payload.extend_from_slice(x));
+    (message, pair.sign(&payload))
+}
+
+fn equivocation(pair: &ed25519::Pair) -> VoteEquivocation<ed25519::Signature> {
+    VoteEquivocation {
+        round: 5,
+        set_id: 1,
+        first: vote(pair, vec![0, 1, 1, 1], 5, 1),
+        second: vote(pair, vec![0, 2, 2, 2], 5, 1),
+    }
+}
+
+#[test]
+fn reward_should_be_paid_after_latency() {
+    with_externalities(&mut new_test_ext(), || {
+        produce_block(1, 10);
+        produce_block(2, 20);
+        assert_eq!(Balances::free_balance(&10), 0);
+
+        produce_block(3, 30);
+        assert_eq!(Balances::free_balance(&10), BLOCK_REWARD);
+        assert_eq!(Pow::plan_authority(1), None);
+        assert_eq!(Pow::reward_plans().len(), 2);
+    });
+}
+
+#[test]
+fn equivocation_should_slash_whole_reward() {
+    with_externalities(&mut new_test_ext(), || {
+        produce_block(1, 10);
+        assert_eq!(Pow::plan_authority(1), Some(authority().public()));
+
+        assert_ok!(Pow::report_equivocation(Origin::signed(99), 1, equivocation(&authority())));
+        assert_eq!(Pow::slashed_plan(1), Some(100));
+
+        produce_block(2, 20);
+        produce_block(3, 30);
+        assert_eq!(Balances::free_balance(&10), 0);
+        assert_eq!(Pow::slashed_plan(1), None);
+    });
+}
+
+#[test]
+fn invalid_equivocation_should_not_slash() {
+    with_externalities(&mut new_test_ext(), || {
+        produce_block(1, 10);
+
+        let other = ed25519::Pair::from_string("//Bob", None).unwrap();
+        assert_noop!(
+            Pow::report_equivocation(Origin::signed(99), 1, equivocation(&other)),
+            "invalid equivocation"
+        );
+
+        let mut same_vote = equivocation(&authority());
+        same_vote.second = same_vote.first.clone();
+        assert_noop!(
+            Pow::report_equivocation(Origin::signed(99), 1, same_vote),
+            "invalid equivocation"
+        );
+
+        let mut other_stage = equivocation(&authority());
+        other_stage.second = vote(&authority(), vec![1, 2, 2, 2], 5, 1);
+        assert_noop!(
+            Pow::report_equivocation(Origin::signed(99), 1, other_stage),
+            "invalid equivocation"
+        );
+
+        assert_noop!(
+            Pow::report_equivocation(Origin::signed(99), 2, equivocation(&authority())),
+            "no pending reward plan for the block"
+        );
+    });
+}
+
+#[test]
+fn partial_slash_should_pay_sink() {
+    with_externalities(&mut new_test_ext(), || {
+        assert_ok!(Pow::set_slash_sink(Origin::ROOT, Some(7)));
+        produce_block(1, 10);
+
+        assert_noop!(Pow::slash(Origin::signed(1), 1, 30), "bad origin: expected to be a root origin");
+        assert_noop!(Pow::slash(Origin::ROOT, 1, 101), "slash percent should be in 1..=100");
+        assert_ok!(Pow::slash(Origin::ROOT, 1, 30));
+        // a lower slash never reduces the slashed percent
+        assert_ok!(Pow::slash(Origin::ROOT, 1, 10));
+        assert_eq!(Pow::slashed_plan(1), Some(30));
+
+        produce_block(2, 20);
+        produce_block(3, 30);
+        assert_eq!(Balances::free_balance(&10), BLOCK_REWARD * 70 / 100);
+        assert_eq!(Balances::free_balance(&7), BLOCK_REWARD * 30 / 100);
+    });
+}
+
+#[test]
+fn matured_plan_should_not_be_slashed() {
+    with_externalities(&mut new_test_ext(), || {
+        produce_block(1, 10);
+        produce_block(2, 20);
+        produce_block(3, 30);
+
+        assert_noop!(Pow::slash(Origin::ROOT, 1, 50), "no pending reward plan for the block");
+        assert_eq!(Balances::free_balance(&10), BLOCK_REWARD);
+    });
+}
@@ -3,24 +3,188 @@
 use parity_codec::{Compact};
 use rstd::vec::Vec;
 use rstd::prelude::*;
-use srml_support::{decl_module, dispatch::Result};
-use yee_sr_primitives::{RelayTypes};
+use primitives::traits::{As, Hash, StaticLookup};
+use srml_support::{decl_module, decl_storage, decl_event, ensure, StorageMap, dispatch::Result};
+use system::{ensure_signed, ensure_root};
+use yee_sharding_primitives::ShardingInfo;
+use yee_sr_primitives::{RelayTypes, RelayCallInfo, RelayRegistry, RelayRegistryActivation, RelayParams, OriginExtrinsic, MAX_MESSAGE_SIZE};
 
-pub trait Trait: system::Trait {
-    type Runtime: balances::Trait + assets::Trait;
+/// Applies the relayed origin extrinsics on the destination shard
+pub trait RelayHandler {
+    fn relay_transfer(relay_type: RelayTypes, registry: &[RelayCallInfo], tx: Vec<u8>) -> Result;
+
+    /// Refund an origin extrinsic of this shard whose transfer failed on the destination shard
+    fn relay_refund(relay_type: RelayTypes, registry: &[RelayCallInfo], tx: Vec<u8>) -> Result;
+}
+
+/// Handles the cross shard messages sent by `send_message`
+pub trait MessageHandler<AccountId, Hash> {
+    /// A message from `from` on another shard to `to` on this shard. Storage changes are not
+    /// reverted on error, so check before writing. No receipt is sent for a failed message.
+    fn on_message(hash: &Hash, from: &AccountId, to: &AccountId, data: Vec<u8>) -> Result;
+
+    /// The message sent by `from` was delivered to `to` on the destination shard
+    fn on_receipt(hash: &Hash, from: &AccountId, to: &AccountId);
+}
+
+impl<AccountId, Hash> MessageHandler<AccountId, Hash> for () {
+    fn on_message(_hash: &Hash, _from: &AccountId, _to: &AccountId, _data: Vec<u8>) -> Result {
+        Ok(())
+    }
+
+    fn on_receipt(_hash: &Hash, _from: &AccountId, _to: &AccountId) {}
+}
+
+pub trait Trait: sharding::Trait {
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+    /// The relay calls declared by the runtime, and their handlers
+    type Registry: RelayRegistry + RelayHandler;
+
+    type Sharding: ShardingInfo<Self::ShardNum>;
+
+    /// Receives the messages sent to this shard, and the receipts of the ones sent from it
+    type MessageHandler: MessageHandler<Self::AccountId, Self::Hash>;
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as Relay {
+        /// Messages sent from this shard and not yet delivered (sender, dest), keyed by the
+        /// hash of the origin extrinsic.
+        pub PendingMessages get(pending_message): map T::Hash => Option<(T::AccountId, T::AccountId)>;
+        /// Relayed extrinsics executed on this shard whose origin shard gets a receipt: delivered
+        /// messages and failed transfers, keyed by the hash of the origin extrinsic.
+        pub RelayReceipts get(relay_receipt): map T::Hash => bool;
+        /// The `RELAY_REGISTRY_FORK_ID` fork point of this shard: the registry rules apply to the
+        /// blocks after it. Unset until the fork point is scheduled.
+        pub RegistryFrom get(registry_from): Option<T::BlockNumber>;
+    }
 }
 
 decl_module!{
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        fn deposit_event<T>() = default;
+
         pub fn transfer(_origin, relay_type: RelayTypes, tx: Vec<u8>, _number: Compact<u64>, _hash: T::Hash, _parent: T::Hash) -> Result{
+            let registry = T::Registry::relay_calls();
             match relay_type {
-                RelayTypes::Balance => {
-                    <balances::Module<T::Runtime>>::relay_transfer(tx)
-                },
-                RelayTypes::Assets => {
-                    <assets::Module<T::Runtime>>::relay_transfer(tx)
+                RelayTypes::Message => Self::deliver_message(&registry, tx),
+                RelayTypes::Receipt => Self::deliver_receipt(&registry, tx),
+                _ => {
+                    // (module, call) classification, which came with spec_version 6; nodes only
+                    // relay by it from `RELAY_REGISTRY_FORK_ID`
+                    let origin_type = OriginExtrinsic::<T::AccountId, u128>::decode_type(&registry, tx.clone());
+                    ensure!(origin_type == Some(relay_type.clone()), "relay type not match");
+
+                    let hash = T::Hashing::hash(&tx);
+                    T::Registry::relay_transfer(relay_type, &registry, tx).map_err(|e| {
+                        // failed relays are proved back to the origin shard for a refund
+                        <RelayReceipts<T>>::insert(&hash, true);
+                        Self::deposit_event(RawEvent::RelayFailed(hash));
+                        e
+                    })
                 }
             }
         }
+
+        /// Send `data` to `dest` on another shard. It is relayed like a transfer and handed to
+        /// the `MessageHandler` of the destination shard, which relays a receipt back.
+        ///
+        /// Only available after the relay registry fork point: the legacy relay calls don't
+        /// relay messages, so a message sent before would stay pending forever.
+        pub fn send_message(origin, dest: <T::Lookup as StaticLookup>::Source, data: Vec<u8>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::relay_registry_active(), "messages are not relayed before the relay registry fork");
+            let dest = T::Lookup::lookup(dest)?;
+            ensure!(data.len() <= MAX_MESSAGE_SIZE, "message is larger than max size.");
+
+            let (cn, c) = (T::Sharding::get_curr_shard().expect("can't get current shard num").as_() as u16, T::Sharding::get_shard_count().as_() as u16);
+            let dn = yee_sharding_primitives::utils::shard_num_for(&dest, c).expect("can't get target shard num");
+            ensure!(cn != dn, "message dest is in the current shard.");
+
+            let index = match <system::Module<T>>::extrinsic_index() {
+                Some(index) => index,
+                None => return Err("message must be sent by an extrinsic."),
+            };
+            let hash = T::Hashing::hash(&<system::Module<T>>::extrinsic_data(index));
+            <PendingMessages<T>>::insert(&hash, (sender.clone(), dest.clone()));
+
+            Self::deposit_event(RawEvent::MessageSent(hash, sender, dest));
+            Ok(())
+        }
+
+        /// Set the `RELAY_REGISTRY_FORK_ID` fork point of this shard, the same block number as
+        /// in the fork points of the nodes.
+        pub fn set_registry_from(origin, #[compact] number: T::BlockNumber) -> Result {
+            ensure_root(origin)?;
+
+            <RegistryFrom<T>>::put(number);
+            Ok(())
+        }
+    }
+}
+
+decl_event!(
+    pub enum Event<T> where <T as system::Trait>::AccountId, <T as system::Trait>::Hash {
+        /// A message was sent to another shard (hash, sender, dest).
+        MessageSent(Hash, AccountId, AccountId),
+        /// A message from another shard was handled (hash, sender, dest).
+        MessageDelivered(Hash, AccountId, AccountId),
+        /// A message sent from this shard was delivered (hash, sender, dest).
+        MessageReceipt(Hash, AccountId, AccountId),
+        /// A relayed transfer failed on this shard, its origin shard will refund it (hash).
+        RelayFailed(Hash),
+        /// A transfer sent from this shard failed on the destination shard and was refunded (hash).
+        RelayRefunded(Hash),
+    }
+);
+
+impl<T: Trait> RelayRegistryActivation for Module<T> {
+    fn relay_registry_active() -> bool {
+        Self::registry_from().map(|from| <system::Module<T>>::block_number() > from).unwrap_or(false)
+    }
+}
+
+impl<T: Trait> Module<T> {
+    fn deliver_message(registry: &[RelayCallInfo], tx: Vec<u8>) -> Result {
+        let message = match OriginExtrinsic::<T::AccountId, u128>::decode(registry, tx.clone()) {
+            Some(message) => message,
+            None => return Err("message is invalid."),
+        };
+        ensure!(message.relay_type() == RelayTypes::Message, "relay type not match");
+
+        let hash = T::Hashing::hash(&tx);
+        T::MessageHandler::on_message(&hash, &message.from(), &message.to(), message.data())?;
+        <RelayReceipts<T>>::insert(&hash, true);
+
+        Self::deposit_event(RawEvent::MessageDelivered(hash, message.from(), message.to()));
+        Ok(())
+    }
+
+    fn deliver_receipt(registry: &[RelayCallInfo], tx: Vec<u8>) -> Result {
+        let relay = match RelayParams::<T::Hash>::decode(tx) {
+            Some(relay) => relay,
+            None => return Err("receipt is invalid."),
+        };
+        let hash = relay.hash();
+        match relay.relay_type() {
+            RelayTypes::Message => {}
+            RelayTypes::Receipt => return Err("receipt is for a receipt."),
+            relay_type => {
+                T::Registry::relay_refund(relay_type, registry, relay.origin())?;
+                Self::deposit_event(RawEvent::RelayRefunded(hash));
+                return Ok(());
+            }
+        }
+
+        let (sender, dest) = match <PendingMessages<T>>::take(&hash) {
+            Some(v) => v,
+            None => return Err("message is not pending."),
+        };
+        T::MessageHandler::on_receipt(&hash, &sender, &dest);
+
+        Self::deposit_event(RawEvent::MessageReceipt(hash, sender, dest));
+        Ok(())
     }
 }
@@ -34,7 +34,7 @@ use {
             Codec, Decode, Encode,
         },
         traits::{
-            Member, SimpleArithmetic,
+            Member, SimpleArithmetic, As,
             MaybeDisplay, MaybeSerializeDebug,
         },
     },
@@ -45,6 +45,7 @@ use {
     system::{self, ensure_inherent},
     sharding_primitives::ShardingInfo,
 };
+use rstd::prelude::*;
 
 pub type Log<T> = RawLog<<T as Trait>::ShardNum, <T as system::Trait>::BlockNumber>;
 
@@ -52,6 +53,16 @@ pub const INHERENT_IDENTIFIER: InherentIdentifier = *b"YeeShard";
 
 pub type InherentType = ShardInfo<u16>;
 
+/// Identifier of the foreign shards' scale out phases, reported by the foreign light clients
+pub const FOREIGN_INHERENT_IDENTIFIER: InherentIdentifier = *b"YeeFrnSc";
+
+/// Latest scale out phase of every foreign shard the node follows
+pub type ForeignInherentType<BlockNumber> = Vec<(u16, Option<ScaleOutPhase<BlockNumber, u16>>)>;
+
+/// Minimal percentage of the blocks in an observe window carrying a ScaleOut marker
+/// required to advance the scale out phase
+pub const SCALE_OUT_MIN_MARKED_PERCENT: u64 = 75;
+
 #[derive(Clone, PartialEq, Eq)]
 #[derive(Decode, Encode)]
 #[cfg_attr(feature = "std", derive(Debug, Serialize))]
@@ -181,6 +192,9 @@ decl_storage! {
         /// Storage for ScaleOutPhase used for current block
         pub CurrentScaleOutPhase get(current_scale_out_phase): Option<ScaleOutPhase<T::BlockNumber, T::ShardNum>>;
 
+        /// Blocks carrying a ScaleOut marker in the current observe window
+        pub ScaleOutMarkedBlocks get(scale_out_marked_blocks): u64;
+
     }
 }
 
@@ -204,70 +218,71 @@ decl_module! {
                 None => info.num,
             };
 
+            if info.scale_out.is_some() {
+                <ScaleOutMarkedBlocks<T>>::mutate(|orig| *orig += 1);
+            }
+
             match current_scale_out_phase {
                 None => {
                     if let Some(_scale_out) = info.scale_out {
-                        <Self as Store>::CurrentScaleOutPhase::mutate(|orig| {
-                            *orig = Some(ScaleOutPhase::Started{
-                                observe_util: block_number + scale_out_observe_blocks,
-                                shard_num: target_shard_num,
-                            });
-                        });
+                        Self::enter_phase(Some(ScaleOutPhase::Started{
+                            observe_util: block_number + scale_out_observe_blocks,
+                            shard_num: target_shard_num,
+                        }));
                     }
                 },
                 Some(current_scale_out_phase) => match current_scale_out_phase{
                     ScaleOutPhase::Started{observe_util, shard_num} => {
 
-                        //TODO: check scaled shard_num percentage
+                        // abort if too few miners are ready for the scaled shards
                         if observe_util == block_number{
-                            <Self as Store>::CurrentScaleOutPhase::mutate(|orig| {
-                                *orig = Some(ScaleOutPhase::NativeReady{
+                            Self::enter_phase(if Self::observed_enough_markers() {
+                                Some(ScaleOutPhase::NativeReady{
                                     observe_util: block_number + scale_out_observe_blocks,
-                                    shard_num: target_shard_num,
-                                });
+                                    shard_num,
+                                })
+                            } else {
+                                None
                             });
                         }
                     },
                     ScaleOutPhase::NativeReady{observe_util, shard_num} => {
 
-                        //TODO: check foreign scale out phase
+                        // in this phase, a block only carries the ScaleOut marker when the foreign
+                        // light clients see all foreign shards native ready (see `check_inherent`)
                         if observe_util == block_number{
-                            <Self as Store>::CurrentScaleOutPhase::mutate(|orig| {
-                                *orig = Some(ScaleOutPhase::Ready{
+                            Self::enter_phase(if Self::observed_enough_markers() {
+                                Some(ScaleOutPhase::Ready{
                                     observe_util: block_number + scale_out_observe_blocks,
-                                    shard_num: target_shard_num,
-                                });
+                                    shard_num,
+                                })
+                            } else {
+                                None
                             });
                         }
 
                     },
-                    ScaleOutPhase::Ready{observe_util, shard_num} => {
+                    ScaleOutPhase::Ready{observe_util, shard_num: _} => {
 
                         if observe_util == block_number{
 
                             let scale_out_shard_count = info.count + info.count;
 
-                            <Self as Store>::CurrentScaleOutPhase::mutate(|orig| {
-                                *orig = Some(ScaleOutPhase::Commiting{
-                                    shard_count: scale_out_shard_count,
-                                });
-                            });
+                            Self::enter_phase(Some(ScaleOutPhase::Commiting{
+                                shard_count: scale_out_shard_count,
+                            }));
                         }
                     },
                     ScaleOutPhase::Commiting{shard_count} => {
 
-                        <Self as Store>::CurrentScaleOutPhase::mutate(|orig| {
-                            *orig = Some(ScaleOutPhase::Committed{
-                                shard_num: target_shard_num,
-                                shard_count: shard_count,
-                            });
-                        });
+                        Self::enter_phase(Some(ScaleOutPhase::Committed{
+                            shard_num: target_shard_num,
+                            shard_count: shard_count,
+                        }));
                     },
-                    ScaleOutPhase::Committed{shard_num, shard_count} => {
+                    ScaleOutPhase::Committed{shard_num: _, shard_count: _} => {
 
-                        <Self as Store>::CurrentScaleOutPhase::mutate(|orig| {
-                            *orig = None;
-                        });
+                        Self::enter_phase(None);
                     },
                 }
             }
@@ -292,6 +307,39 @@ impl<T: Trait> Module<T> {
     fn deposit_log(log: Log<T>) {
         <system::Module<T>>::deposit_log(<T as Trait>::Log::from(log).into());
     }
+
+    /// Move to the next scale out phase and start a new observe window
+    fn enter_phase(phase: Option<ScaleOutPhase<T::BlockNumber, T::ShardNum>>) {
+        <Self as Store>::CurrentScaleOutPhase::mutate(|orig| {
+            *orig = phase;
+        });
+        <ScaleOutMarkedBlocks<T>>::kill();
+    }
+
+    /// Whether enough blocks of the observe window carried a ScaleOut marker
+    fn observed_enough_markers() -> bool {
+        let observe_blocks = Self::scale_out_observe_blocks().as_();
+        Self::scale_out_marked_blocks() * 100 >= observe_blocks * SCALE_OUT_MIN_MARKED_PERCENT
+    }
+}
+
+/// Whether every foreign shard is at least native ready for the scale out of `shard_count` shards
+pub fn foreign_native_ready<BlockNumber>(
+    foreign_phases: &ForeignInherentType<BlockNumber>,
+    shard_num: u16,
+    shard_count: u16,
+) -> bool {
+    (0..shard_count).filter(|x| *x != shard_num).all(|foreign_shard_num| {
+        let phase = foreign_phases.iter()
+            .find(|(num, _)| *num == foreign_shard_num)
+            .and_then(|(_, phase)| phase.as_ref());
+        match phase {
+            Some(ScaleOutPhase::NativeReady{..}) | Some(ScaleOutPhase::Ready{..}) => true,
+            Some(ScaleOutPhase::Commiting{shard_count: count}) |
+            Some(ScaleOutPhase::Committed{shard_count: count, ..}) => *count == shard_count * 2,
+            _ => false,
+        }
+    })
 }
 
 impl<T: Trait> ShardingInfo<T::ShardNum> for Module<T> {
@@ -319,13 +367,54 @@ impl<T: Trait> ProvideInherent for Module<T> {
     const INHERENT_IDENTIFIER: InherentIdentifier = INHERENT_IDENTIFIER;
 
     fn create_inherent(data: &InherentData) -> Option<Self::Call> {
-        let data = extract_inherent_data::<T::ShardNum>(data)
+        let mut info = extract_inherent_data::<T::ShardNum>(data)
             .expect("Sharding inherent data must exist");
 
-        Some(Call::set_shard_info(data))
+        // only vote for leaving native ready when the foreign shards are native ready too
+        if let Some(ScaleOutPhase::NativeReady{..}) = Self::current_scale_out_phase() {
+            let foreign_phases = extract_foreign_inherent_data::<T::BlockNumber>(data).unwrap_or_default();
+            if !foreign_native_ready(&foreign_phases, info.num.as_() as u16, info.count.as_() as u16) {
+                info.scale_out = None;
+            }
+        }
+
+        Some(Call::set_shard_info(info))
     }
 
-    fn check_inherent(_: &Self::Call, _: &InherentData) -> Result<(), Self::Error> {
+    /// Checked against the parent state. A ScaleOut marker leaving the native ready phase is
+    /// also checked against the foreign phases, when the importing node follows the foreign shards.
+    fn check_inherent(call: &Self::Call, data: &InherentData) -> Result<(), Self::Error> {
+        let info = match call {
+            Call::set_shard_info(info) => info,
+            _ => return Ok(()),
+        };
+
+        if let Some(scale_out) = &info.scale_out {
+            if scale_out.shard_num != info.num && scale_out.shard_num != info.num + info.count {
+                return Err(RuntimeString::from("invalid scale out shard num").into());
+            }
+            if let Some(ScaleOutPhase::NativeReady{..}) = Self::current_scale_out_phase() {
+                if let Ok(foreign_phases) = extract_foreign_inherent_data::<T::BlockNumber>(data) {
+                    if !foreign_native_ready(&foreign_phases, info.num.as_() as u16, info.count.as_() as u16) {
+                        return Err(RuntimeString::from("foreign shards not native ready").into());
+                    }
+                }
+            }
+        }
+
+        let parent = match Self::current_shard_info() {
+            Some(parent) => parent,
+            None => return Ok(()),
+        };
+        // the shards split when the parent commits the scale out
+        let ok = match Self::current_scale_out_phase() {
+            Some(ScaleOutPhase::Commiting{shard_count}) =>
+                info.count == shard_count && (info.num == parent.num || info.num == parent.num + parent.count),
+            _ => info.num == parent.num && info.count == parent.count,
+        };
+        if !ok {
+            return Err(RuntimeString::from("shard info not match").into());
+        }
         Ok(())
     }
 }
@@ -337,3 +426,157 @@ fn extract_inherent_data<N>(data: &InherentData) -> Result<ShardInfo<N>, Runtime
         .map_err(|_| RuntimeString::from("Invalid sharding inherent data encoding."))?
         .ok_or_else(|| "Sharding inherent data is not provided.".into())
 }
+
+fn extract_foreign_inherent_data<BlockNumber>(data: &InherentData) -> Result<ForeignInherentType<BlockNumber>, RuntimeString> where
+    BlockNumber: Decode,
+{
+    data.get_data::<ForeignInherentType<BlockNumber>>(&FOREIGN_INHERENT_IDENTIFIER)
+        .map_err(|_| RuntimeString::from("Invalid foreign scale out inherent data encoding."))?
+        .ok_or_else(|| "Foreign scale out inherent data is not provided.".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use runtime_io::with_externalities;
+    use substrate_primitives::{H256, Blake2Hasher};
+    use support::{impl_outer_origin, assert_ok};
+    use runtime_primitives::{
+        BuildStorage,
+        traits::{BlakeTwo256, IdentityLookup},
+        testing::{Digest, DigestItem, Header},
+    };
+
+    impl_outer_origin! {
+        pub enum Origin for Test {}
+    }
+
+    #[derive(Clone, Eq, PartialEq)]
+    pub struct Test;
+    impl system::Trait for Test {
+        type Origin = Origin;
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type Digest = Digest;
+        type AccountId = u64;
+        type Lookup = IdentityLookup<Self::AccountId>;
+        type Header = Header;
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl From<Log<Test>> for DigestItem {
+        fn from(log: Log<Test>) -> Self { DigestItem::Other(log.encode()) }
+    }
+    impl Trait for Test {
+        type ShardNum = u16;
+        type Log = DigestItem;
+    }
+    type Sharding = Module<Test>;
+    type System = system::Module<Test>;
+
+    const OBSERVE_BLOCKS: u64 = 4;
+
+    fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+        let mut t = system::GenesisConfig::<Test>::default().build_storage().unwrap().0;
+        t.extend(GenesisConfig::<Test> {
+            genesis_sharding_count: 2,
+            scale_out_observe_blocks: OBSERVE_BLOCKS,
+        }.build_storage().unwrap().0);
+        t.into()
+    }
+
+    fn import_block(number: u64, marked: bool) {
+        System::set_block_number(number);
+        assert_ok!(Sharding::set_shard_info(Origin::INHERENT, ShardInfo {
+            num: 0,
+            count: 2,
+            scale_out: if marked { Some(ScaleOut { shard_num: 2 }) } else { None },
+        }));
+    }
+
+    fn check_with(num: u16, count: u16, scale_out: Option<u16>, data: &InherentData) -> bool {
+        let info = ShardInfo { num, count, scale_out: scale_out.map(|shard_num| ScaleOut { shard_num }) };
+        Sharding::check_inherent(&Call::set_shard_info(info), data).is_ok()
+    }
+
+    fn check(num: u16, count: u16, scale_out: Option<u16>) -> bool {
+        check_with(num, count, scale_out, &InherentData::new())
+    }
+
+    #[test]
+    fn scale_out_should_advance_with_enough_markers() {
+        with_externalities(&mut new_test_ext(), || {
+            import_block(1, true);
+            assert_eq!(Sharding::current_scale_out_phase(), Some(ScaleOutPhase::Started { observe_util: 5, shard_num: 2 }));
+            (2..=4).for_each(|n| import_block(n, true));
+            import_block(5, false);
+            assert_eq!(Sharding::current_scale_out_phase(), Some(ScaleOutPhase::NativeReady { observe_util: 9, shard_num: 2 }));
+            assert_eq!(Sharding::scale_out_marked_blocks(), 0);
+        });
+    }
+
+    #[test]
+    fn scale_out_should_abort_without_enough_markers() {
+        with_externalities(&mut new_test_ext(), || {
+            import_block(1, true);
+            import_block(2, true);
+            (3..=5).for_each(|n| import_block(n, false));
+            assert_eq!(Sharding::current_scale_out_phase(), None);
+        });
+    }
+
+    #[test]
+    fn check_inherent_should_follow_parent_shard_info() {
+        with_externalities(&mut new_test_ext(), || {
+            assert!(check(1, 4, None));
+
+            import_block(1, false);
+            assert!(check(0, 2, None));
+            assert!(check(0, 2, Some(2)));
+            assert!(!check(1, 2, None));
+            assert!(!check(0, 4, None));
+            assert!(!check(0, 2, Some(1)));
+
+            Sharding::enter_phase(Some(ScaleOutPhase::Commiting { shard_count: 4 }));
+            assert!(check(0, 4, None));
+            assert!(check(2, 4, None));
+            assert!(!check(1, 4, None));
+            assert!(!check(0, 2, None));
+        });
+    }
+
+    #[test]
+    fn check_inherent_should_reject_marker_without_foreign_native_ready() {
+        with_externalities(&mut new_test_ext(), || {
+            import_block(1, false);
+            Sharding::enter_phase(Some(ScaleOutPhase::NativeReady { observe_util: 5, shard_num: 2 }));
+
+            let mut data = InherentData::new();
+            let started = Some(ScaleOutPhase::Started { observe_util: 5u64, shard_num: 3 });
+            data.put_data(FOREIGN_INHERENT_IDENTIFIER, &vec![(1u16, started)]).unwrap();
+            assert!(!check_with(0, 2, Some(2), &data));
+            assert!(check_with(0, 2, None, &data));
+            assert!(check(0, 2, Some(2)));
+
+            let mut data = InherentData::new();
+            let ready = Some(ScaleOutPhase::NativeReady { observe_util: 5u64, shard_num: 3 });
+            data.put_data(FOREIGN_INHERENT_IDENTIFIER, &vec![(1u16, ready)]).unwrap();
+            assert!(check_with(0, 2, Some(2), &data));
+        });
+    }
+
+    #[test]
+    fn foreign_native_ready_should_require_all_foreign_shards() {
+        let ready = Some(ScaleOutPhase::NativeReady { observe_util: 9u64, shard_num: 1 });
+        assert!(!foreign_native_ready(&vec![(1, ready.clone()), (2, ready.clone()), (3, None)], 0, 4));
+        assert!(!foreign_native_ready(&vec![(1, ready.clone()), (2, ready.clone())], 0, 4));
+        assert!(foreign_native_ready(&vec![(1, ready.clone()), (2, ready.clone()),
+            (3, Some(ScaleOutPhase::Commiting { shard_count: 8 }))], 0, 4));
+        assert!(!foreign_native_ready(&vec![(1, ready.clone()), (2, ready.clone()),
+            (3, Some(ScaleOutPhase::Started { observe_util: 5, shard_num: 3 }))], 0, 4));
+    }
+}
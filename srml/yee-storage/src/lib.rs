@@ -2,34 +2,215 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use rstd::vec::Vec;
-use system::ensure_signed;
+use system::{ensure_signed, ensure_root};
 // use parity_codec::Codec;
-use srml_support::{decl_module, decl_event, dispatch::Result};
+use primitives::traits::{As, Hash, CheckedMul};
+use srml_support::{decl_module, decl_event, decl_storage, ensure, StorageMap, StorageValue, dispatch::Result};
+use srml_support::traits::{Currency, ReservableCurrency};
 use yee_sr_primitives::MAX_STORAGE_SIZE;
 
+/// Default deposit reserved for every stored byte
+pub const DEFAULT_BYTE_DEPOSIT: u64 = 100_000;
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
 pub trait Trait: system::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+    /// The currency the storage deposit is reserved from.
+    type Currency: ReservableCurrency<Self::AccountId>;
+}
+
+decl_storage! {
+    trait Store for Module<T: Trait> as Storage {
+        /// The deposit reserved for every stored byte.
+        pub ByteDeposit get(byte_deposit): BalanceOf<T> = As::sa(DEFAULT_BYTE_DEPOSIT);
+        /// The stored blobs, keyed by the blake2 hash of their content.
+        pub Blobs get(blob): map T::Hash => Option<Vec<u8>>;
+        /// The owner of a stored blob and the deposit reserved for it.
+        pub BlobOwner get(blob_owner): map T::Hash => Option<(T::AccountId, BalanceOf<T>)>;
+    }
 }
 
 decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
         fn deposit_event<T>() = default;
 
+        /// Store `data` under its blake2 hash, reserving `ByteDeposit` for every byte.
         pub fn store(origin, data: Vec<u8>) -> Result {
             let origin = ensure_signed(origin)?;
             let size = data.len();
             if size > MAX_STORAGE_SIZE {
                 return Err("storage size is larger than max size.")
             }
-            Self::deposit_event(RawEvent::Storage(origin, size as u32));
+            let hash = T::Hashing::hash(&data);
+            ensure!(!<Blobs<T>>::exists(&hash), "data is already stored.");
+
+            let deposit = match Self::byte_deposit().checked_mul(&As::sa(size as u64)) {
+                Some(d) => d,
+                None => return Err("storage deposit overflow."),
+            };
+            T::Currency::reserve(&origin, deposit)?;
+
+            <Blobs<T>>::insert(&hash, data);
+            <BlobOwner<T>>::insert(&hash, (origin.clone(), deposit));
+
+            Self::deposit_event(RawEvent::Storage(origin.clone(), size as u32));
+            Self::deposit_event(RawEvent::Stored(origin, hash, deposit));
+            Ok(())
+        }
+
+        /// Remove a blob stored by the origin and refund its deposit.
+        pub fn remove(origin, hash: T::Hash) -> Result {
+            let origin = ensure_signed(origin)?;
+            let (owner, deposit) = match Self::blob_owner(&hash) {
+                Some(v) => v,
+                None => return Err("data is not stored."),
+            };
+            ensure!(owner == origin, "only the owner can remove the data.");
+
+            <Blobs<T>>::remove(&hash);
+            <BlobOwner<T>>::remove(&hash);
+            T::Currency::unreserve(&origin, deposit);
+
+            Self::deposit_event(RawEvent::Removed(origin, hash, deposit));
             Ok(())
         }
+
+        /// Set the deposit reserved for every stored byte. Existing blobs keep their deposit.
+        pub fn set_byte_deposit(origin, #[compact] deposit: BalanceOf<T>) {
+            ensure_root(origin)?;
+            <ByteDeposit<T>>::put(deposit);
+        }
     }
 }
 
 decl_event!(
-    pub enum Event<T> where <T as system::Trait>::AccountId {
+    pub enum Event<T> where <T as system::Trait>::AccountId, <T as system::Trait>::Hash, Balance = BalanceOf<T> {
 		/// A new storage record event.
 		Storage(AccountId, u32),
+		/// A blob was stored (owner, hash, deposit).
+		Stored(AccountId, Hash, Balance),
+		/// A blob was removed and its deposit refunded (owner, hash, deposit).
+		Removed(AccountId, Hash, Balance),
 	}
 );
+
+impl<T: Trait> Module<T> {
+    /// Get a stored blob by its hash
+    pub fn get_blob(hash: T::Hash) -> Option<Vec<u8>> {
+        Self::blob(hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use runtime_io::with_externalities;
+    use srml_support::{impl_outer_origin, assert_ok, assert_noop};
+    use substrate_primitives::{H256, Blake2Hasher};
+    use primitives::{
+        BuildStorage,
+        traits::{BlakeTwo256, IdentityLookup},
+        testing::{Digest, DigestItem, Header},
+    };
+
+    impl_outer_origin! {
+        pub enum Origin for Test {}
+    }
+
+    #[derive(Clone, Eq, PartialEq)]
+    pub struct Test;
+    impl system::Trait for Test {
+        type Origin = Origin;
+        type Index = u64;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type Digest = Digest;
+        type AccountId = u64;
+        type Lookup = IdentityLookup<Self::AccountId>;
+        type Header = Header;
+        type Event = ();
+        type Log = DigestItem;
+    }
+
+    impl balances::Trait for Test {
+        type Balance = u64;
+        type OnFreeBalanceZero = ();
+        type OnNewAccount = ();
+        type Event = ();
+        type TransactionPayment = ();
+        type DustRemoval = ();
+        type TransferPayment = ();
+    }
+
+    impl Trait for Test {
+        type Event = ();
+        type Currency = balances::Module<Test>;
+    }
+    type Storage = Module<Test>;
+    type Balances = balances::Module<Test>;
+
+    const BYTE_DEPOSIT: u64 = 10;
+
+    fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+        let mut t = system::GenesisConfig::<Test>::default().build_storage().unwrap().0;
+        t.extend(balances::GenesisConfig::<Test> {
+            balances: vec![(1, 100), (2, 100)],
+            ..Default::default()
+        }.build_storage().unwrap().0);
+        let mut t: runtime_io::TestExternalities<Blake2Hasher> = t.into();
+        with_externalities(&mut t, || <ByteDeposit<Test>>::put(BYTE_DEPOSIT));
+        t
+    }
+
+    #[test]
+    fn store_should_reserve_deposit() {
+        with_externalities(&mut new_test_ext(), || {
+            let data = vec![1u8; 5];
+            let hash = BlakeTwo256::hash(&data);
+            assert_ok!(Storage::store(Origin::signed(1), data.clone()));
+            assert_eq!(Storage::get_blob(hash), Some(data));
+            assert_eq!(Storage::blob_owner(hash), Some((1, 50)));
+            assert_eq!(Balances::reserved_balance(&1), 50);
+            assert_eq!(Balances::free_balance(&1), 50);
+
+            assert_noop!(Storage::store(Origin::signed(1), vec![2u8; 11]), "not enough free funds");
+        });
+    }
+
+    #[test]
+    fn store_should_reject_duplicate_blob() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Storage::store(Origin::signed(1), vec![1u8; 5]));
+            assert_noop!(Storage::store(Origin::signed(2), vec![1u8; 5]), "data is already stored.");
+        });
+    }
+
+    #[test]
+    fn remove_should_refund_deposit() {
+        with_externalities(&mut new_test_ext(), || {
+            let data = vec![1u8; 5];
+            let hash = BlakeTwo256::hash(&data);
+            assert_ok!(Storage::store(Origin::signed(1), data));
+            assert_noop!(Storage::remove(Origin::signed(2), hash), "only the owner can remove the data.");
+
+            assert_ok!(Storage::remove(Origin::signed(1), hash));
+            assert_eq!(Storage::get_blob(hash), None);
+            assert_eq!(Balances::reserved_balance(&1), 0);
+            assert_eq!(Balances::free_balance(&1), 100);
+            assert_noop!(Storage::remove(Origin::signed(1), hash), "data is not stored.");
+        });
+    }
+
+    #[test]
+    fn set_byte_deposit_should_require_root() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_noop!(Storage::set_byte_deposit(Origin::signed(1), 1), "bad origin: expected to be a root origin");
+            assert_ok!(Storage::set_byte_deposit(Origin::ROOT, 1));
+            assert_eq!(Storage::byte_deposit(), 1);
+        });
+    }
+}
@@ -18,10 +18,13 @@
 //!
 //! ## Overview
 //!
-//! The sudo module allows for a single account (called the "sudo key")
+//! The sudo module allows a set of accounts (called the "sudo keys")
 //! to execute dispatchable functions that require a `Root` call
-//! or designate a new account to replace them as the sudo key.
-//! Only one account can be the sudo key at a time.
+//! or designate new accounts to replace them as the sudo keys.
+//!
+//! A `Root` call is dispatched once `threshold` of the sudo keys approved the
+//! hash of the call and `delay` blocks passed since the last required approval.
+//! While the threshold is 1 and no delay is set, a single key may still `sudo` directly.
 //!
 //! You can start using the sudo module by implementing the sudo [`Trait`].
 //!
@@ -31,10 +34,20 @@
 //!
 //! ### Dispatchable Functions
 //!
-//! Only the sudo key can call the dispatchable functions from the sudo module.
+//! Only the sudo keys can call the signed dispatchable functions from the sudo module.
+//!
+//! * `sudo` - Make a `Root` call to a dispatchable function, if a single key is sufficient.
+//! * `propose` - Submit the hash of a call for approval.
+//! * `approve` - Approve a proposed call hash.
+//! * `execute` - Make a `Root` call to an approved call once its delay has passed.
+//! * `cancel` - Drop a pending proposal, by its proposer.
 //!
-//! * `sudo` - Make a `Root` call to a dispatchable function.
-//! * `set_key` - Assign a new account to be the sudo key.
+//! The following functions require a `Root` origin and so can only be reached via `execute`
+//! (or `sudo` while a single key is sufficient):
+//!
+//! * `set_keys` - Assign new accounts to be the sudo keys.
+//! * `set_threshold` - Set how many keys must approve a proposal.
+//! * `set_delay` - Set how many blocks an approved proposal waits before it can be executed.
 //!
 //! Please refer to the [`Call`] enum and its associated variants for documentation on each function.
 //!
@@ -103,12 +116,15 @@
 //!
 //! ## Genesis Config
 //!
-//! To use the sudo module, you need to set an initial superuser account as the sudo `key`.
+//! To use the sudo module, you need to set the initial superuser accounts as the sudo `keys`.
+//! The threshold starts at 1 and the delay at 0, so a single key keeps full control until
+//! `set_threshold` is dispatched. Chains upgrading to this module must do so right after the
+//! upgrade, with `sudo` of `set_threshold` (and optionally `set_delay`).
 //!
 //! ```ignore
 //! GenesisConfig {
 //!     sudo: Some(SudoConfig {
-//!         key: AccountId,
+//!         keys: Vec<AccountId>,
 //!     })
 //! }
 //! ```
@@ -125,11 +141,12 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use sr_std::prelude::*;
-use sr_primitives::traits::StaticLookup;
-use srml_support::{StorageValue, Parameter, Dispatchable, decl_module, decl_event, decl_storage, ensure};
+use sr_primitives::traits::{StaticLookup, Hash, Zero};
+use srml_support::{StorageValue, StorageMap, Parameter, Dispatchable, decl_module, decl_event, decl_storage, ensure};
 use srml_support::storage::unhashed::StorageVec;
-use system::ensure_signed;
+use system::{ensure_signed, ensure_root};
 use parity_codec as codec;
+use parity_codec_derive::{Encode, Decode};
 
 pub trait Trait: system::Trait {
 	/// The overarching event type.
@@ -148,6 +165,18 @@ impl<S: codec::Codec + Default> StorageVec for SudoKeyStorageVec<S> {
 	const PREFIX: &'static [u8] = SUDO_KEY_PREFIX;
 }
 
+/// Approvals collected for a proposed call hash.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct PendingProposal<AccountId, BlockNumber> {
+	/// The key which proposed the call.
+	pub proposer: AccountId,
+	/// The keys which approved the call, including the proposer.
+	pub approvals: Vec<AccountId>,
+	/// The block in which the threshold was reached.
+	pub approved_at: Option<BlockNumber>,
+}
+
 decl_module! {
 	// Simple declaration of the `Module` type. Lets the macro know what it's working on.
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
@@ -155,51 +184,176 @@ decl_module! {
 
 		/// Authenticates the sudo key and dispatches a function call with `Root` origin.
 		///
+		/// Only available while the threshold is 1 and no delay is set.
+		///
 		/// The dispatch origin for this call must be _Signed_.
 		fn sudo(origin, proposal: Box<T::Proposal>) {
 			// This is a public call, so we ensure that the origin is some signed account.
 			let sender = ensure_signed(origin)?;
 
-			let keys = <SudoKeyStorageVec<T::AccountId>>::items();
-			let contains = keys.iter().find(|x|x == &&sender).is_some();
-
-			ensure!(contains, "only the current sudo key can sudo");
+			ensure!(Self::is_key(&sender), "only the current sudo key can sudo");
+			ensure!(Self::threshold() <= 1 && Self::delay().is_zero(), "sudo requires the approval of multiple keys");
 
 			let ok = proposal.dispatch(system::RawOrigin::Root.into()).is_ok();
 			Self::deposit_event(RawEvent::Sudid(ok));
 		}
 
-		/// Authenticates the current sudo key and sets the given AccountId (`new`) as the new sudo key.
+		/// Sets the given AccountIds (`new`) as the new sudo keys.
 		///
-		/// The dispatch origin for this call must be _Signed_.
+		/// The dispatch origin for this call must be _Root_.
 		fn set_keys(origin, new: Vec<<T::Lookup as StaticLookup>::Source>) {
-			// This is a public call, so we ensure that the origin is some signed account.
-			let sender = ensure_signed(origin)?;
-
-			let keys = <SudoKeyStorageVec<T::AccountId>>::items();
-			let contains = keys.iter().find(|x|x == &&sender).is_some();
-
-			ensure!(contains, "only the current sudo key can change the sudo key");
+			ensure_root(origin)?;
 
 			let new_keys = new.into_iter().map(|x|T::Lookup::lookup(x)).collect::<Result<Vec<_>,_>>()?;
+			ensure!(new_keys.iter().enumerate().all(|(i, x)| !new_keys[..i].contains(x)), "sudo keys must be unique");
+			ensure!(new_keys.len() as u32 >= Self::threshold(), "sudo keys must be no less than the threshold");
 
 			Self::deposit_event(RawEvent::KeyChanged(new_keys.clone()));
 			<SudoKeyStorageVec<T::AccountId>>::set_items(new_keys);
 		}
+
+		/// Submit the hash of a call to be dispatched with `Root` origin. The proposer's
+		/// approval is counted.
+		///
+		/// The dispatch origin for this call must be _Signed_ by a sudo key.
+		fn propose(origin, proposal_hash: T::Hash) {
+			let sender = ensure_signed(origin)?;
+
+			ensure!(Self::is_key(&sender), "only the current sudo key can propose");
+			ensure!(!<Proposals<T>>::exists(&proposal_hash), "proposal already exists");
+
+			let pending = PendingProposal {
+				proposer: sender.clone(),
+				approvals: Vec::new(),
+				approved_at: None,
+			};
+			Self::deposit_event(RawEvent::Proposed(sender.clone(), proposal_hash));
+			Self::do_approve(sender, proposal_hash, pending);
+		}
+
+		/// Approve a proposed call hash.
+		///
+		/// The dispatch origin for this call must be _Signed_ by a sudo key.
+		fn approve(origin, proposal_hash: T::Hash) {
+			let sender = ensure_signed(origin)?;
+
+			ensure!(Self::is_key(&sender), "only the current sudo key can approve");
+			let pending = match Self::proposals(&proposal_hash) {
+				Some(p) => p,
+				None => return Err("proposal not exists"),
+			};
+			ensure!(!pending.approvals.contains(&sender), "proposal already approved");
+
+			Self::do_approve(sender, proposal_hash, pending);
+		}
+
+		/// Dispatch an approved call with `Root` origin once its delay has passed.
+		///
+		/// A proposal reaching the threshold only because the threshold was lowered is
+		/// marked approved by the first `execute`, and dispatched by a later one after the delay.
+		///
+		/// The dispatch origin for this call must be _Signed_ by a sudo key.
+		fn execute(origin, proposal: Box<T::Proposal>) {
+			let sender = ensure_signed(origin)?;
+
+			ensure!(Self::is_key(&sender), "only the current sudo key can execute");
+			let proposal_hash = T::Hashing::hash_of(&proposal);
+			let pending = match Self::proposals(&proposal_hash) {
+				Some(p) => p,
+				None => return Err("proposal not exists"),
+			};
+			ensure!(Self::valid_approvals(&pending.approvals) >= Self::threshold(), "proposal is not approved");
+			let now = <system::Module<T>>::block_number();
+			let approved_at = match pending.approved_at {
+				Some(n) => n,
+				// the threshold was lowered after the last approval, so the proposal is approved from now on
+				None if Self::delay().is_zero() => now,
+				None => {
+					let mut pending = pending;
+					pending.approved_at = Some(now);
+					<Proposals<T>>::insert(&proposal_hash, pending);
+					return Ok(());
+				},
+			};
+			ensure!(now >= approved_at + Self::delay(), "proposal is still delayed");
+
+			<Proposals<T>>::remove(&proposal_hash);
+			let ok = proposal.dispatch(system::RawOrigin::Root.into()).is_ok();
+			Self::deposit_event(RawEvent::Executed(proposal_hash, ok));
+		}
+
+		/// Drop a pending proposal. Only its proposer may cancel, so a key cannot veto
+		/// a proposal rotating it out.
+		///
+		/// The dispatch origin for this call must be _Signed_ by a sudo key.
+		fn cancel(origin, proposal_hash: T::Hash) {
+			let sender = ensure_signed(origin)?;
+
+			ensure!(Self::is_key(&sender), "only the current sudo key can cancel");
+			let pending = match Self::proposals(&proposal_hash) {
+				Some(p) => p,
+				None => return Err("proposal not exists"),
+			};
+			ensure!(pending.proposer == sender, "only the proposer can cancel");
+
+			<Proposals<T>>::remove(&proposal_hash);
+			Self::deposit_event(RawEvent::Cancelled(sender, proposal_hash));
+		}
+
+		/// Set how many sudo keys must approve a proposal.
+		///
+		/// The dispatch origin for this call must be _Root_.
+		fn set_threshold(origin, threshold: u32) {
+			ensure_root(origin)?;
+
+			ensure!(threshold > 0, "threshold should be non-zero");
+			ensure!(threshold <= <SudoKeyStorageVec<T::AccountId>>::count(), "threshold must be no more than the sudo keys");
+
+			<Threshold<T>>::put(threshold);
+			Self::deposit_event(RawEvent::ThresholdChanged(threshold));
+		}
+
+		/// Set how many blocks an approved proposal waits before it can be executed.
+		///
+		/// The dispatch origin for this call must be _Root_.
+		fn set_delay(origin, delay: T::BlockNumber) {
+			ensure_root(origin)?;
+
+			<Delay<T>>::put(delay);
+			Self::deposit_event(RawEvent::DelayChanged(delay));
+		}
 	}
 }
 
 decl_event!(
-	pub enum Event<T> where AccountId = <T as system::Trait>::AccountId {
+	pub enum Event<T> where AccountId = <T as system::Trait>::AccountId, Hash = <T as system::Trait>::Hash, BlockNumber = <T as system::Trait>::BlockNumber {
 		/// A sudo just took place.
 		Sudid(bool),
 		/// The sudoer just switched identity; the old key is supplied.
 		KeyChanged(Vec<AccountId>),
+		/// A call hash was proposed.
+		Proposed(AccountId, Hash),
+		/// A proposal was approved; the number of valid approvals is supplied.
+		Approved(AccountId, Hash, u32),
+		/// An approved proposal was dispatched.
+		Executed(Hash, bool),
+		/// A proposal was cancelled.
+		Cancelled(AccountId, Hash),
+		/// The approval threshold changed.
+		ThresholdChanged(u32),
+		/// The execution delay changed.
+		DelayChanged(BlockNumber),
 	}
 );
 
 decl_storage! {
 	trait Store for Module<T: Trait> as Sudo {
+		/// How many sudo keys must approve a proposal.
+		pub Threshold get(threshold): u32 = 1;
+		/// How many blocks an approved proposal waits before it can be executed.
+		pub Delay get(delay): T::BlockNumber;
+		/// The pending proposals.
+		pub Proposals get(proposals): map T::Hash => Option<PendingProposal<T::AccountId, T::BlockNumber>>;
 	}
 	add_extra_genesis {
 		config(keys): Vec<T::AccountId>;
@@ -221,3 +375,228 @@ decl_storage! {
 		});
 	}
 }
+
+impl<T: Trait> Module<T> {
+	/// Get the current sudo keys
+	pub fn keys() -> Vec<T::AccountId> {
+		<SudoKeyStorageVec<T::AccountId>>::items()
+	}
+
+	fn is_key(who: &T::AccountId) -> bool {
+		Self::keys().contains(who)
+	}
+
+	/// Count the approvals given by accounts which are still sudo keys
+	fn valid_approvals(approvals: &[T::AccountId]) -> u32 {
+		let keys = Self::keys();
+		approvals.iter().filter(|x| keys.contains(x)).count() as u32
+	}
+
+	fn do_approve(who: T::AccountId, proposal_hash: T::Hash, mut pending: PendingProposal<T::AccountId, T::BlockNumber>) {
+		pending.approvals.push(who.clone());
+		let count = Self::valid_approvals(&pending.approvals);
+		if pending.approved_at.is_none() && count >= Self::threshold() {
+			pending.approved_at = Some(<system::Module<T>>::block_number());
+		}
+		<Proposals<T>>::insert(&proposal_hash, pending);
+		Self::deposit_event(RawEvent::Approved(who, proposal_hash, count));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use sr_io::with_externalities;
+	use srml_support::{impl_outer_origin, assert_ok, assert_noop};
+	use substrate_primitives::{H256, Blake2Hasher};
+	use sr_primitives::{
+		BuildStorage,
+		traits::{BlakeTwo256, IdentityLookup},
+		testing::{Digest, DigestItem, Header}
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for Test {}
+	}
+
+	#[derive(Clone, Eq, PartialEq, Debug)]
+	pub struct Test;
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type Digest = Digest;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type Log = DigestItem;
+	}
+
+	// the calls of this module requiring root are the proposals
+	impl Trait for Test {
+		type Event = ();
+		type Proposal = Call<Test>;
+	}
+	type Sudo = Module<Test>;
+	type System = system::Module<Test>;
+
+	fn new_test_ext() -> sr_io::TestExternalities<Blake2Hasher> {
+		let mut t = system::GenesisConfig::<Test>::default().build_storage().unwrap().0;
+		t.extend(GenesisConfig::<Test> {
+			keys: vec![1, 2, 3],
+		}.build_storage().unwrap().0);
+		t.into()
+	}
+
+	fn proposal() -> (Box<Call<Test>>, H256) {
+		let proposal = Call::set_delay(7);
+		let hash = BlakeTwo256::hash_of(&proposal);
+		(Box::new(proposal), hash)
+	}
+
+	#[test]
+	fn sudo_should_require_threshold_of_one() {
+		with_externalities(&mut new_test_ext(), || {
+			let (call, _) = proposal();
+			assert_noop!(Sudo::sudo(Origin::signed(4), call.clone()), "only the current sudo key can sudo");
+			assert_ok!(Sudo::sudo(Origin::signed(1), call.clone()));
+			assert_eq!(Sudo::delay(), 7);
+
+			assert_ok!(Sudo::set_delay(Origin::ROOT, 0));
+			assert_ok!(Sudo::set_threshold(Origin::ROOT, 2));
+			assert_noop!(Sudo::sudo(Origin::signed(1), call), "sudo requires the approval of multiple keys");
+		});
+	}
+
+	#[test]
+	fn execute_should_require_threshold() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Sudo::set_threshold(Origin::ROOT, 2));
+			let (call, hash) = proposal();
+			assert_ok!(Sudo::propose(Origin::signed(1), hash));
+			assert_noop!(Sudo::approve(Origin::signed(1), hash), "proposal already approved");
+			assert_noop!(Sudo::execute(Origin::signed(1), call.clone()), "proposal is not approved");
+
+			assert_ok!(Sudo::approve(Origin::signed(2), hash));
+			assert_ok!(Sudo::execute(Origin::signed(3), call));
+			assert_eq!(Sudo::delay(), 7);
+			assert_eq!(Sudo::proposals(hash), None);
+		});
+	}
+
+	#[test]
+	fn execute_should_wait_for_delay() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Sudo::set_delay(Origin::ROOT, 3));
+			System::set_block_number(1);
+			let (call, hash) = proposal();
+			assert_ok!(Sudo::propose(Origin::signed(1), hash));
+
+			System::set_block_number(3);
+			assert_noop!(Sudo::execute(Origin::signed(1), call.clone()), "proposal is still delayed");
+			System::set_block_number(4);
+			assert_ok!(Sudo::execute(Origin::signed(1), call));
+			assert_eq!(Sudo::delay(), 7);
+		});
+	}
+
+	#[test]
+	fn approvals_of_rotated_out_keys_should_not_count() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Sudo::set_threshold(Origin::ROOT, 2));
+			let (call, hash) = proposal();
+			assert_ok!(Sudo::propose(Origin::signed(1), hash));
+			assert_ok!(Sudo::set_keys(Origin::ROOT, vec![2, 3, 4]));
+			assert_noop!(Sudo::approve(Origin::signed(1), hash), "only the current sudo key can approve");
+
+			assert_ok!(Sudo::approve(Origin::signed(2), hash));
+			assert_noop!(Sudo::execute(Origin::signed(2), call.clone()), "proposal is not approved");
+			assert_ok!(Sudo::approve(Origin::signed(3), hash));
+			assert_ok!(Sudo::execute(Origin::signed(2), call));
+			assert_eq!(Sudo::delay(), 7);
+		});
+	}
+
+	#[test]
+	fn execute_should_follow_lowered_threshold() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Sudo::set_threshold(Origin::ROOT, 3));
+			let (call, hash) = proposal();
+			assert_ok!(Sudo::propose(Origin::signed(1), hash));
+			assert_ok!(Sudo::approve(Origin::signed(2), hash));
+			assert_noop!(Sudo::execute(Origin::signed(1), call.clone()), "proposal is not approved");
+
+			assert_ok!(Sudo::set_threshold(Origin::ROOT, 2));
+			assert_ok!(Sudo::execute(Origin::signed(1), call));
+			assert_eq!(Sudo::delay(), 7);
+		});
+	}
+
+	#[test]
+	fn execute_should_delay_proposal_approved_by_lowered_threshold() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Sudo::set_threshold(Origin::ROOT, 3));
+			assert_ok!(Sudo::set_delay(Origin::ROOT, 3));
+			System::set_block_number(1);
+			let (call, hash) = proposal();
+			assert_ok!(Sudo::propose(Origin::signed(1), hash));
+			assert_ok!(Sudo::approve(Origin::signed(2), hash));
+			assert_ok!(Sudo::set_threshold(Origin::ROOT, 2));
+
+			System::set_block_number(2);
+			assert_ok!(Sudo::execute(Origin::signed(1), call.clone()));
+			assert_eq!(Sudo::delay(), 3);
+			assert_eq!(Sudo::proposals(hash).unwrap().approved_at, Some(2));
+			assert_noop!(Sudo::execute(Origin::signed(1), call.clone()), "proposal is still delayed");
+
+			System::set_block_number(5);
+			assert_ok!(Sudo::execute(Origin::signed(1), call));
+			assert_eq!(Sudo::delay(), 7);
+		});
+	}
+
+	#[test]
+	fn cancel_should_drop_proposal() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Sudo::set_delay(Origin::ROOT, 3));
+			let (call, hash) = proposal();
+			assert_ok!(Sudo::propose(Origin::signed(1), hash));
+			assert_noop!(Sudo::cancel(Origin::signed(4), hash), "only the current sudo key can cancel");
+
+			assert_ok!(Sudo::cancel(Origin::signed(1), hash));
+			assert_eq!(Sudo::proposals(hash), None);
+			System::set_block_number(10);
+			assert_noop!(Sudo::execute(Origin::signed(1), call), "proposal not exists");
+		});
+	}
+
+	#[test]
+	fn cancel_should_not_work_for_other_keys() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Sudo::set_threshold(Origin::ROOT, 2));
+			let rotation = Call::set_keys(vec![1, 2, 4]);
+			let hash = BlakeTwo256::hash_of(&rotation);
+			assert_ok!(Sudo::propose(Origin::signed(1), hash));
+			assert_noop!(Sudo::cancel(Origin::signed(3), hash), "only the proposer can cancel");
+
+			assert_ok!(Sudo::approve(Origin::signed(2), hash));
+			assert_ok!(Sudo::execute(Origin::signed(2), Box::new(rotation)));
+			assert_eq!(Sudo::keys(), vec![1, 2, 4]);
+		});
+	}
+
+	#[test]
+	fn set_keys_should_reject_duplicates() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_noop!(Sudo::set_keys(Origin::ROOT, vec![1, 2, 1]), "sudo keys must be unique");
+			assert_ok!(Sudo::set_threshold(Origin::ROOT, 3));
+			assert_noop!(Sudo::set_keys(Origin::ROOT, vec![1, 2]), "sudo keys must be no less than the threshold");
+			assert_ok!(Sudo::set_keys(Origin::ROOT, vec![4, 5, 6]));
+			assert_eq!(Sudo::keys(), vec![4, 5, 6]);
+		});
+	}
+}
@@ -0,0 +1,553 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Share ledger
+//! record the shares of the workers and the shard blocks they found, and split the
+//! matured rewards of the found blocks among the workers (PPLNS)
+//!
+//! When a block is found, the difficulty of the last `window` shares is snapshot per worker.
+//! The reward of the block is read from the `yee-pow` reward plans at the block, and is
+//! paid once the block is `BlockRewardLatency` blocks deep, in proportion to the snapshot.
+//! A matured block is paid only if it is still on the chain of the shard, less the part of
+//! its reward slashed by `yee-pow` (`SlashedPlans`), read just before the reward is minted.
+//! The rounding remainder goes to the worker who found the block.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Debug;
+use std::sync::Arc;
+use futures::Future;
+use log::info;
+use parity_codec::{Decode, Encode};
+use runtime_primitives::traits::{BlakeTwo256, Hash as HashT};
+use serde::Serialize;
+use serde_json::{json, Value};
+use yee_consensus_pow_primitives::PowTarget;
+use crate::error;
+
+/// Storage key of `Pow RewardPlans`, `twox_128(b"Pow RewardPlans")`
+pub const REWARD_PLANS_KEY: &str = "0xee2a031484982652ec3e6feb046bdffe";
+/// Storage key of `Pow BlockRewardLatency`, `twox_128(b"Pow BlockRewardLatency")`
+pub const BLOCK_REWARD_LATENCY_KEY: &str = "0x36eec347da40ff9153e0d99bdb218be5";
+/// Storage prefix of the `Pow SlashedPlans` map, keyed by `blake2_256(prefix ++ block_number)`
+pub const SLASHED_PLANS_PREFIX: &[u8] = b"Pow SlashedPlans";
+
+pub const DEFAULT_PPLNS_WINDOW: usize = 1000;
+/// Payout reports kept in memory
+const MAX_REPORTS: usize = 1000;
+
+/// `RewardPlan { block_number, coinbase, block_reward, fee_reward }` of `yee-pow`
+type RewardPlan = (u64, [u8; 32], u128, u128);
+
+/// RPC calls to the nodes of the shards, the `RpcClient` of the switch
+pub trait ShardRpc: Send + Sync {
+	/// Call `method` on a node of the configured shard `config_shard_num`, `params` being an array
+	fn call(&self, config_shard_num: u16, method: &str, params: Value) -> Box<dyn Future<Item=Value, Error=error::Error> + Send>;
+}
+
+/// State of a matured block on its shard
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Confirmation {
+	/// Not on the chain of the shard anymore, never rewarded
+	Orphaned,
+	/// On the chain, with the percentage of its reward slashed
+	Canonical(u32),
+}
+
+struct Share {
+	worker: String,
+	difficulty: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WorkerStats {
+	pub shares: u64,
+	/// Sum of the difficulties of the accepted shares
+	pub difficulty: u128,
+	/// Lowest `nonce_target` of the accepted shares
+	pub best_nonce_target: Option<PowTarget>,
+	pub blocks: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct FoundBlock<Hash> {
+	pub shard_num: u16,
+	pub config_shard_num: u16,
+	pub number: u64,
+	pub hash: Hash,
+	pub worker: String,
+	/// Difficulty of each worker in the window when the block was found
+	pub weights: Vec<(String, u128)>,
+	/// Block reward and fee reward of the block, once fetched
+	pub reward: Option<(u128, u128)>,
+	requested: bool,
+	confirming: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PayoutReport<Hash> {
+	pub shard_num: u16,
+	pub number: u64,
+	pub hash: Hash,
+	/// Reward paid, without the slashed part
+	pub reward: u128,
+	/// Percentage of the reward slashed
+	pub slashed: u32,
+	/// Amount of each worker, the amounts summing up to `reward`
+	pub payouts: Vec<(String, u128)>,
+}
+
+pub struct ShareLedger<Hash> {
+	window: usize,
+	shares: VecDeque<Share>,
+	workers: HashMap<String, WorkerStats>,
+	pending: Vec<FoundBlock<Hash>>,
+	reports: VecDeque<PayoutReport<Hash>>,
+}
+
+impl<Hash> ShareLedger<Hash> where
+	Hash: Clone + PartialEq + Debug,
+{
+	pub fn new(window: usize) -> Self {
+		Self {
+			window,
+			shares: VecDeque::new(),
+			workers: HashMap::new(),
+			pending: Vec::new(),
+			reports: VecDeque::new(),
+		}
+	}
+
+	/// Record a share accepted at `share_target`
+	pub fn on_share(&mut self, worker: &str, share_target: &PowTarget, nonce_target: &PowTarget) {
+		let difficulty = difficulty(share_target);
+
+		let stats = self.workers.entry(worker.to_string()).or_insert_with(Default::default);
+		stats.shares += 1;
+		stats.difficulty += difficulty as u128;
+		if stats.best_nonce_target.map(|best| *nonce_target < best).unwrap_or(true) {
+			stats.best_nonce_target = Some(*nonce_target);
+		}
+
+		self.shares.push_back(Share { worker: worker.to_string(), difficulty });
+		while self.shares.len() > self.window {
+			self.shares.pop_front();
+		}
+	}
+
+	/// Record a shard block found by a share of `worker`
+	pub fn on_block(&mut self, shard_num: u16, config_shard_num: u16, number: u64, hash: Hash, worker: &str) {
+		let mut weights = HashMap::<String, u128>::new();
+		for share in &self.shares {
+			*weights.entry(share.worker.clone()).or_insert(0) += share.difficulty as u128;
+		}
+		let mut weights = weights.into_iter().collect::<Vec<_>>();
+		weights.sort();
+
+		if let Some(stats) = self.workers.get_mut(worker) {
+			stats.blocks += 1;
+		}
+
+		info!("Block found: shard_num: {}, number: {}, hash: {:?}, worker: {}", shard_num, number, hash, worker);
+
+		self.pending.push(FoundBlock {
+			shard_num,
+			config_shard_num,
+			number,
+			hash,
+			worker: worker.to_string(),
+			weights,
+			reward: None,
+			requested: false,
+			confirming: false,
+		});
+	}
+
+	/// Found blocks whose reward is to be fetched, marking them as requested
+	pub fn request_rewards(&mut self) -> Vec<(u16, u64, Hash)> {
+		self.pending.iter_mut()
+			.filter(|block| block.reward.is_none() && !block.requested)
+			.map(|block| {
+				block.requested = true;
+				(block.config_shard_num, block.number, block.hash.clone())
+			})
+			.collect()
+	}
+
+	/// Set the block reward and the fee reward of a found block, `None` to fetch them again
+	pub fn on_reward(&mut self, hash: &Hash, reward: Option<(u128, u128)>) {
+		if let Some(block) = self.pending.iter_mut().find(|block| &block.hash == hash) {
+			block.reward = reward;
+			block.requested = reward.is_some();
+		}
+	}
+
+	/// Found blocks of `shard_num` matured at `best_number`, to be confirmed before being paid,
+	/// marking them as confirming
+	pub fn on_best(&mut self, shard_num: u16, best_number: u64, reward_latency: u64) -> Vec<(u16, u64, Hash)> {
+		self.pending.iter_mut()
+			.filter(|block| block.shard_num == shard_num && block.reward.is_some() && !block.confirming
+				&& block.number + reward_latency <= best_number)
+			.map(|block| {
+				block.confirming = true;
+				(block.config_shard_num, block.number, block.hash.clone())
+			})
+			.collect()
+	}
+
+	/// Pay a matured block once confirmed, `None` to confirm it again
+	pub fn on_confirmation(&mut self, hash: &Hash, confirmation: Option<Confirmation>) -> Option<PayoutReport<Hash>> {
+		let index = self.pending.iter().position(|block| &block.hash == hash)?;
+		let slashed = match confirmation {
+			None => {
+				self.pending[index].confirming = false;
+				return None;
+			}
+			Some(Confirmation::Orphaned) => {
+				let block = self.pending.remove(index);
+				info!("Block orphaned: shard_num: {}, number: {}, hash: {:?}, worker: {}", block.shard_num, block.number, block.hash, block.worker);
+				return None;
+			}
+			Some(Confirmation::Canonical(slashed)) => slashed,
+		};
+
+		let block = self.pending.remove(index);
+		let (block_reward, fee_reward) = block.reward.expect("only blocks with a reward are confirmed; qed");
+		let reward = unslashed(block_reward, slashed).saturating_add(unslashed(fee_reward, slashed));
+		let report = PayoutReport {
+			shard_num: block.shard_num,
+			number: block.number,
+			hash: block.hash,
+			reward,
+			slashed,
+			payouts: split(reward, &block.weights, &block.worker),
+		};
+		info!("Block reward matured: {:?}", report);
+
+		self.reports.push_back(report.clone());
+		while self.reports.len() > MAX_REPORTS {
+			self.reports.pop_front();
+		}
+
+		Some(report)
+	}
+
+	pub fn pending(&self) -> &[FoundBlock<Hash>] {
+		&self.pending
+	}
+
+	pub fn reports(&self) -> Vec<PayoutReport<Hash>> {
+		self.reports.iter().cloned().collect()
+	}
+
+	pub fn worker_stats(&self, worker: &str) -> Option<WorkerStats> {
+		self.workers.get(worker).cloned()
+	}
+}
+
+/// Difficulty of a share target, relative to the max target
+fn difficulty(share_target: &PowTarget) -> u64 {
+	if share_target.is_zero() {
+		return u64::max_value();
+	}
+	let difficulty = PowTarget::max_value() / *share_target;
+	if difficulty > PowTarget::from(u64::max_value()) {
+		u64::max_value()
+	} else {
+		difficulty.low_u64()
+	}
+}
+
+/// Part of `amount` paid when `percent` of it is slashed, rounded as `yee-pow` does
+fn unslashed(amount: u128, percent: u32) -> u128 {
+	let percent = percent.min(100) as u128;
+	let slashed = amount / 100 * percent + amount % 100 * percent / 100;
+	amount - slashed
+}
+
+/// Split `reward` in proportion to `weights`, the remainder going to `finder`
+fn split(reward: u128, weights: &[(String, u128)], finder: &str) -> Vec<(String, u128)> {
+	let total = weights.iter().map(|(_, weight)| *weight).sum::<u128>();
+	if total == 0 {
+		return vec![(finder.to_string(), reward)];
+	}
+
+	let mut payouts = weights.iter().map(|(worker, weight)| {
+		let amount = match reward.checked_mul(*weight) {
+			Some(product) => product / total,
+			None => reward / total * *weight,
+		};
+		(worker.clone(), amount)
+	}).collect::<Vec<_>>();
+
+	let remainder = reward - payouts.iter().map(|(_, amount)| *amount).sum::<u128>();
+	match payouts.iter_mut().find(|(worker, _)| worker == finder) {
+		Some(payout) => payout.1 += remainder,
+		None => payouts.push((finder.to_string(), remainder)),
+	}
+	payouts.retain(|(_, amount)| *amount > 0);
+	payouts
+}
+
+/// Block reward and fee reward of the block `number` from the reward plans stored at the block `hash`
+pub fn fetch_reward_future<Hash>(rpc: Arc<dyn ShardRpc>, config_shard_num: u16, number: u64, hash: Hash) -> Box<dyn Future<Item=Option<(u128, u128)>, Error=error::Error> + Send> where
+	Hash: Serialize,
+{
+	Box::new(get_storage_future(&*rpc, config_shard_num, REWARD_PLANS_KEY.to_string(), Some(hash)).map(move |data| {
+		let plans: Vec<RewardPlan> = data.and_then(|data| Decode::decode(&mut &data[..]))?;
+		plans.into_iter()
+			.find(|(block_number, ..)| *block_number == number)
+			.map(|(_, _, block_reward, fee_reward)| (block_reward, fee_reward))
+	}))
+}
+
+/// `BlockRewardLatency` at the best block of the shard
+pub fn fetch_reward_latency_future(rpc: Arc<dyn ShardRpc>, config_shard_num: u16) -> Box<dyn Future<Item=Option<u64>, Error=error::Error> + Send> {
+	Box::new(get_storage_future::<()>(&*rpc, config_shard_num, BLOCK_REWARD_LATENCY_KEY.to_string(), None).map(|data| {
+		data.and_then(|data| Decode::decode(&mut &data[..]))
+	}))
+}
+
+/// Whether the matured block `number` is `hash` on the chain of the shard, and the percentage
+/// of its reward slashed, read at the block before the one minting the reward
+pub fn fetch_confirmation_future<Hash>(rpc: Arc<dyn ShardRpc>, config_shard_num: u16, number: u64, hash: Hash, reward_latency: u64)
+	-> Box<dyn Future<Item=Option<Confirmation>, Error=error::Error> + Send> where
+	Hash: Serialize,
+{
+	let hash = match serde_json::to_value(hash) {
+		Ok(hash) => hash,
+		Err(e) => return Box::new(futures::future::err(format!("Invalid hash: {:?}", e).into())),
+	};
+	let canonical = rpc.call(config_shard_num, "chain_getBlockHash", json!([number]));
+	let before_reward = rpc.call(config_shard_num, "chain_getBlockHash", json!([(number + reward_latency).saturating_sub(1)]));
+
+	let mut key = SLASHED_PLANS_PREFIX.to_vec();
+	key.extend(number.encode());
+	let key = format!("0x{}", hex::encode(BlakeTwo256::hash(&key)));
+
+	Box::new(canonical.join(before_reward).and_then(move |(canonical, before_reward)| -> Box<dyn Future<Item=Option<Confirmation>, Error=error::Error> + Send> {
+		if canonical.is_null() || before_reward.is_null() {
+			return Box::new(futures::future::ok(None));
+		}
+		if canonical != hash {
+			return Box::new(futures::future::ok(Some(Confirmation::Orphaned)));
+		}
+		Box::new(get_storage_future(&*rpc, config_shard_num, key, Some(before_reward)).map(|data| {
+			let slashed = data.and_then(|data| Decode::decode(&mut &data[..])).unwrap_or(0u32);
+			Some(Confirmation::Canonical(slashed))
+		}))
+	}))
+}
+
+fn get_storage_future<Hash>(rpc: &dyn ShardRpc, config_shard_num: u16, key: String, hash: Option<Hash>) -> Box<dyn Future<Item=Option<Vec<u8>>, Error=error::Error> + Send> where
+	Hash: Serialize,
+{
+	Box::new(rpc.call(config_shard_num, "state_getStorage", json!([key, hash])).map(|result| {
+		result.as_str().and_then(|data| hex::decode(data.trim_start_matches("0x")).ok())
+	}))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use runtime_primitives::traits::{BlakeTwo256, Hash as HashT};
+	use yee_consensus_pow::ExtraData;
+
+	type Hash = <BlakeTwo256 as HashT>::Output;
+
+	/// Mines the work of `merkle_root` with the workers taking turns, each worker with its own
+	/// extra data, recording the shares and the found blocks in the ledger
+	struct LocalMiner {
+		merkle_root: Hash,
+		share_target: PowTarget,
+		block_target: PowTarget,
+		workers: Vec<(String, u64)>,
+		nonce: u64,
+		number: u64,
+	}
+
+	impl LocalMiner {
+		fn new(workers: &[(&str, u64)], share_target: PowTarget, block_target: PowTarget) -> Self {
+			Self {
+				merkle_root: BlakeTwo256::hash(b"merkle root"),
+				share_target,
+				block_target,
+				workers: workers.iter().map(|(worker, turns)| (worker.to_string(), *turns)).collect(),
+				nonce: 0,
+				number: 1,
+			}
+		}
+
+		fn extra_data(worker: usize) -> ExtraData {
+			let mut e_d = [0u8; 40];
+			e_d[0] = worker as u8;
+			let h = BlakeTwo256::hash(&e_d[..36]);
+			e_d[36..].copy_from_slice(&h[..4]);
+			ExtraData::from(e_d)
+		}
+
+		/// Mine until `blocks` blocks are found, returning the hashes of the found blocks
+		fn mine(&mut self, ledger: &mut ShareLedger<Hash>, blocks: usize) -> Vec<Hash> {
+			let mut found = vec![];
+			while found.len() < blocks {
+				for (i, (worker, turns)) in self.workers.iter().enumerate() {
+					for _ in 0..*turns {
+						let nonce = self.nonce;
+						self.nonce += 1;
+						let source_hash = BlakeTwo256::hash_of(&(self.merkle_root, nonce, Self::extra_data(i)));
+						let nonce_target = PowTarget::from(source_hash.as_ref());
+						if nonce_target > self.share_target {
+							continue;
+						}
+						ledger.on_share(worker, &self.share_target, &nonce_target);
+						if nonce_target <= self.block_target {
+							ledger.on_block(0, 0, self.number, source_hash, worker);
+							self.number += 1;
+							found.push(source_hash);
+							if found.len() == blocks {
+								return found;
+							}
+						}
+					}
+				}
+			}
+			found
+		}
+	}
+
+	#[test]
+	fn pplns_payouts() {
+		let share_target = PowTarget::max_value() / PowTarget::from(16u64);
+		let block_target = PowTarget::max_value() / PowTarget::from(256u64);
+		let mut miner = LocalMiner::new(&[("alice", 2), ("bob", 1)], share_target, block_target);
+		let mut ledger = ShareLedger::new(DEFAULT_PPLNS_WINDOW);
+
+		let found = miner.mine(&mut ledger, 3);
+
+		let alice = ledger.worker_stats("alice").unwrap();
+		let bob = ledger.worker_stats("bob").unwrap();
+		assert!(alice.shares > bob.shares);
+		assert_eq!(alice.difficulty, alice.shares as u128 * 16);
+		assert!(alice.best_nonce_target.unwrap() <= share_target);
+		assert_eq!(alice.blocks + bob.blocks, 3);
+
+		// rewards not fetched yet
+		assert_eq!(ledger.request_rewards().len(), 3);
+		assert!(ledger.request_rewards().is_empty());
+		assert!(ledger.on_best(0, 100, 6).is_empty());
+
+		for hash in &found {
+			ledger.on_reward(hash, Some((1_000_000_000, 7)));
+		}
+
+		// block 3 not matured
+		let matured = ledger.on_best(0, 8, 6);
+		assert_eq!(matured.iter().map(|(_, number, _)| *number).collect::<Vec<_>>(), vec![1, 2]);
+		// being confirmed
+		assert!(ledger.on_best(0, 8, 6).is_empty());
+		for (_, _, hash) in matured {
+			assert!(ledger.on_confirmation(&hash, Some(Confirmation::Canonical(0))).is_some());
+		}
+		assert_eq!(ledger.pending().len(), 1);
+		let finder = ledger.pending()[0].worker.clone();
+
+		let matured = ledger.on_best(0, 9, 6);
+		assert_eq!(matured.len(), 1);
+		// confirmation failed, to be confirmed again
+		assert!(ledger.on_confirmation(&matured[0].2, None).is_none());
+		assert_eq!(ledger.on_best(0, 9, 6), matured);
+		let reports = vec![ledger.on_confirmation(&matured[0].2, Some(Confirmation::Canonical(0))).unwrap()];
+		assert!(ledger.pending().is_empty());
+		assert_eq!(ledger.reports().len(), 3);
+
+		for report in ledger.reports() {
+			assert_eq!(report.payouts.iter().map(|(_, amount)| *amount).sum::<u128>(), 1_000_000_007);
+		}
+
+		// the window at the last block has all the shares, of the same difficulty
+		let report = &reports[0];
+		let amount = |worker: &str| report.payouts.iter().find(|(w, _)| w == worker).map(|(_, amount)| *amount).unwrap_or(0);
+		let total_shares = (alice.shares + bob.shares) as u128;
+		let alice_amount = 1_000_000_007 * alice.shares as u128 / total_shares;
+		let bob_amount = 1_000_000_007 * bob.shares as u128 / total_shares;
+		let remainder = 1_000_000_007 - alice_amount - bob_amount;
+		if finder == "alice" {
+			assert_eq!((amount("alice"), amount("bob")), (alice_amount + remainder, bob_amount));
+		} else {
+			assert_eq!((amount("alice"), amount("bob")), (alice_amount, bob_amount + remainder));
+		}
+		assert!(amount("alice") > amount("bob"));
+	}
+
+	#[test]
+	fn pplns_window() {
+		let share_target = PowTarget::max_value() / PowTarget::from(16u64);
+		let mut ledger = ShareLedger::new(2);
+		ledger.on_share("alice", &share_target, &share_target);
+		ledger.on_share("bob", &share_target, &share_target);
+		ledger.on_share("bob", &(share_target / PowTarget::from(2u64)), &share_target);
+		ledger.on_block(1, 5, 10, BlakeTwo256::hash(b"block"), "bob");
+
+		ledger.on_reward(&BlakeTwo256::hash(b"block"), Some((90, 10)));
+		// another shard
+		assert!(ledger.on_best(0, 20, 6).is_empty());
+
+		assert_eq!(ledger.on_best(1, 20, 6), vec![(5, 10, BlakeTwo256::hash(b"block"))]);
+		let report = ledger.on_confirmation(&BlakeTwo256::hash(b"block"), Some(Confirmation::Canonical(0))).unwrap();
+		assert_eq!(report.payouts, vec![("bob".to_string(), 100)]);
+	}
+
+	#[test]
+	fn orphaned_and_slashed_blocks() {
+		let share_target = PowTarget::max_value() / PowTarget::from(16u64);
+		let mut ledger = ShareLedger::new(DEFAULT_PPLNS_WINDOW);
+		ledger.on_share("alice", &share_target, &share_target);
+		ledger.on_block(0, 0, 10, BlakeTwo256::hash(b"orphaned"), "alice");
+		ledger.on_block(0, 0, 11, BlakeTwo256::hash(b"slashed"), "alice");
+		ledger.on_reward(&BlakeTwo256::hash(b"orphaned"), Some((1_000, 1)));
+		ledger.on_reward(&BlakeTwo256::hash(b"slashed"), Some((1_000, 199)));
+		assert_eq!(ledger.on_best(0, 20, 6).len(), 2);
+
+		// replaced by another block on the chain of the shard
+		assert!(ledger.on_confirmation(&BlakeTwo256::hash(b"orphaned"), Some(Confirmation::Orphaned)).is_none());
+		assert_eq!(ledger.pending().len(), 1);
+
+		let report = ledger.on_confirmation(&BlakeTwo256::hash(b"slashed"), Some(Confirmation::Canonical(30))).unwrap();
+		assert_eq!((report.reward, report.slashed), (700 + 140, 30));
+		assert_eq!(report.payouts, vec![("alice".to_string(), 840)]);
+		assert!(ledger.pending().is_empty());
+		assert_eq!(ledger.reports().len(), 1);
+	}
+
+	#[test]
+	fn split_remainder() {
+		let weights = vec![("alice".to_string(), 1), ("bob".to_string(), 1), ("carol".to_string(), 1)];
+		assert_eq!(split(100, &weights, "bob"), vec![
+			("alice".to_string(), 33),
+			("bob".to_string(), 34),
+			("carol".to_string(), 33),
+		]);
+		assert_eq!(split(100, &[], "bob"), vec![("bob".to_string(), 100)]);
+	}
+
+	#[test]
+	fn unslashed_rounding() {
+		assert_eq!(unslashed(1_000, 30), 700);
+		// 199 / 100 * 30 + 199 % 100 * 30 / 100 = 30 + 29 slashed
+		assert_eq!(unslashed(199, 30), 140);
+		assert_eq!(unslashed(199, 150), 0);
+	}
+}
@@ -17,14 +17,19 @@
 
 pub mod error;
 pub mod work_manager;
+pub mod stratum;
+pub mod ledger;
 mod worker;
 use yee_primitives::{Config, Shard};
 use runtime_primitives::traits::{BlakeTwo256, Hash as HashT};
 use std::sync::Arc;
 use crate::work_manager::{WorkManager, DefaultWorkManager, WorkManagerConfig};
+use crate::stratum::{Stratum, StratumConfig};
+use crate::ledger::{ShardRpc, ShareLedger};
 use yee_runtime::{BlockNumber, AuthorityId};
 use parity_codec::{Decode, Encode};
 use parking_lot::RwLock;
+use serde::Serialize;
 
 pub fn start_work_manager(config: Config, work_manager_config: WorkManagerConfig) -> error::Result<Arc<RwLock<DefaultWorkManager<
 	BlockNumber,
@@ -54,3 +59,19 @@ where WM: WorkManager + Send + Sync + 'static,
 
 	Ok(())
 }
+
+/// Start the stratum server, returning its share ledger
+pub fn start_stratum<WM>(work_manager: Arc<RwLock<WM>>, rpc: Arc<dyn ShardRpc>, stratum_config: StratumConfig)
+	-> error::Result<Arc<RwLock<ShareLedger<<WM::Hashing as HashT>::Output>>>>
+where WM: WorkManager + Send + Sync + 'static,
+	  WM::Number: Into<u64> + Send + Sync + 'static,
+	  <WM::Hashing as HashT>::Output: Decode + Encode + Serialize,
+{
+
+	let stratum = Stratum::new(stratum_config, work_manager, rpc);
+	let ledger = stratum.ledger();
+
+	stratum.start()?;
+
+	Ok(ledger)
+}
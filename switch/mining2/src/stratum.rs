@@ -0,0 +1,631 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Stratum server
+//! push work to the miners over tcp and accept their shares
+//!
+//! Line delimited json-rpc:
+//! - `mining.subscribe` => `[subscription_id, extranonce1, extranonce2_size]`
+//! - `mining.authorize [worker, password]` => `true`
+//! - `mining.submit [worker, job_id, extranonce2, nonce]` => `true`
+//! - notification `mining.set_target [share_target]`
+//! - notification `mining.notify [job_id, target, shard_count, clean_jobs, algorithm]`
+//!
+//! `job_id` is the merkle root of the work. The extra data of a share is
+//! `extranonce1 (4 bytes) ++ extranonce2 (8 bytes) ++ zeros (24 bytes) ++ checksum (4 bytes)`,
+//! the checksum being the first 4 bytes of the blake2 hash of the first 36 bytes.
+//! A share is `hash((merkle_root, nonce, extra_data)) <= share_target`, hashed by the pow
+//! algorithm of the work, and is submitted to
+//! the shards whose targets it satisfies.
+//!
+//! The accepted shares and the found blocks are recorded in the share ledger, which reports
+//! the payouts of the found blocks once their rewards mature. The ledger is served by the
+//! `get_payouts` and `get_worker_stats` RPC of the switch.
+
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use futures::{Future, Stream};
+use futures::future;
+use futures::sync::mpsc;
+use tokio::codec::{FramedRead, FramedWrite, LinesCodec};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::prelude::AsyncRead;
+use tokio::runtime::Runtime;
+use tokio::timer::Interval;
+use log::{info, debug, warn};
+use lru::LruCache;
+use parking_lot::RwLock;
+use parity_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use runtime_primitives::traits::{BlakeTwo256, Hash as HashT};
+use yee_consensus_pow::{ExtraData, get_algorithm};
+use yee_consensus_pow_primitives::PowTarget;
+use crate::error;
+use crate::ledger::{self, ShardRpc, ShareLedger};
+use crate::work_manager::{WorkManager, Work};
+
+const WORK_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const MAX_LINE_LENGTH: usize = 4096;
+/// Recent shares of a connection kept to reject duplicates
+const SHARE_CACHE_SIZE: usize = 1024;
+
+const EXTRANONCE1_SIZE: usize = 4;
+const EXTRANONCE2_SIZE: usize = 8;
+/// Extra data not covered by the checksum
+const EXTRA_SIZE: usize = 36;
+
+pub struct StratumConfig {
+	pub listen: SocketAddr,
+	/// The share target is `target * share_factor`
+	pub share_factor: u64,
+	/// Number of the last shares a found block is paid to
+	pub pplns_window: usize,
+}
+
+#[derive(Deserialize)]
+struct Request {
+	id: Value,
+	method: String,
+	#[serde(default)]
+	params: Vec<Value>,
+}
+
+struct Connection<Hash> {
+	sender: mpsc::UnboundedSender<String>,
+	extranonce1: [u8; EXTRANONCE1_SIZE],
+	subscribed: bool,
+	worker: Option<String>,
+	shares: LruCache<(Hash, [u8; EXTRANONCE2_SIZE], u64), ()>,
+}
+
+type StratumFuture = Box<dyn Future<Item=Value, Error=(i32, String)> + Send>;
+
+pub struct Stratum<WM: WorkManager> {
+	config: StratumConfig,
+	work_manager: Arc<RwLock<WM>>,
+	connections: RwLock<HashMap<usize, Connection<<WM::Hashing as HashT>::Output>>>,
+	next_id: AtomicUsize,
+	work: RwLock<Option<Work<<WM::Hashing as HashT>::Output, WM::Number>>>,
+	rpc: Arc<dyn ShardRpc>,
+	ledger: Arc<RwLock<ShareLedger<<WM::Hashing as HashT>::Output>>>,
+	reward_latency: Arc<RwLock<Option<u64>>>,
+}
+
+impl<WM> Stratum<WM> where
+	WM: WorkManager + Send + Sync + 'static,
+	WM::Number: Into<u64> + Send + Sync + 'static,
+	<WM::Hashing as HashT>::Output: Decode + Encode + Serialize,
+{
+	pub fn new(config: StratumConfig, work_manager: Arc<RwLock<WM>>, rpc: Arc<dyn ShardRpc>) -> Self {
+		let ledger = ShareLedger::new(config.pplns_window);
+		Self {
+			config,
+			work_manager,
+			connections: RwLock::new(HashMap::new()),
+			next_id: AtomicUsize::new(0),
+			work: RwLock::new(None),
+			rpc,
+			ledger: Arc::new(RwLock::new(ledger)),
+			reward_latency: Arc::new(RwLock::new(None)),
+		}
+	}
+
+	pub fn ledger(&self) -> Arc<RwLock<ShareLedger<<WM::Hashing as HashT>::Output>>> {
+		self.ledger.clone()
+	}
+
+	pub fn start(self) -> error::Result<()> {
+		if self.config.share_factor == 0 {
+			return Err("stratum share factor must be at least 1".into());
+		}
+		let listener = TcpListener::bind(&self.config.listen).map_err(|e| format!("{:?}", e))?;
+		let runtime = Runtime::new().map_err(|e| format!("{:?}", e))?;
+
+		info!("Stratum listen on: {}", self.config.listen);
+
+		let stratum = Arc::new(self);
+
+		let _thread = thread::Builder::new().name("stratum".to_string()).spawn(move || {
+			let mut runtime = runtime;
+
+			let poll = {
+				let stratum = stratum.clone();
+				Interval::new(Instant::now(), WORK_POLL_INTERVAL).for_each(move |_instant| {
+					stratum.refresh_work();
+					stratum.update_rewards();
+					Ok(())
+				}).map_err(|e| warn!("Stratum work poll error: {:?}", e))
+			};
+			runtime.spawn(poll);
+
+			let server = listener.incoming().for_each(move |socket| {
+				Self::accept(&stratum, socket);
+				Ok(())
+			}).map_err(|e| warn!("Stratum listener error: {:?}", e));
+
+			let _ = runtime.block_on(server);
+		});
+
+		Ok(())
+	}
+
+	fn accept(stratum: &Arc<Self>, socket: TcpStream) {
+		let (sender, receiver) = mpsc::unbounded();
+		let id = stratum.add_connection(sender);
+		let peer = socket.peer_addr().ok();
+		debug!("Stratum connection {}: {:?}", id, peer);
+
+		let (reader, writer) = socket.split();
+
+		let write = receiver
+			.map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+			.forward(FramedWrite::new(writer, LinesCodec::new()))
+			.map(|_| ())
+			.map_err(move |e| debug!("Stratum connection {} write error: {:?}", id, e));
+		tokio::spawn(write);
+
+		let stratum2 = stratum.clone();
+		let stratum = stratum.clone();
+		let read = FramedRead::new(reader, LinesCodec::new_with_max_length(MAX_LINE_LENGTH))
+			.for_each(move |line| {
+				let request: Request = match serde_json::from_str(&line) {
+					Ok(request) => request,
+					Err(e) => {
+						debug!("Stratum connection {} invalid request: {:?}", id, e);
+						return Ok(());
+					}
+				};
+				let stratum = stratum.clone();
+				let request_id = request.id.clone();
+				let subscribe = request.method == "mining.subscribe";
+				tokio::spawn(stratum.handle(id, request).then(move |result| {
+					let response = match result {
+						Ok(result) => json!({"id": request_id, "result": result, "error": Value::Null}),
+						Err((code, message)) => json!({"id": request_id, "result": Value::Null, "error": [code, message, Value::Null]}),
+					};
+					stratum.send(id, response);
+					if subscribe {
+						stratum.send_work(id);
+					}
+					Ok::<(), ()>(())
+				}));
+				Ok(())
+			})
+			.then(move |result| {
+				debug!("Stratum connection {} closed: {:?}", id, result);
+				stratum2.connections.write().remove(&id);
+				Ok::<(), ()>(())
+			});
+		tokio::spawn(read);
+	}
+
+	/// Register a connection, its extranonce1 being unique among the connections
+	fn add_connection(&self, sender: mpsc::UnboundedSender<String>) -> usize {
+		let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+		let mut extranonce1 = [0u8; EXTRANONCE1_SIZE];
+		extranonce1.copy_from_slice(&(id as u32).to_be_bytes());
+		self.connections.write().insert(id, Connection {
+			sender,
+			extranonce1,
+			subscribed: false,
+			worker: None,
+			shares: LruCache::new(SHARE_CACHE_SIZE),
+		});
+		id
+	}
+
+	fn handle(&self, id: usize, request: Request) -> StratumFuture {
+		match request.method.as_str() {
+			"mining.subscribe" => {
+				let mut connections = self.connections.write();
+				let connection = match connections.get_mut(&id) {
+					Some(connection) => connection,
+					None => return Box::new(future::err((20, "Connection closed".to_string()))),
+				};
+				connection.subscribed = true;
+				Box::new(future::ok(json!([
+					format!("{}", id),
+					hex::encode(connection.extranonce1),
+					EXTRANONCE2_SIZE,
+				])))
+			},
+			"mining.authorize" => {
+				let worker = match request.params.get(0).and_then(Value::as_str) {
+					Some(worker) => worker.to_string(),
+					None => return Box::new(future::err((20, "Invalid params".to_string()))),
+				};
+				if let Some(connection) = self.connections.write().get_mut(&id) {
+					info!("Stratum connection {} authorized: {}", id, worker);
+					connection.worker = Some(worker);
+				}
+				Box::new(future::ok(Value::Bool(true)))
+			},
+			"mining.submit" => self.submit(id, &request.params),
+			_ => Box::new(future::err((20, format!("Unknown method: {}", request.method)))),
+		}
+	}
+
+	fn submit(&self, id: usize, params: &[Value]) -> StratumFuture {
+		let params = params.iter().map(Value::as_str).collect::<Option<Vec<_>>>();
+		let (job_id, extranonce2, nonce) = match params.as_ref().map(Vec::as_slice) {
+			Some([_worker, job_id, extranonce2, nonce]) => (*job_id, *extranonce2, *nonce),
+			_ => return Box::new(future::err((20, "Invalid params".to_string()))),
+		};
+
+		let merkle_root = match hex::decode(job_id).ok().and_then(|bytes| Decode::decode(&mut &bytes[..])) {
+			Some(merkle_root) => merkle_root,
+			None => return Box::new(future::err((20, "Invalid job id".to_string()))),
+		};
+		let mut extranonce2_bytes = [0u8; EXTRANONCE2_SIZE];
+		match hex::decode(extranonce2) {
+			Ok(ref bytes) if bytes.len() == EXTRANONCE2_SIZE => extranonce2_bytes.copy_from_slice(bytes),
+			_ => return Box::new(future::err((20, "Invalid extranonce2".to_string()))),
+		};
+		let nonce = match u64::from_str_radix(nonce, 16) {
+			Ok(nonce) => nonce,
+			Err(_) => return Box::new(future::err((20, "Invalid nonce".to_string()))),
+		};
+
+		let mut work = match self.work_manager.read().get_work_by_merkle(merkle_root) {
+			Ok(work) => work,
+			Err(_) => return Box::new(future::err((21, "Job not found".to_string()))),
+		};
+
+		let (extra_data, worker) = {
+			let mut connections = self.connections.write();
+			let connection = match connections.get_mut(&id) {
+				Some(connection) => connection,
+				None => return Box::new(future::err((20, "Connection closed".to_string()))),
+			};
+			if connection.worker.is_none() {
+				return Box::new(future::err((24, "Unauthorized worker".to_string())));
+			}
+			let share = (merkle_root, extranonce2_bytes, nonce);
+			if connection.shares.put(share, ()).is_some() {
+				return Box::new(future::err((22, "Duplicate share".to_string())));
+			}
+			(share_extra_data(&connection.extranonce1, &extranonce2_bytes), connection.worker.clone().expect("qed"))
+		};
+
+		let algorithm = match get_algorithm(&work.algorithm) {
+			Ok(algorithm) => algorithm,
+			Err(e) => return Box::new(future::err((20, e))),
+		};
+		let source = (merkle_root, nonce, extra_data.clone());
+		let source_hash = algorithm.hash(&source.encode());
+		let nonce_target = PowTarget::from(source_hash.as_ref());
+
+		let share_target = share_target(&work.target, self.config.share_factor);
+		if nonce_target > share_target {
+			return Box::new(future::err((23, "Low difficulty share".to_string())));
+		}
+		self.ledger.write().on_share(&worker, &share_target, &nonce_target);
+
+		debug!("Share accepted: worker: {}, merkle_root: {:?}, nonce: {}, nonce_target: {:#x}", worker, merkle_root, nonce, nonce_target);
+
+		if nonce_target > work.target {
+			return Box::new(future::ok(Value::Bool(true)));
+		}
+
+		// the work manager submits the share to the shards whose job target it satisfies
+		work.extra_data = extra_data;
+		work.nonce = Some(nonce);
+		work.nonce_target = Some(nonce_target);
+		let ledger = self.ledger.clone();
+		Box::new(self.work_manager.read().submit_work_future(work).then(move |result| {
+			match result {
+				Ok(mined) => for block in mined {
+					ledger.write().on_block(block.shard_num, block.config_shard_num, block.number.into(), block.hash, &worker);
+				},
+				Err(e) => warn!("Share submit error: worker: {}, {:?}", worker, e),
+			}
+			Ok::<Value, (i32, String)>(Value::Bool(true))
+		}))
+	}
+
+	/// Push the work to the subscribed connections if the merkle root changed
+	fn refresh_work(&self) {
+		let work = match self.work_manager.read().get_work() {
+			Ok(work) => work,
+			Err(e) => {
+				debug!("Stratum get work error: {:?}", e);
+				return;
+			}
+		};
+		if self.work.read().as_ref().map(|x| x.merkle_root) == Some(work.merkle_root) {
+			return;
+		}
+		info!("Stratum new work: merkle_root: {:?}, target: {:#x}", work.merkle_root, work.target);
+		*self.work.write() = Some(work);
+
+		let ids = self.connections.read().iter()
+			.filter(|(_, connection)| connection.subscribed)
+			.map(|(id, _)| *id)
+			.collect::<Vec<_>>();
+		for id in ids {
+			self.send_work(id);
+		}
+	}
+
+	/// Fetch the rewards of the found blocks and pay the matured ones once confirmed
+	fn update_rewards(&self) {
+		let reward_latency = *self.reward_latency.read();
+		let reward_latency = match reward_latency {
+			Some(reward_latency) => reward_latency,
+			None => {
+				let latency = self.reward_latency.clone();
+				tokio::spawn(ledger::fetch_reward_latency_future(self.rpc.clone(), 0).then(move |result| {
+					match result {
+						Ok(result) => *latency.write() = result,
+						Err(e) => warn!("Stratum get reward latency error: {:?}", e),
+					}
+					Ok::<(), ()>(())
+				}));
+				return;
+			}
+		};
+
+		for (config_shard_num, number, hash) in self.ledger.write().request_rewards() {
+			let ledger = self.ledger.clone();
+			tokio::spawn(ledger::fetch_reward_future(self.rpc.clone(), config_shard_num, number, hash.clone()).then(move |result| {
+				let reward = result.map_err(|e| warn!("Stratum get reward error: {:?}", e)).ok().and_then(|x| x);
+				ledger.write().on_reward(&hash, reward);
+				Ok::<(), ()>(())
+			}));
+		}
+
+		// the work is on top of the best blocks
+		let best_numbers = match self.work.read().as_ref() {
+			Some(work) => work.shard_block_number.iter()
+				.map(|(shard_num, number)| (*shard_num, number.clone().into()))
+				.collect::<Vec<(u16, u64)>>(),
+			None => return,
+		};
+		let matured = {
+			let mut ledger = self.ledger.write();
+			best_numbers.into_iter()
+				.flat_map(|(shard_num, number)| ledger.on_best(shard_num, number.saturating_sub(1), reward_latency))
+				.collect::<Vec<_>>()
+		};
+		for (config_shard_num, number, hash) in matured {
+			let ledger = self.ledger.clone();
+			tokio::spawn(ledger::fetch_confirmation_future(self.rpc.clone(), config_shard_num, number, hash.clone(), reward_latency).then(move |result| {
+				let confirmation = result.map_err(|e| warn!("Stratum confirm block error: {:?}", e)).ok().and_then(|x| x);
+				ledger.write().on_confirmation(&hash, confirmation);
+				Ok::<(), ()>(())
+			}));
+		}
+	}
+
+	fn send_work(&self, id: usize) {
+		let work = self.work.read();
+		let work = match work.as_ref() {
+			Some(work) => work,
+			None => return,
+		};
+		self.send(id, json!({
+			"id": Value::Null,
+			"method": "mining.set_target",
+			"params": [target_hex(&share_target(&work.target, self.config.share_factor))],
+		}));
+		self.send(id, json!({
+			"id": Value::Null,
+			"method": "mining.notify",
+			"params": [hex::encode(work.merkle_root.as_ref()), target_hex(&work.target), work.shard_count, true, work.algorithm],
+		}));
+	}
+
+	fn send(&self, id: usize, message: Value) {
+		if let Some(connection) = self.connections.read().get(&id) {
+			let _ = connection.sender.unbounded_send(message.to_string());
+		}
+	}
+}
+
+/// Extra data of a share, with the checksum checked by the shards
+fn share_extra_data(extranonce1: &[u8; EXTRANONCE1_SIZE], extranonce2: &[u8; EXTRANONCE2_SIZE]) -> ExtraData {
+	let mut e_d = [0u8; 40];
+	e_d[..EXTRANONCE1_SIZE].copy_from_slice(extranonce1);
+	e_d[EXTRANONCE1_SIZE..EXTRANONCE1_SIZE + EXTRANONCE2_SIZE].copy_from_slice(extranonce2);
+	let h = BlakeTwo256::hash(&e_d[..EXTRA_SIZE]);
+	e_d[EXTRA_SIZE..].copy_from_slice(&h[..4]);
+	ExtraData::from(e_d)
+}
+
+fn share_target(target: &PowTarget, share_factor: u64) -> PowTarget {
+	target.checked_mul(PowTarget::from(share_factor)).unwrap_or_else(PowTarget::max_value)
+}
+
+fn target_hex(target: &PowTarget) -> String {
+	let mut bytes = [0u8; 32];
+	target.to_big_endian(&mut bytes);
+	hex::encode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::HashSet;
+	use yee_consensus_pow::DEFAULT_POW_ALGORITHM;
+	use crate::work_manager::MinedBlock;
+
+	type Hash = <BlakeTwo256 as HashT>::Output;
+
+	struct TestWorkManager {
+		work: Work<Hash, u64>,
+		submitted: RwLock<Vec<Work<Hash, u64>>>,
+	}
+
+	impl WorkManager for TestWorkManager {
+		type Hashing = BlakeTwo256;
+		type Number = u64;
+
+		fn get_work(&self) -> error::Result<Work<Hash, u64>> {
+			Ok(self.work.clone())
+		}
+
+		fn get_work_by_merkle(&self, root: Hash) -> error::Result<Work<Hash, u64>> {
+			if root == self.work.merkle_root {
+				Ok(self.work.clone())
+			} else {
+				Err("work not found".into())
+			}
+		}
+
+		fn submit_work(&self, work: Work<Hash, u64>) -> error::Result<()> {
+			self.submitted.write().push(work);
+			Ok(())
+		}
+
+		fn submit_work_future(&self, work: Work<Hash, u64>) -> Box<dyn Future<Item=Vec<MinedBlock<Hash, u64>>, Error=error::Error> + Send> {
+			self.submitted.write().push(work);
+			Box::new(future::ok(vec![MinedBlock { shard_num: 0, config_shard_num: 0, number: 10, hash: BlakeTwo256::hash(b"block") }]))
+		}
+	}
+
+	struct NoRpc;
+
+	impl ShardRpc for NoRpc {
+		fn call(&self, _config_shard_num: u16, method: &str, _params: Value) -> Box<dyn Future<Item=Value, Error=error::Error> + Send> {
+			Box::new(future::err(format!("no rpc: {}", method).into()))
+		}
+	}
+
+	fn stratum(share_factor: u64) -> Stratum<TestWorkManager> {
+		let work = Work {
+			merkle_root: BlakeTwo256::hash(b"merkle root"),
+			extra_data: ExtraData::from([0u8; 40]),
+			target: PowTarget::max_value() / PowTarget::from(256u64),
+			shard_count: 1,
+			shard_block_number: HashMap::new(),
+			nonce: None,
+			nonce_target: None,
+			algorithm: DEFAULT_POW_ALGORITHM.to_string(),
+		};
+		let config = StratumConfig {
+			listen: "127.0.0.1:0".parse().unwrap(),
+			share_factor,
+			pplns_window: 10,
+		};
+		let work_manager = TestWorkManager { work, submitted: RwLock::new(vec![]) };
+		Stratum::new(config, Arc::new(RwLock::new(work_manager)), Arc::new(NoRpc))
+	}
+
+	fn connect(stratum: &Stratum<TestWorkManager>) -> (usize, mpsc::UnboundedReceiver<String>) {
+		let (sender, receiver) = mpsc::unbounded();
+		(stratum.add_connection(sender), receiver)
+	}
+
+	fn request(stratum: &Stratum<TestWorkManager>, id: usize, method: &str, params: Vec<Value>) -> Result<Value, (i32, String)> {
+		stratum.handle(id, Request { id: Value::from(1), method: method.to_string(), params }).wait()
+	}
+
+	/// The first nonce whose share of the connection satisfies `accept`
+	fn find_nonce<F: Fn(&PowTarget) -> bool>(stratum: &Stratum<TestWorkManager>, id: usize, extranonce2: [u8; EXTRANONCE2_SIZE], accept: F) -> u64 {
+		let extranonce1 = stratum.connections.read().get(&id).unwrap().extranonce1;
+		let extra_data = share_extra_data(&extranonce1, &extranonce2);
+		let merkle_root = BlakeTwo256::hash(b"merkle root");
+		let algorithm = get_algorithm(DEFAULT_POW_ALGORITHM).unwrap();
+		(0u64..).find(|nonce| {
+			let source = (merkle_root, *nonce, extra_data.clone());
+			accept(&PowTarget::from(algorithm.hash(&source.encode()).as_ref()))
+		}).unwrap()
+	}
+
+	fn submit(stratum: &Stratum<TestWorkManager>, id: usize, extranonce2: [u8; EXTRANONCE2_SIZE], nonce: u64) -> Result<Value, (i32, String)> {
+		request(stratum, id, "mining.submit", vec![
+			json!("alice"),
+			json!(hex::encode(BlakeTwo256::hash(b"merkle root").as_ref())),
+			json!(hex::encode(extranonce2)),
+			json!(format!("{:x}", nonce)),
+		])
+	}
+
+	#[test]
+	fn extranonce1_is_unique() {
+		let stratum = stratum(16);
+		let connections = (0..3).map(|_| connect(&stratum)).collect::<Vec<_>>();
+
+		let mut extranonces = HashSet::new();
+		for (id, _receiver) in &connections {
+			let result = request(&stratum, *id, "mining.subscribe", vec![]).unwrap();
+			let extranonce1 = stratum.connections.read().get(id).unwrap().extranonce1;
+			assert_eq!(result[1], json!(hex::encode(extranonce1)));
+			assert_eq!(result[2], json!(EXTRANONCE2_SIZE));
+			extranonces.insert(extranonce1);
+		}
+		assert_eq!(extranonces.len(), 3);
+	}
+
+	#[test]
+	fn shares_and_blocks_are_accepted() {
+		let stratum = stratum(16);
+		let (id, _receiver) = connect(&stratum);
+		assert_eq!(request(&stratum, id, "mining.authorize", vec![json!("alice"), json!("x")]), Ok(json!(true)));
+
+		let (target, share_target) = (stratum.work_manager.read().work.target, share_target(&stratum.work_manager.read().work.target, 16));
+		let extranonce2 = [1u8; EXTRANONCE2_SIZE];
+
+		// below the share target only
+		let nonce = find_nonce(&stratum, id, extranonce2, |nonce_target| *nonce_target <= share_target && *nonce_target > target);
+		assert_eq!(submit(&stratum, id, extranonce2, nonce), Ok(json!(true)));
+		assert!(stratum.work_manager.read().submitted.read().is_empty());
+		assert_eq!(stratum.ledger.read().worker_stats("alice").unwrap().shares, 1);
+
+		// below the block target, submitted to the shards
+		let nonce = find_nonce(&stratum, id, extranonce2, |nonce_target| *nonce_target <= target);
+		assert_eq!(submit(&stratum, id, extranonce2, nonce), Ok(json!(true)));
+		assert_eq!(stratum.work_manager.read().submitted.read()[0].nonce, Some(nonce));
+		let stats = stratum.ledger.read().worker_stats("alice").unwrap();
+		assert_eq!((stats.shares, stats.blocks), (2, 1));
+		assert_eq!(stratum.ledger.read().pending().len(), 1);
+	}
+
+	#[test]
+	fn invalid_shares_are_rejected() {
+		let stratum = stratum(16);
+		let (id, _receiver) = connect(&stratum);
+		let share_target = share_target(&stratum.work_manager.read().work.target, 16);
+		let extranonce2 = [2u8; EXTRANONCE2_SIZE];
+		let nonce = find_nonce(&stratum, id, extranonce2, |nonce_target| *nonce_target <= share_target);
+
+		// not authorized
+		assert_eq!(submit(&stratum, id, extranonce2, nonce).unwrap_err().0, 24);
+
+		assert_eq!(request(&stratum, id, "mining.authorize", vec![json!("alice")]), Ok(json!(true)));
+		assert_eq!(submit(&stratum, id, extranonce2, nonce), Ok(json!(true)));
+		// duplicate
+		assert_eq!(submit(&stratum, id, extranonce2, nonce).unwrap_err().0, 22);
+
+		// low difficulty
+		let nonce = find_nonce(&stratum, id, extranonce2, |nonce_target| *nonce_target > share_target);
+		assert_eq!(submit(&stratum, id, extranonce2, nonce).unwrap_err().0, 23);
+
+		assert_eq!(stratum.ledger.read().worker_stats("alice").unwrap().shares, 1);
+	}
+
+	#[test]
+	fn zero_share_factor_is_rejected() {
+		assert!(stratum(0).start().is_err());
+	}
+}
@@ -40,7 +40,7 @@ use std::thread;
 use parity_codec::{Decode, Encode};
 use yee_sharding::{GENERATED_MODULE_LOG_PREFIX, GENERATED_SHARDING_PREFIX};
 use yee_consensus_pow_primitives::PowTarget;
-use yee_consensus_pow::{MiningHash, MiningAlgorithm, ExtraData, OriginalMerkleProof, CompactMerkleProof};
+use yee_consensus_pow::{MiningHash, MiningAlgorithm, ExtraData, OriginalMerkleProof, CompactMerkleProof, DEFAULT_POW_ALGORITHM};
 use merkle_light::merkle::MerkleTree;
 use runtime_primitives::traits::{BlakeTwo256, Hash as HashT};
 use std::iter::FromIterator;
@@ -71,7 +71,8 @@ pub trait WorkManager {
 
 	fn submit_work(&self, work: Work<<Self::Hashing as HashT>::Output, Self::Number>) -> error::Result<()>;
 
-	fn submit_work_future(&self, work: Work<<Self::Hashing as HashT>::Output, Self::Number>) -> Box<dyn Future<Item=(), Error=error::Error> + Send>;
+	/// Submit the work to the shards whose job target it satisfies, resolving to the blocks mined
+	fn submit_work_future(&self, work: Work<<Self::Hashing as HashT>::Output, Self::Number>) -> Box<dyn Future<Item=Vec<MinedBlock<<Self::Hashing as HashT>::Output, Self::Number>>, Error=error::Error> + Send>;
 }
 
 impl<Number, AuthorityId, Hashing> WorkManager for DefaultWorkManager<Number, AuthorityId, Hashing> where
@@ -162,11 +163,11 @@ impl<Number, AuthorityId, Hashing> WorkManager for DefaultWorkManager<Number, Au
 	}
 
 	fn submit_work(&self, work: Work<<Self::Hashing as HashT>::Output, Self::Number>) -> error::Result<()> {
-		tokio::run(self.accept_work_future(work).map_err(|_|()));
+		tokio::run(self.accept_work_future(work).map(|_| ()).map_err(|_|()));
 		Ok(())
 	}
 
-	fn submit_work_future(&self, work: Work<<Self::Hashing as HashT>::Output, Self::Number>) -> Box<dyn Future<Item=(), Error=error::Error> + Send> {
+	fn submit_work_future(&self, work: Work<<Self::Hashing as HashT>::Output, Self::Number>) -> Box<dyn Future<Item=Vec<MinedBlock<<Self::Hashing as HashT>::Output, Self::Number>>, Error=error::Error> + Send> {
 		self.accept_work_future(work)
 	}
 }
@@ -181,6 +182,22 @@ pub struct Work<Hash, Number> {
 	pub shard_block_number: HashMap<u16, Number>,
 	pub nonce: Option<u64>,
 	pub nonce_target: Option<PowTarget>,
+	/// Name of the pow algorithm of the work
+	#[serde(default = "default_algorithm")]
+	pub algorithm: String,
+}
+
+fn default_algorithm() -> String {
+	DEFAULT_POW_ALGORITHM.to_string()
+}
+
+/// A shard block mined by a submitted work
+#[derive(Debug, Clone)]
+pub struct MinedBlock<Hash, Number> {
+	pub shard_num: u16,
+	pub config_shard_num: u16,
+	pub number: Number,
+	pub hash: Hash,
 }
 
 #[derive(Debug, Clone)]
@@ -212,12 +229,19 @@ impl<Number, AuthorityId, Hashing> RawWork<Number, AuthorityId, Hashing> where
 	//update merkle_tree and work
 	fn compile(&mut self) {
 
+		// the work is hashed by the algorithm of the first shard, the shards of other algorithms are left out
+		let algorithm = (0..self.shard_count)
+			.filter_map(|x| self.shard_jobs.get(&x))
+			.map(|(_, job)| job.algorithm.clone())
+			.next()
+			.unwrap_or_else(default_algorithm);
+
 		// calc work
 		let item_and_target_list: Vec<(Hashing::Output, PowTarget)> = (0..self.shard_count).map(|x| {
 			let job = self.shard_jobs.get(&x);
 			let item_and_target = match job {
-				Some((_, job)) => (job.hash, job.digest_item.pow_target),
-				None => (Default::default(), Default::default()),
+				Some((_, job)) if job.algorithm == algorithm => (job.hash, job.digest_item.pow_target),
+				_ => (Default::default(), Default::default()),
 			};
 			item_and_target
 		}).collect();
@@ -249,6 +273,7 @@ impl<Number, AuthorityId, Hashing> RawWork<Number, AuthorityId, Hashing> where
 			shard_block_number,
 			nonce: None,
 			nonce_target: None,
+			algorithm,
 		};
 
 		self.merkle_tree = Some(merkle_tree);
@@ -333,7 +358,7 @@ impl<Number, AuthorityId, Hashing> DefaultWorkManager<Number, AuthorityId, Hashi
 		Ok(())
 	}
 
-	fn accept_work_future(&self, work: Work<Hashing::Output, Number>) -> Box<dyn Future<Item=(), Error=error::Error> + Send> {
+	fn accept_work_future(&self, work: Work<Hashing::Output, Number>) -> Box<dyn Future<Item=Vec<MinedBlock<Hashing::Output, Number>>, Error=error::Error> + Send> {
 		let nonce = work.nonce.expect("qed");
 		let nonce_target = work.nonce_target.expect("qed");
 
@@ -362,6 +387,9 @@ impl<Number, AuthorityId, Hashing> DefaultWorkManager<Number, AuthorityId, Hashi
 
 		for actual_shard_num in shard_num_list {
 			if let Some((config_shard_num, job)) = shard_jobs.get(&actual_shard_num) {
+				if job.algorithm != work.algorithm {
+					continue;
+				}
 				let job_target = job.digest_item.pow_target;
 
 				let config_shard_num = config_shard_num.clone();
@@ -434,6 +462,13 @@ impl<Number, AuthorityId, Hashing> DefaultWorkManager<Number, AuthorityId, Hashi
 						submitted_number2.insert(config_shard_num, (job.header.number.clone(), Instant::now()));
 					}
 
+					let mined = MinedBlock {
+						shard_num: actual_shard_num,
+						config_shard_num,
+						number: job.header.number.clone(),
+						hash: result,
+					};
+
 					future::loop_fn(0usize, move |i| {
 						let job_block_number = job.header.number.clone();
 						let jobs = jobs.clone();
@@ -469,17 +504,30 @@ impl<Number, AuthorityId, Hashing> DefaultWorkManager<Number, AuthorityId, Hashi
 								}
 							}
 						})
-					})
+					}).map(move |_| mined)
 				});
-				tasks.push(task);
+				// a shard failing should not cancel the submitting to the others
+				tasks.push(task.then(|result| Ok::<_, error::Error>(result)));
 			}
 		}
 
 		let tasks = future::join_all(tasks)
-			.map(|_| ())
-			.map_err(|e| {
-				warn!("{:?}", e);
-				e
+			.and_then(|results| {
+				let mut mined = Vec::new();
+				let mut first_error = None;
+				for result in results {
+					match result {
+						Ok(block) => mined.push(block),
+						Err(e) => {
+							warn!("{:?}", e);
+							first_error = first_error.or(Some(e));
+						},
+					}
+				}
+				match first_error {
+					Some(e) if mined.is_empty() => Err(e),
+					_ => Ok(mined),
+				}
 			});
 		Box::new(tasks)
 	}
@@ -29,6 +29,7 @@ use log::warn;
 use runtime_primitives::traits::{Hash as HashT};
 use parity_codec::{Decode, Encode};
 use yee_consensus_pow_primitives::PowTarget;
+use yee_consensus_pow::get_algorithm;
 use log::debug;
 use parking_lot::RwLock;
 
@@ -82,11 +83,20 @@ impl<WM> Worker<WM> where
 						}
 						let nonce_end = nonce_start + NONCE_STEPS;
 
+						let algorithm = match get_algorithm(&work.algorithm) {
+							Ok(algorithm) => algorithm,
+							Err(e) => {
+								warn!("miner error: {}", e);
+								sleep(Duration::from_secs(3));
+								continue;
+							}
+						};
+
 						for nonce in nonce_start..nonce_end{
 
 							//test hash
 							let source = (work.merkle_root.clone(), nonce, work.extra_data.clone());
-							let source_hash = WM::Hashing::hash_of(&source);
+							let source_hash = algorithm.hash(&source.encode());
 							let source_pow_target = PowTarget::from(source_hash.as_ref());
 							if &source_pow_target <= &work.target {
 								let mut work_result = work.clone();
@@ -1,20 +1,24 @@
-use parity_codec::{Decode, Compact};
-// use yee_sr_primitives::{OriginExtrinsic, RelayParams};
+use parity_codec::Decode;
+use yee_sr_primitives::{OriginExtrinsic, RelayCallInfo, RelayParams, RelayTypes};
 use runtime_primitives::{
+    generic,
     traits::{BlakeTwo256, Hash},
 };
 use serde::{Deserialize, Serialize};
 use substrate_primitives::H256;
 
-use yee_assets::Call as AssetsCall;
-use yee_balances::Call as BalancesCall;
+use yee_consensus_pow::CompatibleDigestItem;
 use yee_relay::Call as RelayCall;
 use yee_runtime::{
-    // AccountId,
+    AccountId,
+    AuthorityId,
+    AuthoritySignature,
+    Block as RuntimeBlock,
     Call,
-    // Hash as RuntimeHash,
+    Hash as RuntimeHash,
     UncheckedExtrinsic,
 };
+use yee_sharding::{ScaleOutPhase, ScaleOutPhaseDigestItem, ShardingDigestItem};
 
 use crate::serde::SerdeHex;
 
@@ -53,31 +57,61 @@ pub struct Digest {
     pub logs: Vec<String>,
 }
 
-pub fn decode_extrinsic(ec: Vec<u8>, tc: u16, cs: u16) -> (bool, Option<H256>) {
+type DigestItem = generic::DigestItem<RuntimeHash, AuthorityId, AuthoritySignature>;
+
+impl Header {
+    fn digest_items(&self) -> Vec<DigestItem> {
+        self.digest.logs.iter().filter_map(|log| {
+            let bytes = hex::decode(log.trim_start_matches("0x")).ok()?;
+            Decode::decode(&mut bytes.as_slice())
+        }).collect()
+    }
+
+    /// (shard num, shard count) of the block
+    pub fn shard_info(&self) -> Option<(u16, u16)> {
+        self.digest_items().iter().filter_map(ShardingDigestItem::as_sharding_info).next()
+    }
+
+    /// Fork id of the block, from its pow seal
+    pub fn fork_id(&self) -> Option<u32> {
+        self.digest_items().iter()
+            .filter_map(<DigestItem as CompatibleDigestItem<RuntimeBlock, AuthorityId>>::as_pow_seal)
+            .next()
+            .and_then(|seal| seal.extra.fork_id)
+    }
+
+    /// The new shard count, if the block commits a scale out
+    pub fn scale_out_committed(&self) -> Option<u16> {
+        self.digest_items().iter()
+            .filter_map(ScaleOutPhaseDigestItem::<u64, u16>::as_scale_out_phase)
+            .filter_map(|phase| match phase {
+                ScaleOutPhase::Committed { shard_count, .. } => Some(shard_count),
+                _ => None,
+            })
+            .next()
+    }
+}
+
+/// Classify an extrinsic by the relay calls in `registry`: (true, hash) for a cross shard
+/// origin extrinsic, (false, origin hash) for a relay extrinsic
+pub fn decode_extrinsic(ec: Vec<u8>, tc: u16, cs: u16, registry: &[RelayCallInfo]) -> (bool, Option<H256>) {
     let ex: UncheckedExtrinsic = match Decode::decode(&mut ec.as_slice()){
         Some(v) => v,
         None => return (false, None)
     };
     if ex.signature.is_some() {
         let h = BlakeTwo256::hash(ec.as_slice());
-        match ex.function {
-            Call::Balances(BalancesCall::transfer(dest, _value)) => {
-                let ds = yee_sharding_primitives::utils::shard_num_for(&dest, tc).expect("qed");
-                if cs != ds {
-                    return (true, Some(h));
-                }
-            }
-            Call::Assets(AssetsCall::transfer(_shard_code, _id, dest, _value)) => {
-                let ds = yee_sharding_primitives::utils::shard_num_for(&dest, tc).expect("qed");
-                if cs != ds {
-                    return (true, Some(h));
-                }
+        if let Some(origin) = OriginExtrinsic::<AccountId, u128>::decode(registry, ec) {
+            let ds = yee_sharding_primitives::utils::shard_num_for(&origin.to(), tc).expect("qed");
+            if cs != ds {
+                return (true, Some(h));
             }
-            _ => {}
         }
         return (false, None);
     } else {
         match ex.function {
+            // receipts have no origin extrinsic to pair with
+            Call::Relay(RelayCall::transfer(RelayTypes::Receipt, _, _, _, _)) => {}
             Call::Relay(RelayCall::transfer(_, otx, _, _, _)) => {
                 let h = BlakeTwo256::hash(otx.as_slice());
                 return (false, Some(h));
@@ -87,4 +121,20 @@ pub fn decode_extrinsic(ec: Vec<u8>, tc: u16, cs: u16) -> (bool, Option<H256>) {
     }
 
     return (true, None);
-}
\ No newline at end of file
+}
+
+/// The hash of the origin extrinsic refunded by a receipt extrinsic: the receipt of a relayed
+/// transfer is only sent when the transfer failed on the destination shard
+pub fn decode_refund(ec: Vec<u8>) -> Option<H256> {
+    let ex: UncheckedExtrinsic = Decode::decode(&mut ec.as_slice())?;
+    match ex.function {
+        Call::Relay(RelayCall::transfer(RelayTypes::Receipt, otx, _, _, _)) => {
+            let relay = RelayParams::<H256>::decode(otx)?;
+            match relay.relay_type() {
+                RelayTypes::Message | RelayTypes::Receipt => None,
+                _ => Some(relay.hash()),
+            }
+        }
+        _ => None,
+    }
+}
@@ -22,6 +22,7 @@ use yee_switch_rpc::{self, author::AuthorApi, state::StateApi};
 use yee_switch_rpc::system::SystemApi;
 use yee_switch_rpc::chain::ChainApi;
 use yee_switch_rpc::pow::PowApi;
+use yee_switch_rpc::relay::RelayApi;
 
 /// Maximal payload accepted by RPC servers
 const MAX_PAYLOAD: usize = 15 * 1024 * 1024;
@@ -32,18 +33,20 @@ pub type HttpServer = http::Server;
 pub type WsServer = ws::Server;
 
 /// Construct rpc `IoHandler`
-pub fn rpc_handler<A, S, Y, C, P, Hash, Number>(
+pub fn rpc_handler<A, S, Y, C, P, R, Hash, Number>(
 	author: A,
 	state: S,
 	system: Y,
 	chain: C,
 	pow: Option<P>,
+	relay: Option<R>,
 ) -> RpcHandler where
-	A: AuthorApi<Hash>,
-	S: StateApi<Hash>,
+	A: AuthorApi<Hash, Metadata=Metadata>,
+	S: StateApi<Hash, Metadata=Metadata>,
 	Y: SystemApi,
-	C: ChainApi<Number, Hash>,
+	C: ChainApi<Number, Hash, Metadata=Metadata>,
 	P: PowApi<Hash>,
+	R: RelayApi<Hash>,
 	Hash: Send + Sync + 'static + sr_primitives::Serialize + sr_primitives::DeserializeOwned,
 	Number: Send + Sync + 'static + sr_primitives::Serialize + sr_primitives::DeserializeOwned,
 {   let mut io = pubsub::PubSubHandler::default();
@@ -54,6 +57,9 @@ pub fn rpc_handler<A, S, Y, C, P, Hash, Number>(
 	pow.map(|pow|{
 		io.extend_with(pow.to_delegate());
 	});
+	relay.map(|relay|{
+		io.extend_with(relay.to_delegate());
+	});
 	io
 }
 
@@ -18,61 +18,78 @@
 use jsonrpc_derive::rpc;
 use primitives::{Bytes, sr25519::{Public}};
 use crate::Config;
-use crate::client::RpcClient;
+use crate::client::{RpcClient, BoxStream};
 use crate::errors;
 use serde::{Serialize};
 use serde::de::DeserializeOwned;
 use parity_codec::{Encode, Decode};
 use runtime_primitives::OpaqueExtrinsic;
 use yee_sharding_primitives::utils::shard_num_for_bytes;
-use jsonrpc_core::BoxFuture;
-use crate::rpc::{self, futures::future::{self, FutureResult}};
+use jsonrpc_core::{BoxFuture, Result as RpcResult};
+use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
+use serde_json::Value;
+use crate::rpc::{self, futures::{Future, Stream, future::{self, FutureResult}}};
+use crate::subscriptions::{self, Subscriptions};
+use yee_rpc::misc::fee::FeeDetail;
 
 /// Substrate authoring RPC API
 #[rpc]
 pub trait AuthorApi<Hash> {
+	/// RPC metadata
+	type Metadata;
 
 	/// Submit hex-encoded extrinsic for inclusion in block.
 	#[rpc(name = "author_submitExtrinsic")]
 	fn submit_extrinsic(&self, extrinsic: Bytes) -> BoxFuture<Hash>;
+
+	/// Fee breakdown of a hex-encoded extrinsic, estimated on the shard of its sender.
+	#[rpc(name = "author_estimateFee")]
+	fn estimate_fee(&self, extrinsic: Bytes, hash: Option<Hash>) -> BoxFuture<FeeDetail>;
+
+	/// Submit an extrinsic to the shard of its sender and watch its status.
+	#[pubsub(subscription = "author_extrinsicUpdate", subscribe, name = "author_submitAndWatchExtrinsic")]
+	fn watch_extrinsic(&self, metadata: Self::Metadata, subscriber: Subscriber<Value>, extrinsic: Bytes);
+
+	/// Unsubscribe from extrinsic watching.
+	#[pubsub(subscription = "author_extrinsicUpdate", unsubscribe, name = "author_unwatchExtrinsic")]
+	fn unwatch_extrinsic(&self, metadata: Option<Self::Metadata>, id: SubscriptionId) -> RpcResult<bool>;
 }
 
 /// Authoring API
 pub struct Author {
 	config : Config,
 	rpc_client: RpcClient,
+	subscriptions: Subscriptions,
 }
 
 impl Author {
 	/// Create new State API RPC handler.
-	pub fn new(config: Config) -> Self {
+	pub fn new(rpc_client: RpcClient, subscriptions: Subscriptions) -> Self {
 		Self {
-			config: config.clone(),
-			rpc_client: RpcClient::new(config)
+			config: rpc_client.config().clone(),
+			rpc_client,
+			subscriptions,
 		}
 	}
-}
 
-impl<Hash> AuthorApi<Hash> for Author
-	where Hash: Send + Sync + 'static + Serialize + DeserializeOwned
-{
-	fn submit_extrinsic(&self, extrinsic: Bytes) -> BoxFuture<Hash> {
+	/// Shard of the sender of a signed extrinsic
+	fn shard_num_for_extrinsic(&self, extrinsic: &Bytes) -> errors::Result<u16> {
 
 		let xt : OpaqueExtrinsic = match Decode::decode(&mut &extrinsic[..]){
 			Some(xt) => xt,
-			None => return Box::new(future::err(errors::Error::from(errors::ErrorKind::ParseError).into())),
+			None => return Err(errors::ErrorKind::ParseError.into()),
 		};
 
 		let bytes : &[u8] = &xt.0;
 		if bytes.len() < 64 {
-			return Box::new(future::err(errors::Error::from(errors::ErrorKind::ParseError).into()));
+			return Err(errors::ErrorKind::ParseError.into());
 		}
 		let version = bytes[0];
 
 		let is_signed = version & 0b1000_0000 != 0;
 
 		if !is_signed {
-			return Box::new(future::err(errors::Error::from(errors::ErrorKind::ParseError).into()));
+			return Err(errors::ErrorKind::ParseError.into());
 		}
 
 		let address = &bytes[1..34];//0xFF + 32bytes
@@ -85,13 +102,60 @@ impl<Hash> AuthorApi<Hash> for Author
 
 		let shard_num = match shard_num_for_bytes(address, shard_count){
 			Some(shard_num) => shard_num,
-			None => return Box::new(future::err(errors::Error::from(errors::ErrorKind::InvalidShard).into())),
+			None => return Err(errors::ErrorKind::InvalidShard.into()),
 		};
 
 		log::debug!("shard_count: {}, shard_num: {}", shard_count, shard_num);
 
+		Ok(shard_num)
+	}
+}
+
+impl<Hash> AuthorApi<Hash> for Author
+	where Hash: Send + Sync + 'static + Serialize + DeserializeOwned
+{
+	type Metadata = crate::metadata::Metadata;
+
+	fn submit_extrinsic(&self, extrinsic: Bytes) -> BoxFuture<Hash> {
+
+		let shard_num = match self.shard_num_for_extrinsic(&extrinsic) {
+			Ok(shard_num) => shard_num,
+			Err(e) => return Box::new(future::err(e.into())),
+		};
+
 		self.rpc_client.call_method_async("author_submitExtrinsic", "Hash", (extrinsic,), shard_num)
 			.unwrap_or_else(|e|Box::new(future::err(e.into())))
 	}
 
+	fn estimate_fee(&self, extrinsic: Bytes, hash: Option<Hash>) -> BoxFuture<FeeDetail> {
+
+		let shard_num = match self.shard_num_for_extrinsic(&extrinsic) {
+			Ok(shard_num) => shard_num,
+			Err(e) => return Box::new(future::err(e.into())),
+		};
+
+		self.rpc_client.call_method_async("author_estimateFee", "FeeDetail", (extrinsic, hash), shard_num)
+			.unwrap_or_else(|e|Box::new(future::err(e.into())))
+	}
+
+	fn watch_extrinsic(&self, _metadata: Self::Metadata, subscriber: Subscriber<Value>, extrinsic: Bytes) {
+
+		let status: errors::Result<BoxStream<Value>> = self.shard_num_for_extrinsic(&extrinsic).and_then(|shard_num| {
+			self.rpc_client.subscribe("author_submitAndWatchExtrinsic", (extrinsic,), "author_extrinsicUpdate", "author_unwatchExtrinsic", "Status", shard_num)
+		});
+		let status = match status {
+			Ok(status) => status,
+			Err(e) => {
+				let _ = subscriber.reject(e.into());
+				return;
+			}
+		};
+
+		self.subscriptions.add(subscriber, |sink| subscriptions::forward(sink, status, "extrinsic status"));
+	}
+
+	fn unwatch_extrinsic(&self, _metadata: Option<Self::Metadata>, id: SubscriptionId) -> RpcResult<bool> {
+		Ok(self.subscriptions.cancel(id))
+	}
+
 }
@@ -20,22 +20,26 @@ mod number;
 use jsonrpc_derive::rpc;
 use primitives::{Bytes, sr25519::{Public}};
 use crate::Config;
-use crate::client::RpcClient;
+use crate::client::{RpcClient, BoxStream};
 use crate::errors;
-use serde::{Serialize};
+use crate::subscriptions::{self, Subscriptions};
+use serde::{Serialize, Deserialize};
 use serde::de::DeserializeOwned;
 use parity_codec::{Encode, Decode};
 use runtime_primitives::OpaqueExtrinsic;
 use number::NumberOrHex;
 use serde_json::Value;
 use crate::rpc::{self, futures::future::{self, FutureResult}};
-use futures::Future;
+use futures::{Future, Stream, stream};
 use jsonrpc_client_transports::RpcError;
-use jsonrpc_core::BoxFuture;
+use jsonrpc_core::{BoxFuture, Result as RpcResult};
+use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
 
 /// Substrate authoring RPC API
 #[rpc]
 pub trait ChainApi<Number, Hash> {
+	/// RPC metadata
+	type Metadata;
 
 	/// Get header of a relay chain block.
 	#[rpc(name = "chain_getHeader")]
@@ -54,20 +58,38 @@ pub trait ChainApi<Number, Hash> {
 	/// Recommit relay transfer to dest shard nodes
 	#[rpc(name = "chain_recommitRelay")]
 	fn recommit_relay(&self, shard_num: u16, hash: Hash, index: u16)  -> BoxFuture<Option<Value>>;
+
+	/// New head subscription of a shard, or of all the shards if `shard_num` is not given
+	#[pubsub(subscription = "chain_newHead", subscribe, name = "chain_subscribeNewHeads")]
+	fn subscribe_new_heads(&self, metadata: Self::Metadata, subscriber: Subscriber<ShardHeader>, shard_num: Option<u16>);
+
+	/// Unsubscribe from new head subscription.
+	#[pubsub(subscription = "chain_newHead", unsubscribe, name = "chain_unsubscribeNewHeads")]
+	fn unsubscribe_new_heads(&self, metadata: Option<Self::Metadata>, id: SubscriptionId) -> RpcResult<bool>;
+}
+
+/// New head of a shard
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ShardHeader {
+	pub shard_num: u16,
+	pub header: Value,
 }
 
 /// Chain API
 pub struct Chain {
 	config : Config,
 	rpc_client: RpcClient,
+	subscriptions: Subscriptions,
 }
 
 impl Chain {
 	/// Create new State API RPC handler.
-	pub fn new(config: Config) -> Self {
+	pub fn new(rpc_client: RpcClient, subscriptions: Subscriptions) -> Self {
 		Self {
-			config: config.clone(),
-			rpc_client: RpcClient::new(config)
+			config: rpc_client.config().clone(),
+			rpc_client,
+			subscriptions,
 		}
 	}
 }
@@ -76,6 +98,8 @@ impl<Number, Hash> ChainApi<Number, Hash> for Chain
 	where Hash: Send + Sync + 'static + Serialize + DeserializeOwned,
 		  Number: Send + Sync + 'static + Serialize + DeserializeOwned,
 {
+	type Metadata = crate::metadata::Metadata;
+
 	fn header(&self, shard_num: u16, hash: Option<Hash>) -> BoxFuture<Option<Value>>{
 
 		let shard_count = self.config.get_shard_count();
@@ -123,4 +147,36 @@ impl<Number, Hash> ChainApi<Number, Hash> for Chain
 		self.rpc_client.call_method_async("chain_recommitRelay", "Option<Value>", (hash, index), shard_num)
 			.unwrap_or_else(|e|Box::new(future::err(e.into())))
 	}
+
+	fn subscribe_new_heads(&self, _metadata: Self::Metadata, subscriber: Subscriber<ShardHeader>, shard_num: Option<u16>) {
+
+		let shard_count = self.config.get_shard_count();
+
+		let shards: Vec<u16> = match shard_num {
+			Some(shard_num) if shard_num >= shard_count => {
+				let _ = subscriber.reject(errors::Error::from(errors::ErrorKind::InvalidShard).into());
+				return;
+			},
+			Some(shard_num) => vec![shard_num],
+			None => (0..shard_count).collect(),
+		};
+
+		let mut heads: BoxStream<ShardHeader> = Box::new(stream::empty());
+		for shard_num in shards {
+			let stream: BoxStream<Value> = match self.rpc_client.subscribe("chain_subscribeNewHead", (), "chain_newHead", "chain_unsubscribeNewHead", "Header", shard_num) {
+				Ok(stream) => stream,
+				Err(e) => {
+					let _ = subscriber.reject(e.into());
+					return;
+				},
+			};
+			heads = Box::new(heads.select(stream.map(move |header| ShardHeader { shard_num, header })));
+		}
+
+		self.subscriptions.add(subscriber, |sink| subscriptions::forward(sink, heads, "new heads"));
+	}
+
+	fn unsubscribe_new_heads(&self, _metadata: Option<Self::Metadata>, id: SubscriptionId) -> RpcResult<bool> {
+		Ok(self.subscriptions.cancel(id))
+	}
 }
@@ -15,52 +15,319 @@
 // You should have received a copy of the GNU General Public License
 // along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
 
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use serde::{Serialize, Deserialize};
 use serde::de::DeserializeOwned;
+use serde_json::Value;
 use jsonrpc_core_client::TypedClient;
-use crate::rpc::futures::{Future};
+use crate::rpc::futures::{Async, Future, Poll, Stream, future, stream};
 use crate::Config;
 use crate::errors;
-use rand::Rng;
+use rand::seq::SliceRandom;
 use jsonrpc_client_transports::RpcError;
-use impl_serde::serialize;
-use num_bigint::BigUint;
 use jsonrpc_core::BoxFuture;
-use yee_serde_hex::SerdeHex;
-use std::time::Duration;
+use parking_lot::{Mutex, RwLock};
 use tokio::prelude::FutureExt;
+use tokio::runtime::{current_thread, Runtime};
+use log::{debug, warn};
+use mining::ledger::ShardRpc;
 
-pub struct RpcClient{
-    config: Config,
+pub type BoxStream<T> = Box<dyn Stream<Item=T, Error=jsonrpc_core::Error> + Send>;
+
+/// Timeout of a call to an endpoint
+const CALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Blocks behind the highest best block an endpoint is still considered at the tip
+const TIP_TOLERANCE: u64 = 3;
+
+/// Interval of the endpoint health probing
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Backoff of a failing endpoint, doubled for every consecutive failure
+const MIN_BACKOFF: Duration = Duration::from_secs(5);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Status of a node behind the switch
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointStatus {
+    pub shard_num: u16,
+    pub uri: String,
+    pub healthy: bool,
+    /// Best block number of the node, by the last health probe
+    pub best_number: Option<u64>,
+    /// Consecutive failures
+    pub failures: u32,
+    pub last_error: Option<String>,
 }
 
-impl RpcClient{
+struct Endpoint {
+    status: EndpointStatus,
+    // out of rotation until
+    retry_at: Option<Instant>,
+}
 
-    pub fn new(config: Config) -> Self{
+impl Endpoint {
+    fn available(&self, now: Instant) -> bool {
+        self.retry_at.map(|at| at <= now).unwrap_or(true)
+    }
+}
 
-        RpcClient{
-            config,
+/// Health of the endpoints of every shard, shared by the clones of a `RpcClient`
+struct Endpoints {
+    shards: RwLock<HashMap<u16, Vec<Endpoint>>>,
+}
+
+impl Endpoints {
+    fn new(config: &Config) -> Self {
+        let shards = config.shards.iter().filter_map(|(shard_num, shard)| {
+            let shard_num: u16 = shard_num.parse().ok()?;
+            let endpoints = shard.rpc.iter().map(|uri| Endpoint {
+                status: EndpointStatus {
+                    shard_num,
+                    uri: uri.clone(),
+                    healthy: true,
+                    best_number: None,
+                    failures: 0,
+                    last_error: None,
+                },
+                retry_at: None,
+            }).collect();
+            Some((shard_num, endpoints))
+        }).collect();
+        Self { shards: RwLock::new(shards) }
+    }
+
+    /// Endpoints of a shard to try in order: the available ones near the tip first, at random
+    /// to spread the load, then the lagging ones with the highest best block first, then the
+    /// ones out of rotation in case all of them are failing.
+    fn candidates(&self, shard_num: u16) -> errors::Result<Vec<String>> {
+        let shards = self.shards.read();
+        let endpoints = match shards.get(&shard_num) {
+            Some(endpoints) if !endpoints.is_empty() => endpoints,
+            _ => return Err(errors::Error::from(errors::ErrorKind::ConfigError)),
+        };
+
+        let now = Instant::now();
+        let mut rng = rand::thread_rng();
+        let available: Vec<&Endpoint> = endpoints.iter().filter(|e| e.available(now)).collect();
+        let tip = available.iter().filter_map(|e| e.status.best_number).max();
+        let near_tip = |e: &&Endpoint| match (e.status.best_number, tip) {
+            (Some(number), Some(tip)) => number + TIP_TOLERANCE >= tip,
+            (None, Some(_)) => false,
+            (_, None) => true,
+        };
+        let (mut near, mut lagging): (Vec<&Endpoint>, Vec<&Endpoint>) = available.into_iter().partition(near_tip);
+        near.shuffle(&mut rng);
+        lagging.sort_by(|a, b| b.status.best_number.cmp(&a.status.best_number));
+        let mut backoff: Vec<&Endpoint> = endpoints.iter().filter(|e| !e.available(now)).collect();
+        backoff.sort_by_key(|e| e.retry_at);
+
+        Ok(near.into_iter().chain(lagging).chain(backoff).map(|e| e.status.uri.clone()).collect())
+    }
+
+    fn on_success(&self, shard_num: u16, uri: &str, best_number: Option<u64>) {
+        self.update(shard_num, uri, |endpoint| {
+            endpoint.status.healthy = true;
+            endpoint.status.failures = 0;
+            endpoint.status.last_error = None;
+            if best_number.is_some() {
+                endpoint.status.best_number = best_number;
+            }
+            endpoint.retry_at = None;
+        });
+    }
+
+    fn on_failure(&self, shard_num: u16, uri: &str, error: String) {
+        warn!("RPC endpoint failed: shard: {}, uri: {}, error: {}", shard_num, uri, error);
+        self.update(shard_num, uri, |endpoint| {
+            endpoint.status.healthy = false;
+            endpoint.status.failures = endpoint.status.failures.saturating_add(1);
+            endpoint.status.last_error = Some(error);
+            let backoff = MIN_BACKOFF * 2u32.pow(endpoint.status.failures.min(16) - 1);
+            endpoint.retry_at = Some(Instant::now() + backoff.min(MAX_BACKOFF));
+        });
+    }
+
+    fn update<F: FnOnce(&mut Endpoint)>(&self, shard_num: u16, uri: &str, f: F) {
+        let mut shards = self.shards.write();
+        if let Some(endpoint) = shards.get_mut(&shard_num).and_then(|endpoints| endpoints.iter_mut().find(|e| e.status.uri == uri)) {
+            f(endpoint);
         }
     }
 
-    fn get_random_rpc_uri(&self, shard_num: u16) -> errors::Result<String>  {
+    fn status(&self) -> Vec<EndpointStatus> {
+        let mut status: Vec<EndpointStatus> = self.shards.read().values()
+            .flat_map(|endpoints| endpoints.iter().map(|e| e.status.clone()))
+            .collect();
+        status.sort_by(|a, b| (a.shard_num, &a.uri).cmp(&(b.shard_num, &b.uri)));
+        status
+    }
+}
 
-        let shard = self.config.shards.get(&format!("{}", shard_num)).ok_or(errors::Error::from(errors::ErrorKind::ConfigError))?;
+/// Websocket connections to the nodes, shared by the subscriptions to the same node
+struct WsClients {
+    clients: Arc<Mutex<HashMap<String, TypedClient>>>,
+}
 
-        let rpc = &shard.rpc;
+impl WsClients {
+    fn new() -> Self {
+        Self { clients: Default::default() }
+    }
 
-        if rpc.len()==0{
-            return Err(errors::Error::from(errors::ErrorKind::ConfigError));
+    fn subscribe<R: DeserializeOwned + 'static + Send>(&self, uri: &str, request: &SubscribeRequest) -> BoxStream<R> {
+        let url = match url::Url::parse(uri) {
+            Ok(url) => url,
+            Err(_) => return Box::new(stream::once(Err(errors::Error::from(errors::ErrorKind::ConfigError).into()))),
+        };
+
+        let connected = self.clients.lock().get(uri).cloned();
+        let client: Box<dyn Future<Item=TypedClient, Error=RpcError> + Send> = match connected {
+            Some(client) => Box::new(future::ok(client)),
+            None => {
+                let (clients, uri) = (self.clients.clone(), uri.to_string());
+                Box::new(jsonrpc_core_client::transports::ws::connect(&url).map(move |client: TypedClient| {
+                    clients.lock().insert(uri, client.clone());
+                    client
+                }))
+            }
+        };
+
+        let (clients, uri) = (self.clients.clone(), uri.to_string());
+        let SubscribeRequest { subscribe, params, topic, unsubscribe, returns } = request.clone();
+        let stream = client
+            .and_then(move |client: TypedClient| {
+                client.subscribe(&subscribe, params, &topic, &unsubscribe, returns)
+            })
+            .flatten_stream()
+            .map_err(move |e| -> jsonrpc_core::Error {
+                log::error!("RPC Client subscription error: {:?}", e);
+                // a new connection for the next subscriptions, unless the node rejected the request
+                match e {
+                    RpcError::JsonRpcError(_) => {}
+                    _ => {
+                        clients.lock().remove(&uri);
+                    }
+                }
+                parse_error(e).into()
+            });
+
+        Box::new(stream)
+    }
+}
+
+#[derive(Clone)]
+struct SubscribeRequest {
+    subscribe: String,
+    params: Value,
+    topic: String,
+    unsubscribe: String,
+    returns: &'static str,
+}
+
+/// Subscription to a node of a shard, moved to another node when the node fails.
+/// The nodes are tried once each, again once the subscription delivered notifications.
+/// Subscriptions not safe to repeat are made to a single node.
+struct Upstream<R> {
+    ws: Arc<WsClients>,
+    request: SubscribeRequest,
+    uris: Vec<String>,
+    // not tried yet since the last notification
+    remaining: Vec<String>,
+    current: Option<(String, BoxStream<R>)>,
+    notified: bool,
+}
+
+impl<R: DeserializeOwned + 'static + Send> Stream for Upstream<R> {
+    type Item = R;
+    type Error = jsonrpc_core::Error;
+
+    fn poll(&mut self) -> Poll<Option<R>, jsonrpc_core::Error> {
+        loop {
+            if let Some((uri, stream)) = self.current.as_mut() {
+                let ended = match stream.poll() {
+                    Ok(Async::Ready(Some(item))) => {
+                        self.notified = true;
+                        return Ok(Async::Ready(Some(item)));
+                    }
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    ended => ended,
+                };
+                if self.notified {
+                    let failed = uri.clone();
+                    self.remaining = self.uris.iter().filter(|uri| **uri != failed).cloned().collect();
+                    self.notified = false;
+                }
+                if self.remaining.is_empty() {
+                    return ended;
+                }
+                warn!("Upstream subscription ended: {}, uri: {}, resubscribing", self.request.subscribe, uri);
+                self.current = None;
+            }
+
+            let uri = match self.remaining.pop() {
+                Some(uri) => uri,
+                None => return Err(errors::Error::from(errors::ErrorKind::ConfigError).into()),
+            };
+            let stream = self.ws.subscribe(&uri, &self.request);
+            self.current = Some((uri, stream));
         }
+    }
+}
+
+#[derive(Clone)]
+pub struct RpcClient{
+    config: Config,
+    endpoints: Arc<Endpoints>,
+    ws: Arc<WsClients>,
+}
+
+impl RpcClient{
 
-        let mut rng =rand::thread_rng();
+    pub fn new(config: Config) -> Self{
 
-        let i = rng.gen_range(0, rpc.len());
+        RpcClient{
+            endpoints: Arc::new(Endpoints::new(&config)),
+            ws: Arc::new(WsClients::new()),
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
 
-        Ok(rpc[i].clone())
+    /// Status of every endpoint behind the switch
+    pub fn endpoint_status(&self) -> Vec<EndpointStatus> {
+        self.endpoints.status()
+    }
 
+    /// Probe every endpoint periodically with `system_health` and `chain_getHeader`
+    pub fn start_health_check(&self) {
+        let endpoints = self.endpoints.clone();
+        let _ = std::thread::Builder::new().name("rpc-health-check".to_string()).spawn(move || {
+            let mut rt = Runtime::new().expect("can't start rpc-health-check thread");
+            loop {
+                let targets: Vec<(u16, String)> = endpoints.shards.read().iter()
+                    .flat_map(|(shard_num, list)| list.iter().map(move |e| (*shard_num, e.status.uri.clone())))
+                    .collect();
+                for (shard_num, uri) in targets {
+                    match rt.block_on(probe(&uri)) {
+                        Ok(best_number) => {
+                            debug!("RPC endpoint healthy: shard: {}, uri: {}, best number: {}", shard_num, uri, best_number);
+                            endpoints.on_success(shard_num, &uri, Some(best_number));
+                        }
+                        Err(e) => endpoints.on_failure(shard_num, &uri, e),
+                    }
+                }
+                std::thread::sleep(HEALTH_CHECK_INTERVAL);
+            }
+        });
     }
 
+    /// Blocking call, not to be used from within a tokio runtime
     pub fn call_method<T: Serialize, R: DeserializeOwned + 'static>(
         &self, method: &str,
         returns: &'static str,
@@ -68,16 +335,41 @@ impl RpcClient{
         shard_num: u16,
     ) -> errors::Result<R> {
 
-        let uri = self.get_random_rpc_uri(shard_num)?;
+        let params = serde_json::to_value(args).map_err(|_| errors::Error::from(errors::ErrorKind::ParseError))?;
+
+        let uris = self.uris_for(method, shard_num)?;
 
-        let result = jsonrpc_core_client::transports::http::connect(&uri)
-            .and_then(|client: TypedClient| {
-                client.call_method(method, returns, args).and_then(move |result| {
-                    Ok(result)
+        // the timer of the timeout is driven by the runtime
+        let mut rt = current_thread::Runtime::new().map_err(|e| parse_error(RpcError::Other(e.into())))?;
+        let mut last_error = None;
+        for uri in uris {
+            let call = jsonrpc_core_client::transports::http::connect(&uri)
+                .and_then(|client: TypedClient| {
+                    client.call_method(method, returns, params.clone()).and_then(move |result| {
+                        Ok(result)
+                    })
                 })
-            }).wait().map_err(|e| {log::error!("RPC Client error: {:?}", e); e}).map_err(parse_error);
+                .timeout(CALL_TIMEOUT)
+                .map_err(|e| e.into_inner().unwrap_or(RpcError::Timeout));
+            let result = rt.block_on(call);
+            match result {
+                Ok(result) => {
+                    self.endpoints.on_success(shard_num, &uri, None);
+                    return Ok(result);
+                }
+                Err(e @ RpcError::JsonRpcError(_)) => {
+                    self.endpoints.on_success(shard_num, &uri, None);
+                    return Err(parse_error(e));
+                }
+                Err(e) => {
+                    log::error!("RPC Client error: {:?}", e);
+                    self.endpoints.on_failure(shard_num, &uri, format!("{:?}", e));
+                    last_error = Some(e);
+                }
+            }
+        }
 
-        result
+        Err(last_error.map(parse_error).unwrap_or(errors::Error::from(errors::ErrorKind::ConfigError)))
     }
 
     pub fn call_method_async<T: Serialize + 'static + Send, R: DeserializeOwned + 'static + Send>(
@@ -87,23 +379,202 @@ impl RpcClient{
         shard_num: u16,
     ) ->  errors::Result<BoxFuture<R>> {
 
-        let uri = self.get_random_rpc_uri(shard_num)?;
+        let params = serde_json::to_value(args).map_err(|_| errors::Error::from(errors::ErrorKind::ParseError))?;
 
-        let method = method.to_owned();
+        let uris = self.uris_for(method, shard_num)?;
 
-        let run = jsonrpc_core_client::transports::http::connect(&uri)
-            .and_then(move |client: TypedClient| {
-                client.call_method(&method, "returns", args).and_then(move |result| {
-                    Ok(result)
-                })
-            }).map_err(|e| {log::error!("RPC Client error: {:?}", e); e}).map_err(parse_error).map_err(|e|e.into());
+        Ok(call_uris(self.endpoints.clone(), shard_num, uris, method.to_owned(), params))
+    }
 
-        Ok(Box::new(run))
+    /// Subscribe to a random upstream node of the shard over websocket, resubscribing to
+    /// another node when the node fails
+    pub fn subscribe<T: Serialize, R: DeserializeOwned + 'static + Send>(
+        &self, subscribe: &str,
+        args: T,
+        topic: &str,
+        unsubscribe: &str,
+        returns: &'static str,
+        shard_num: u16,
+    ) -> errors::Result<BoxStream<R>> {
+
+        let mut uris = self.get_ws_uris(shard_num)?;
+        if !is_idempotent(subscribe) {
+            uris.truncate(1);
+        }
+
+        let params = serde_json::to_value(args).map_err(|_| errors::Error::from(errors::ErrorKind::ParseError))?;
+
+        let request = SubscribeRequest {
+            subscribe: subscribe.to_owned(),
+            params,
+            topic: topic.to_owned(),
+            unsubscribe: unsubscribe.to_owned(),
+            returns,
+        };
+        Ok(Box::new(Upstream {
+            ws: self.ws.clone(),
+            request,
+            remaining: uris.clone(),
+            uris,
+            current: None,
+            notified: false,
+        }))
     }
 
+    /// Websocket endpoints of the shard, in random order
+    fn get_ws_uris(&self, shard_num: u16) -> errors::Result<Vec<String>> {
+        let shard = self.config.shards.get(&format!("{}", shard_num)).ok_or(errors::Error::from(errors::ErrorKind::ConfigError))?;
+        if shard.ws.is_empty() {
+            return Err(errors::Error::from(errors::ErrorKind::ConfigError));
+        }
+        let mut uris = shard.ws.clone();
+        uris.shuffle(&mut rand::thread_rng());
+        Ok(uris)
+    }
+
+    /// Endpoints to call in order, only the first one for the calls not safe to repeat
+    fn uris_for(&self, method: &str, shard_num: u16) -> errors::Result<Vec<String>> {
+        let mut uris = self.endpoints.candidates(shard_num)?;
+        if !is_idempotent(method) {
+            uris.truncate(1);
+        }
+        Ok(uris)
+    }
+}
+
+impl ShardRpc for RpcClient {
+    fn call(&self, config_shard_num: u16, method: &str, params: Value) -> Box<dyn Future<Item=Value, Error=mining::error::Error> + Send> {
+        match self.call_method_async::<_, Value>(method, "", params, config_shard_num) {
+            Ok(result) => Box::new(result.map_err(|e| format!("{:?}", e).into())),
+            Err(e) => Box::new(future::err(format!("{:?}", e).into())),
+        }
+    }
+}
+
+/// Whether a call can be repeated on another endpoint after a transport failure
+fn is_idempotent(method: &str) -> bool {
+    !method.starts_with("author_")
+}
+
+fn call_uris<R: DeserializeOwned + 'static + Send>(
+    endpoints: Arc<Endpoints>,
+    shard_num: u16,
+    mut uris: Vec<String>,
+    method: String,
+    params: Value,
+) -> BoxFuture<R> {
+    if uris.is_empty() {
+        return Box::new(future::err(errors::Error::from(errors::ErrorKind::ConfigError).into()));
+    }
+    let uri = uris.remove(0);
+
+    let call_method = method.clone();
+    let call_params = params.clone();
+    let run = jsonrpc_core_client::transports::http::connect(&uri)
+        .and_then(move |client: TypedClient| {
+            client.call_method(&call_method, "returns", call_params)
+        })
+        .timeout(CALL_TIMEOUT)
+        .map_err(|e| e.into_inner().unwrap_or(RpcError::Timeout));
+
+    Box::new(run.then(move |result| -> BoxFuture<R> {
+        match result {
+            Ok(result) => {
+                endpoints.on_success(shard_num, &uri, None);
+                Box::new(future::ok(result))
+            }
+            Err(e @ RpcError::JsonRpcError(_)) => {
+                endpoints.on_success(shard_num, &uri, None);
+                Box::new(future::err(parse_error(e).into()))
+            }
+            Err(e) => {
+                log::error!("RPC Client error: {:?}", e);
+                endpoints.on_failure(shard_num, &uri, format!("{:?}", e));
+                if uris.is_empty() {
+                    Box::new(future::err(parse_error(e).into()))
+                } else {
+                    call_uris(endpoints, shard_num, uris, method, params)
+                }
+            }
+        }
+    }))
+}
+
+/// Best block number of a healthy node
+fn probe(uri: &str) -> impl Future<Item=u64, Error=String> {
+    jsonrpc_core_client::transports::http::connect(uri)
+        .and_then(|client: TypedClient| {
+            let health = client.call_method::<_, Value>("system_health", "Health", ());
+            let header = client.call_method::<_, Value>("chain_getHeader", "Header", ());
+            health.join(header)
+        })
+        .timeout(CALL_TIMEOUT)
+        .map_err(|e| format!("{:?}", e))
+        .and_then(|(health, header)| {
+            if health.get("isSyncing").and_then(Value::as_bool).unwrap_or(false) {
+                return Err("node is syncing".to_string());
+            }
+            header.get("number")
+                .and_then(Value::as_str)
+                .and_then(|number| u64::from_str_radix(number.trim_start_matches("0x"), 16).ok())
+                .ok_or("invalid best header".to_string())
+        })
 }
 
 fn parse_error(error: RpcError) -> errors::Error{
 
     errors::Error::from(errors::ErrorKind::RpcError(error))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yee_primitives::Shard;
+
+    fn endpoints() -> Endpoints {
+        let mut shards = HashMap::new();
+        shards.insert("0".to_string(), Shard { rpc: vec!["http://a".to_string(), "http://b".to_string(), "http://c".to_string()], ws: vec![] });
+        Endpoints::new(&Config { shards, hrp: None })
+    }
+
+    #[test]
+    fn candidates_prefer_the_nodes_near_the_tip() {
+        let endpoints = endpoints();
+        endpoints.on_success(0, "http://a", Some(10));
+        endpoints.on_success(0, "http://b", Some(20));
+        endpoints.on_success(0, "http://c", Some(18));
+        let mut firsts = std::collections::HashSet::new();
+        for _ in 0..64 {
+            let candidates = endpoints.candidates(0).unwrap();
+            assert_eq!(candidates[2], "http://a");
+            firsts.insert(candidates[0].clone());
+        }
+        // spread among the nodes near the tip
+        assert_eq!(firsts.len(), 2);
+        assert!(endpoints.candidates(1).is_err());
+    }
+
+    #[test]
+    fn lagging_nodes_by_best_block() {
+        let endpoints = endpoints();
+        endpoints.on_success(0, "http://a", Some(10));
+        endpoints.on_success(0, "http://b", Some(20));
+        endpoints.on_success(0, "http://c", Some(11));
+        assert_eq!(endpoints.candidates(0).unwrap(), vec!["http://b", "http://c", "http://a"]);
+    }
+
+    #[test]
+    fn failing_endpoint_is_out_of_rotation() {
+        let endpoints = endpoints();
+        endpoints.on_success(0, "http://a", Some(12));
+        endpoints.on_success(0, "http://b", Some(11));
+        endpoints.on_success(0, "http://c", Some(10));
+        endpoints.on_failure(0, "http://a", "refused".to_string());
+        let candidates = endpoints.candidates(0).unwrap();
+        assert_eq!(candidates[2], "http://a");
+
+        endpoints.on_success(0, "http://a", Some(20));
+        assert_eq!(endpoints.candidates(0).unwrap()[0], "http://a");
+        assert_eq!(endpoints.status().iter().map(|s| s.failures).sum::<u32>(), 0);
+    }
+}
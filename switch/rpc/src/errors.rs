@@ -44,9 +44,9 @@ error_chain! {
 			description("parse error"),
 			display("Parse error"),
 		}
-		InvalidAddress {
+		InvalidAddress(reason: String) {
 			description("invalid address"),
-			display("Invalid address"),
+			display("Invalid address: {}", reason),
 		}
 		InvalidShardCode {
 			description("invalid shard code"),
@@ -64,6 +64,14 @@ error_chain! {
 			description("submit work failed"),
 			display("Submit work failed: {}", reason),
 		}
+		RelayError(reason: String) {
+			description("relay failed"),
+			display("Relay failed: {}", reason),
+		}
+		DecodeError(reason: String) {
+			description("decode error"),
+			display("Decode error: {}", reason),
+		}
 	}
 }
 
@@ -91,10 +99,15 @@ impl From<Error> for rpc::Error {
 				message: "Parse error".into(),
 				data: None,
 			},
-			Error(ErrorKind::InvalidAddress, _) => rpc::Error {
+			Error(ErrorKind::InvalidAddress(reason), _) => rpc::Error {
 				code: rpc::ErrorCode::ServerError(1),
 				message: "Invalid address".into(),
-				data: None,
+				data: Some(reason.into()),
+			},
+			Error(ErrorKind::DecodeError(reason), _) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(1),
+				message: "Decode error".into(),
+				data: Some(reason.into()),
 			},
 			Error(ErrorKind::RpcError(e), _) => match e{
 				RpcError::JsonRpcError(e) => serde_json::from_str(&serde_json::to_string(&e).unwrap()).unwrap(),
@@ -21,6 +21,8 @@ pub mod state;
 pub mod system;
 pub mod chain;
 pub mod pow;
+pub mod relay;
+pub mod subscriptions;
 pub mod errors;
 pub mod client;
 use yee_primitives::{Config, Shard};
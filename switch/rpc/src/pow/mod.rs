@@ -21,12 +21,13 @@ use yee_sr_primitives::SHARD_CODE_SIZE;
 use serde_json::map::Entry::Vacant;
 use serde::export::PhantomData;
 use crate::work_manager::{WorkManager, Work};
+use mining::ledger::ShareLedger;
 use runtime_primitives::traits::{BlakeTwo256, Hash as HashT};
 use std::sync::Arc;
 use yee_serde_hex::SerdeHex;
 use parking_lot::RwLock;
 use yee_consensus_pow_primitives::PowTarget;
-use yee_consensus_pow::ExtraData;
+use yee_consensus_pow::{ExtraData, get_algorithm};
 use tokio::timer::Interval;
 use tokio::runtime::{Runtime, TaskExecutor};
 use std::time::{Instant, Duration};
@@ -41,6 +42,14 @@ pub trait PowApi<Hash> {
 
     #[rpc(name = "submit_work")]
     fn submit_work(&self, data: String) -> BoxFuture<()>;
+
+    /// Payouts of the blocks found by the stratum server, the latest last
+    #[rpc(name = "get_payouts")]
+    fn get_payouts(&self) -> errors::Result<Vec<Payout<Hash>>>;
+
+    /// Shares and blocks of a worker of the stratum server
+    #[rpc(name = "get_worker_stats")]
+    fn get_worker_stats(&self, worker: String) -> errors::Result<Option<WorkerStats>>;
 }
 
 #[derive(Serialize, Deserialize, Default, Debug)]
@@ -50,6 +59,31 @@ pub struct Job<Hash> {
     pub target: PowTarget,
     pub shard_count: u16,
     pub shard_block_number: HashMap<u16, BlockNumber>,
+    pub algorithm: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Payout<Hash> {
+    pub shard_num: u16,
+    pub number: u64,
+    pub hash: Hash,
+    /// Reward paid, without the slashed part
+    pub reward: Hex<BigUint>,
+    /// Percentage of the reward slashed
+    pub slashed: u32,
+    /// Amount of each worker
+    pub payouts: Vec<(String, Hex<BigUint>)>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkerStats {
+    pub shares: u64,
+    /// Sum of the difficulties of the accepted shares
+    pub difficulty: Hex<BigUint>,
+    pub best_nonce_target: Option<PowTarget>,
+    pub blocks: u64,
 }
 
 #[derive(Default, Debug)]
@@ -57,7 +91,6 @@ struct SubmitJob<Hash> {
     merkle_root: Hash,
     extra_data: ExtraData,
     nonce: u64,
-    nonce_target: U256,
 }
 
 #[derive(Serialize, Deserialize, Default, Debug)]
@@ -66,14 +99,17 @@ pub struct SubmitResponse {
 }
 
 pub struct Pow<WM: WorkManager> where {
-    work_manager: Arc<RwLock<WM>>
+    work_manager: Arc<RwLock<WM>>,
+    /// Share ledger of the stratum server, if started
+    ledger: Option<Arc<RwLock<ShareLedger<<WM::Hashing as HashT>::Output>>>>,
 }
 
 impl<WM: WorkManager> Pow<WM> where
 {
-    pub fn new(wm: Arc<RwLock<WM>>) -> Self {
+    pub fn new(wm: Arc<RwLock<WM>>, ledger: Option<Arc<RwLock<ShareLedger<<WM::Hashing as HashT>::Output>>>>) -> Self {
         Self {
             work_manager: wm,
+            ledger,
         }
     }
 }
@@ -97,6 +133,7 @@ impl<WM> PowApi<<WM::Hashing as HashT>::Output> for Pow<WM> where
             target: work.target,
             shard_count: work.shard_count,
             shard_block_number: work.shard_block_number,
+            algorithm: work.algorithm,
         };
 
         Ok(job)
@@ -121,14 +158,51 @@ impl<WM> PowApi<<WM::Hashing as HashT>::Output> for Pow<WM> where
                 future::err(errors::Error::from(errors::ErrorKind::SumbitWorkError("not found work by merkle root".to_string())).into())),
         };
 
+        let algorithm = match get_algorithm(&work.algorithm) {
+            Ok(algorithm) => algorithm,
+            Err(e) => return Box::new(
+                future::err(errors::Error::from(errors::ErrorKind::SumbitWorkError(e)).into())),
+        };
+        let source = (job.merkle_root, job.nonce, job.extra_data.clone());
+        debug!("submit work,data: {:?}", hex::encode(source.encode()));
+        let nonce_target = PowTarget::from(algorithm.hash(&source.encode()).as_ref());
+
         work.extra_data = job.extra_data;
         work.nonce = Some(job.nonce);
-        work.nonce_target = Some(job.nonce_target);
+        work.nonce_target = Some(nonce_target);
 
-        Box::new(self.work_manager.write().submit_work_future(work).map_err(|e| {
+        Box::new(self.work_manager.write().submit_work_future(work).map(|_| ()).map_err(|e| {
             errors::Error::from(errors::ErrorKind::SumbitWorkError(format!("{}", e))).into()
         }))
     }
+
+    fn get_payouts(&self) -> errors::Result<Vec<Payout<<WM::Hashing as HashT>::Output>>> {
+        let ledger = self.ledger.as_ref().ok_or(errors::Error::from(errors::ErrorKind::ConfigError))?;
+        let payouts = ledger.read().reports().into_iter().map(|report| Payout {
+            shard_num: report.shard_num,
+            number: report.number,
+            hash: report.hash,
+            reward: Hex(big_uint(report.reward)),
+            slashed: report.slashed,
+            payouts: report.payouts.into_iter().map(|(worker, amount)| (worker, Hex(big_uint(amount)))).collect(),
+        }).collect();
+        Ok(payouts)
+    }
+
+    fn get_worker_stats(&self, worker: String) -> errors::Result<Option<WorkerStats>> {
+        let ledger = self.ledger.as_ref().ok_or(errors::Error::from(errors::ErrorKind::ConfigError))?;
+        let stats = ledger.read().worker_stats(&worker).map(|stats| WorkerStats {
+            shares: stats.shares,
+            difficulty: Hex(big_uint(stats.difficulty)),
+            best_nonce_target: stats.best_nonce_target,
+            blocks: stats.blocks,
+        });
+        Ok(stats)
+    }
+}
+
+fn big_uint(amount: u128) -> BigUint {
+    BigUint::from_bytes_le(&amount.to_le_bytes())
 }
 
 impl<WM> Pow<WM> where
@@ -168,11 +242,6 @@ impl<WM> Pow<WM> where
         }
         let extra_data = ExtraData::from(extra_data);
 
-        let source = (merkle_root.clone(), nonce, extra_data.clone());
-        let buf = source.encode();
-        debug!("submit work,data: {:?}", hex::encode(buf));
-        let source_hash = WM::Hashing::hash_of(&source);
-        let nonce_target = PowTarget::from(source_hash.as_ref());
-        Some(SubmitJob { merkle_root, extra_data, nonce, nonce_target })
+        Some(SubmitJob { merkle_root, extra_data, nonce })
     }
 }
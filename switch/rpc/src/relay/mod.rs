@@ -0,0 +1,137 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use jsonrpc_derive::rpc;
+use jsonrpc_core::BoxFuture;
+use serde::{Serialize, Deserialize};
+use crate::rpc::futures::future;
+use crate::client::RpcClient;
+use crate::errors;
+
+/// Cross shard relay API
+#[rpc]
+pub trait RelayApi<Hash> {
+	/// Returns the relay state of a cross shard origin extrinsic, by the hash of the extrinsic.
+	#[rpc(name = "relay_getStatus")]
+	fn relay_status(&self, hash: Hash) -> errors::Result<Option<RelayInfo<Hash>>>;
+
+	/// Returns the origin extrinsics stuck without relay extrinsic.
+	#[rpc(name = "relay_listStuck")]
+	fn relay_list_stuck(&self) -> errors::Result<Vec<RelayEntry<Hash>>>;
+
+	/// Recommits the relay extrinsic of an origin extrinsic not relayed yet.
+	#[rpc(name = "relay_retry")]
+	fn relay_retry(&self, hash: Hash) -> BoxFuture<()>;
+}
+
+/// Relay state of a cross shard origin extrinsic
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RelayStatus {
+	/// The origin extrinsic is finalized, the relay extrinsic is not seen yet
+	Pending,
+	/// The relay extrinsic is on the best chain of the destination shard, but not finalized
+	Relayed,
+	/// The relay extrinsic is finalized on the destination shard
+	Finalized,
+	/// The relay extrinsic is not seen long after the origin extrinsic, it is being recommitted
+	Stuck,
+	/// The relay extrinsic failed on the destination shard, and the origin extrinsic was refunded
+	/// by the receipt extrinsic finalized on the source shard
+	Refunded,
+}
+
+/// Position of an extrinsic in a shard
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtrinsicLocation<Hash> {
+	pub shard_num: u16,
+	pub block_hash: Hash,
+	pub block_number: u64,
+	pub index: u32,
+	pub finalized: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RelayInfo<Hash> {
+	pub status: RelayStatus,
+	/// The origin extrinsic on the source shard
+	pub origin: Option<ExtrinsicLocation<Hash>>,
+	/// The `Relay::transfer` extrinsic on the destination shard
+	pub relay: Option<ExtrinsicLocation<Hash>>,
+	/// The receipt extrinsic refunding the origin extrinsic on the source shard
+	pub refund: Option<ExtrinsicLocation<Hash>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RelayEntry<Hash> {
+	pub hash: Hash,
+	#[serde(flatten)]
+	pub info: RelayInfo<Hash>,
+}
+
+/// Records the cross shard origin extrinsics and their relay extrinsics
+pub trait RelayTracker {
+	type Hash;
+
+	fn relay_info(&self, hash: &Self::Hash) -> Option<RelayInfo<Self::Hash>>;
+
+	/// Origin extrinsics stuck without relay extrinsic, by hash
+	fn stuck_list(&self) -> Vec<(Self::Hash, RelayInfo<Self::Hash>)>;
+}
+
+pub struct Relay<T> {
+	rpc_client: RpcClient,
+	tracker: Arc<T>,
+}
+
+impl<T> Relay<T> {
+	pub fn new(rpc_client: RpcClient, tracker: Arc<T>) -> Self {
+		Self {
+			rpc_client,
+			tracker,
+		}
+	}
+}
+
+impl<T> RelayApi<T::Hash> for Relay<T> where
+	T: RelayTracker + Send + Sync + 'static,
+	T::Hash: Send + Sync + 'static + Serialize + serde::de::DeserializeOwned,
+{
+	fn relay_status(&self, hash: T::Hash) -> errors::Result<Option<RelayInfo<T::Hash>>> {
+		Ok(self.tracker.relay_info(&hash))
+	}
+
+	fn relay_list_stuck(&self) -> errors::Result<Vec<RelayEntry<T::Hash>>> {
+		Ok(self.tracker.stuck_list().into_iter().map(|(hash, info)| RelayEntry { hash, info }).collect())
+	}
+
+	fn relay_retry(&self, hash: T::Hash) -> BoxFuture<()> {
+		let origin = match self.tracker.relay_info(&hash) {
+			Some(RelayInfo { status: RelayStatus::Pending, origin: Some(origin), .. }) |
+			Some(RelayInfo { status: RelayStatus::Stuck, origin: Some(origin), .. }) => origin,
+			Some(_) => return Box::new(future::err(errors::Error::from(errors::ErrorKind::RelayError("relay extrinsic already seen".to_string())).into())),
+			None => return Box::new(future::err(errors::Error::from(errors::ErrorKind::RelayError("origin extrinsic not found".to_string())).into())),
+		};
+
+		self.rpc_client.call_method_async("author_recommitRelay", "()", (origin.block_hash, origin.index), origin.shard_num)
+			.unwrap_or_else(|e| Box::new(future::err(e.into())))
+	}
+}
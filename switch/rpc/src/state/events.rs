@@ -0,0 +1,306 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Decodes the `System::Events` storage of a block with the event metadata of its runtime
+
+use parity_codec::{Compact, Decode};
+use serde::{Serialize, Deserialize};
+use serde_json::{Value, Map};
+use num_bigint::BigUint;
+use srml_metadata::{DecodeDifferent, RuntimeMetadata, RuntimeMetadataPrefixed};
+use yee_serde_hex::Hex;
+
+/// An event of a block
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockEvent {
+	/// Index of the extrinsic emitting the event, none if emitted on block finalization
+	pub extrinsic_index: Option<u32>,
+	pub module: String,
+	pub event: String,
+	pub args: Vec<EventArg>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EventArg {
+	/// Type name as declared in the runtime
+	#[serde(rename = "type")]
+	pub ty: String,
+	pub value: Value,
+}
+
+/// Events of a runtime module: event name and argument type names
+pub struct ModuleEvents {
+	pub name: String,
+	pub events: Vec<(String, Vec<String>)>,
+}
+
+/// Event decoder of a runtime version
+pub struct EventDecoder {
+	/// Modules with events, in the order of the outer event enum of the runtime
+	modules: Vec<ModuleEvents>,
+}
+
+impl EventDecoder {
+	pub fn new(modules: Vec<ModuleEvents>) -> Self {
+		Self { modules }
+	}
+
+	/// Build from the SCALE encoded metadata returned by `state_getMetadata`
+	pub fn from_metadata(metadata: &[u8]) -> Result<Self, String> {
+		let metadata = RuntimeMetadataPrefixed::decode(&mut &metadata[..])
+			.ok_or_else(|| "invalid metadata".to_string())?;
+		let modules = match metadata.1 {
+			RuntimeMetadata::V3(ref metadata) => decoded(&metadata.modules)?,
+			_ => return Err("unsupported metadata version".to_string()),
+		};
+
+		// `construct_runtime` puts the modules into the metadata and the outer event enum in the
+		// same order, the outer event enum skipping the modules without event, e.g. `sharding`.
+		let modules = modules.iter().filter_map(|module| {
+			let events = match module.event {
+				Some(ref events) => events,
+				None => return None,
+			};
+			Some(decoded(events).and_then(|events| {
+				let events = events.iter().map(|event| {
+					Ok((decoded(&event.name)?, decoded(&event.arguments)?))
+				}).collect::<Result<Vec<_>, String>>()?;
+				Ok(ModuleEvents { name: decoded(&module.name)?, events })
+			}))
+		}).collect::<Result<Vec<_>, String>>()?;
+
+		Ok(Self::new(modules))
+	}
+
+	/// Decode the SCALE encoded `Vec<EventRecord>` of a block
+	pub fn decode(&self, data: &[u8]) -> Result<Vec<BlockEvent>, String> {
+		let input = &mut &data[..];
+		if input.is_empty() {
+			return Ok(vec![]);
+		}
+		let count = <Compact<u32>>::decode(input).ok_or_else(|| "invalid event count".to_string())?.0;
+
+		let mut result = Vec::with_capacity(count as usize);
+		for _ in 0..count {
+			let extrinsic_index = match read::<u8>(input)? {
+				0 => Some(read::<u32>(input)?),
+				1 => None,
+				phase => return Err(format!("invalid phase {}", phase)),
+			};
+			let module_index = read::<u8>(input)? as usize;
+			let module = self.modules.get(module_index)
+				.ok_or_else(|| format!("unknown module {}", module_index))?;
+			let event_index = read::<u8>(input)? as usize;
+			let (event, arguments) = module.events.get(event_index)
+				.ok_or_else(|| format!("unknown event {} of {}", event_index, module.name))?;
+
+			let args = arguments.iter().map(|ty| {
+				let value = decode_value(&normalize(ty), input)
+					.map_err(|e| format!("{}::{}: {}", module.name, event, e))?;
+				Ok(EventArg { ty: ty.clone(), value })
+			}).collect::<Result<Vec<_>, String>>()?;
+
+			result.push(BlockEvent {
+				extrinsic_index,
+				module: module.name.clone(),
+				event: event.clone(),
+				args,
+			});
+		}
+		Ok(result)
+	}
+}
+
+fn decoded<B, O: Clone>(value: &DecodeDifferent<B, O>) -> Result<O, String> {
+	match value {
+		DecodeDifferent::Decoded(value) => Ok(value.clone()),
+		DecodeDifferent::Encode(_) => Err("metadata not decoded".to_string()),
+	}
+}
+
+fn read<T: Decode>(input: &mut &[u8]) -> Result<T, String> {
+	T::decode(input).ok_or_else(|| "unexpected end of event data".to_string())
+}
+
+fn read_bytes(input: &mut &[u8], len: usize) -> Result<String, String> {
+	if input.len() < len {
+		return Err("unexpected end of event data".to_string());
+	}
+	let (bytes, rest) = input.split_at(len);
+	*input = rest;
+	Ok(format!("0x{}", hex::encode(bytes)))
+}
+
+fn normalize(ty: &str) -> String {
+	ty.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// Split `A,B<C,D>,(E,F)` at the top level commas
+fn split_args(args: &str) -> Vec<&str> {
+	let mut result = vec![];
+	let mut depth = 0;
+	let mut start = 0;
+	for (i, c) in args.char_indices() {
+		match c {
+			'<' | '(' => depth += 1,
+			'>' | ')' => depth -= 1,
+			',' if depth == 0 => {
+				result.push(&args[start..i]);
+				start = i + 1;
+			},
+			_ => (),
+		}
+	}
+	if start < args.len() {
+		result.push(&args[start..]);
+	}
+	result
+}
+
+/// Type name and generic arguments of `Name<A,B>`
+fn generic(ty: &str) -> Option<(&str, Vec<&str>)> {
+	if !ty.ends_with('>') {
+		return None;
+	}
+	let open = ty.find('<')?;
+	Some((&ty[..open], split_args(&ty[open + 1..ty.len() - 1])))
+}
+
+fn decode_value(ty: &str, input: &mut &[u8]) -> Result<Value, String> {
+	let value = match ty {
+		"bool" => Value::Bool(read::<bool>(input)?),
+		"u8" => read::<u8>(input)?.into(),
+		"u16" | "ShardNum" => read::<u16>(input)?.into(),
+		"u32" | "AccountIndex" | "AssetId" => read::<u32>(input)?.into(),
+		"u64" | "BlockNumber" | "N" | "Nonce" | "Moment" => read::<u64>(input)?.into(),
+		"u128" | "Balance" => serde_json::to_value(Hex(BigUint::from(read::<u128>(input)?)))
+			.map_err(|e| e.to_string())?,
+		"AccountId" | "SessionKey" | "AuthorityId" | "Hash" | "H256" => read_bytes(input, 32)?.into(),
+		"Vec<u8>" => {
+			let len = <Compact<u32>>::decode(input).ok_or_else(|| "invalid length".to_string())?.0;
+			read_bytes(input, len as usize)?.into()
+		},
+		_ if ty.starts_with('(') && ty.ends_with(')') => {
+			let values = split_args(&ty[1..ty.len() - 1]).into_iter()
+				.map(|ty| decode_value(ty, input))
+				.collect::<Result<Vec<_>, String>>()?;
+			Value::Array(values)
+		},
+		_ => match generic(ty) {
+			Some(("Vec", ref args)) if args.len() == 1 => {
+				let len = <Compact<u32>>::decode(input).ok_or_else(|| "invalid length".to_string())?.0;
+				let values = (0..len).map(|_| decode_value(args[0], input))
+					.collect::<Result<Vec<_>, String>>()?;
+				Value::Array(values)
+			},
+			Some(("Option", ref args)) if args.len() == 1 => match read::<u8>(input)? {
+				0 => Value::Null,
+				1 => decode_value(args[0], input)?,
+				_ => return Err("invalid option".to_string()),
+			},
+			// pow reward, `RewardPlan<N, AccountId, Balance>`
+			Some(("RewardPlan", ref args)) if args.len() == 3 => {
+				let mut plan = Map::new();
+				plan.insert("blockNumber".to_string(), decode_value(args[0], input)?);
+				plan.insert("coinbase".to_string(), decode_value(args[1], input)?);
+				plan.insert("blockReward".to_string(), decode_value(args[2], input)?);
+				plan.insert("feeReward".to_string(), decode_value(args[2], input)?);
+				Value::Object(plan)
+			},
+			_ => return Err(format!("unsupported type {}", ty)),
+		},
+	};
+	Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use parity_codec::Encode;
+
+	fn decoder() -> EventDecoder {
+		EventDecoder::new(vec![
+			ModuleEvents {
+				name: "system".to_string(),
+				events: vec![("ExtrinsicSuccess".to_string(), vec![]), ("ExtrinsicFailed".to_string(), vec![])],
+			},
+			ModuleEvents {
+				name: "pow".to_string(),
+				events: vec![("Reward".to_string(), vec!["RewardPlan<N, AccountId, Balance>".to_string()])],
+			},
+			ModuleEvents {
+				name: "balances".to_string(),
+				events: vec![
+					("NewAccount".to_string(), vec!["AccountId".to_string(), "Balance".to_string()]),
+					("Transfer".to_string(), vec!["AccountId".to_string(), "AccountId".to_string(), "Balance".to_string(), "Balance".to_string()]),
+				],
+			},
+		])
+	}
+
+	#[test]
+	fn decode_block_events() {
+		let mut data = Compact(3u32).encode();
+		// ApplyExtrinsic(1), balances::Transfer
+		data.extend((0u8, 1u32, 2u8, 1u8, [1u8; 32], [2u8; 32], 100u128, 1u128).encode());
+		// ApplyExtrinsic(1), system::ExtrinsicSuccess
+		data.extend((0u8, 1u32, 0u8, 0u8).encode());
+		// Finalization, pow::Reward
+		data.extend((1u8, 1u8, 0u8, 10u64, [3u8; 32], 50u128, 2u128).encode());
+
+		let events = decoder().decode(&data).unwrap();
+		assert_eq!(events.len(), 3);
+
+		assert_eq!(events[0].extrinsic_index, Some(1));
+		assert_eq!((events[0].module.as_str(), events[0].event.as_str()), ("balances", "Transfer"));
+		assert_eq!(events[0].args[0].value, Value::String(format!("0x{}", hex::encode([1u8; 32]))));
+		assert_eq!(events[0].args[2].value, serde_json::to_value(Hex(BigUint::from(100u32))).unwrap());
+
+		assert_eq!((events[1].module.as_str(), events[1].event.as_str()), ("system", "ExtrinsicSuccess"));
+
+		assert_eq!(events[2].extrinsic_index, None);
+		assert_eq!(events[2].args[0].value["blockNumber"], Value::from(10u64));
+		assert_eq!(events[2].args[0].value["feeReward"], serde_json::to_value(Hex(BigUint::from(2u32))).unwrap());
+	}
+
+	#[test]
+	fn decode_with_runtime_metadata() {
+		use yee_runtime::{AccountId, Event, Hash, Runtime};
+
+		let decoder = EventDecoder::from_metadata(&Runtime::metadata().encode()).unwrap();
+
+		// an event of a module after `sharding`, which has no event
+		let mut data = Compact(1u32).encode();
+		data.extend((0u8, 3u32).encode());
+		data.extend(Event::from(storage::RawEvent::Stored(AccountId::from_raw([1u8; 32]), Hash::repeat_byte(2), 5u128)).encode());
+
+		let events = decoder.decode(&data).unwrap();
+		assert_eq!(events.len(), 1);
+		assert_eq!(events[0].extrinsic_index, Some(3));
+		assert_eq!((events[0].module.as_str(), events[0].event.as_str()), ("storage", "Stored"));
+		assert_eq!(events[0].args[1].value, Value::String(format!("0x{}", hex::encode([2u8; 32]))));
+		assert_eq!(events[0].args[2].value, serde_json::to_value(Hex(BigUint::from(5u32))).unwrap());
+	}
+
+	#[test]
+	fn decode_unknown_event_fails() {
+		let mut data = Compact(1u32).encode();
+		data.extend((0u8, 0u32, 2u8, 5u8).encode());
+		assert!(decoder().decode(&data).is_err());
+	}
+}
@@ -16,10 +16,11 @@
 // along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
 
 use jsonrpc_derive::rpc;
-use primitives::{sr25519, storage::{StorageKey, StorageData}};
+use primitives::{sr25519, Bytes, storage::{StorageKey, StorageData}};
 use crate::rpc::futures::{Future, Stream};
 use crate::Config;
-use crate::client::RpcClient;
+use crate::client::{RpcClient, BoxStream};
+use crate::subscriptions::{self, Subscriptions};
 use serde::{Serialize, Deserialize};
 use serde::de::DeserializeOwned;
 use parity_codec::{KeyedVec, Codec, Decode, Encode, Input, Compact};
@@ -28,7 +29,9 @@ use num_bigint::BigUint;
 use yee_runtime::AccountId;
 use yee_sharding_primitives::utils::shard_num_for_bytes;
 use crate::errors;
-use jsonrpc_core::{BoxFuture, Error, ErrorCode};
+use jsonrpc_core::{BoxFuture, Error, ErrorCode, Result as RpcResult};
+use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
+use std::collections::HashMap;
 use crate::rpc::{self, futures::future::{self, FutureResult}};
 use yee_serde_hex::Hex;
 use yee_primitives::{Address, AddressCodec, Hrp};
@@ -36,10 +39,20 @@ use serde_json::Value;
 use hex;
 use yee_sr_primitives::SHARD_CODE_SIZE;
 use serde_json::map::Entry::Vacant;
+use std::sync::Arc;
+use parking_lot::RwLock;
+
+mod events;
+
+pub use events::{BlockEvent, EventArg};
+use events::EventDecoder;
 
 /// Substrate state API
 #[rpc]
 pub trait StateApi<Hash> {
+	/// RPC metadata
+	type Metadata;
+
 	#[rpc(name = "state_getBalance")]
 	fn balance(&self, address: Address, hash: Option<Hash>) -> BoxFuture<Hex<BigUint>>;
 
@@ -55,6 +68,49 @@ pub trait StateApi<Hash> {
 	/// Get block events.
 	#[rpc(name = "state_getBlockEvents")]
 	fn block_events(&self, shard_num: u16, hash: Option<Hash>) -> BoxFuture<Vec<u8>>;
+
+	/// Get block events decoded with the metadata of the runtime of the block.
+	#[rpc(name = "state_getDecodedBlockEvents")]
+	fn decoded_block_events(&self, shard_num: u16, hash: Option<Hash>) -> BoxFuture<Vec<BlockEvent>>;
+
+	/// Balance change subscription of an account
+	#[pubsub(subscription = "state_balance", subscribe, name = "state_subscribeBalance")]
+	fn subscribe_balance(&self, metadata: Self::Metadata, subscriber: Subscriber<BalanceChange<Hash>>, address: Address);
+
+	/// Unsubscribe from balance change subscription.
+	#[pubsub(subscription = "state_balance", unsubscribe, name = "state_unsubscribeBalance")]
+	fn unsubscribe_balance(&self, metadata: Option<Self::Metadata>, id: SubscriptionId) -> RpcResult<bool>;
+
+	/// Asset balance change subscription of an account
+	#[pubsub(subscription = "state_assetBalance", subscribe, name = "state_subscribeAssetBalance")]
+	fn subscribe_asset_balance(&self, metadata: Self::Metadata, subscriber: Subscriber<BalanceChange<Hash>>, address: Address, shard_code: String, asset_id: u32);
+
+	/// Unsubscribe from asset balance change subscription.
+	#[pubsub(subscription = "state_assetBalance", unsubscribe, name = "state_unsubscribeAssetBalance")]
+	fn unsubscribe_asset_balance(&self, metadata: Option<Self::Metadata>, id: SubscriptionId) -> RpcResult<bool>;
+}
+
+/// Balance of an account after a block changed it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BalanceChange<Hash> {
+	pub block: Hash,
+	pub balance: Hex<BigUint>,
+}
+
+/// Part of the runtime version identifying the runtime of a block
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct SpecVersion {
+	spec_name: String,
+	spec_version: u32,
+	impl_version: u32,
+}
+
+/// Storage changes of a block, as notified by `state_subscribeStorage`
+#[derive(Deserialize, Debug)]
+struct StorageChangeSet<Hash> {
+	block: Hash,
+	changes: Vec<(StorageKey, Option<StorageData>)>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -72,16 +128,21 @@ pub struct AssetDetail {
 pub struct State {
 	config: Config,
 	rpc_client: RpcClient,
+	subscriptions: Subscriptions,
+	/// Event decoders by runtime version: spec name, spec version and impl version
+	decoders: Arc<RwLock<HashMap<(String, u32, u32), Arc<EventDecoder>>>>,
 }
 
 const BLOCK_EVENTS_PREFIX: &'static str = "0xcc956bdb7605e3547539f321ac2bc95c";
 
 impl State {
 	/// Create new State API RPC handler.
-	pub fn new(config: Config) -> Self {
+	pub fn new(rpc_client: RpcClient, subscriptions: Subscriptions) -> Self {
 		Self {
-			config: config.clone(),
-			rpc_client: RpcClient::new(config),
+			config: rpc_client.config().clone(),
+			rpc_client,
+			subscriptions,
+			decoders: Arc::new(RwLock::new(HashMap::new())),
 		}
 	}
 }
@@ -89,10 +150,12 @@ impl State {
 impl<Hash> StateApi<Hash> for State
 	where Hash: Send + Sync + 'static + Serialize + DeserializeOwned + Clone
 {
+	type Metadata = crate::metadata::Metadata;
+
 	fn balance(&self, address: Address, hash: Option<Hash>) -> BoxFuture<Hex<BigUint>> {
-		let account_id = match AccountId::from_address(&address) {
-			Ok((account_id, _hrp)) => account_id,
-			Err(e) => return Box::new(future::err(errors::Error::from(errors::ErrorKind::InvalidAddress).into())),
+		let account_id = match self.account_id(&address) {
+			Ok(account_id) => account_id,
+			Err(e) => return Box::new(future::err(e.into())),
 		};
 
 		let shard_count = self.config.get_shard_count();
@@ -136,9 +199,9 @@ impl<Hash> StateApi<Hash> for State
 	}
 
 	fn nonce(&self, address: Address, hash: Option<Hash>) -> BoxFuture<Hex<BigUint>> {
-		let account_id = match AccountId::from_address(&address) {
-			Ok((account_id, _hrp)) => account_id,
-			Err(e) => return Box::new(future::err(errors::Error::from(errors::ErrorKind::InvalidAddress).into())),
+		let account_id = match self.account_id(&address) {
+			Ok(account_id) => account_id,
+			Err(e) => return Box::new(future::err(e.into())),
 		};
 
 		let shard_count = self.config.get_shard_count();
@@ -167,9 +230,9 @@ impl<Hash> StateApi<Hash> for State
 	}
 
 	fn asset_balance(&self, address: Address, shard_code: String, asset_id: u32, hash: Option<Hash>) -> BoxFuture<Hex<BigUint>> {
-		let account_id = match AccountId::from_address(&address) {
-			Ok((id, _)) => id,
-			Err(_e) => return Box::new(future::err(errors::Error::from(errors::ErrorKind::InvalidAddress).into()))
+		let account_id = match self.account_id(&address) {
+			Ok(id) => id,
+			Err(e) => return Box::new(future::err(e.into()))
 		};
 		let shard_count = self.config.get_shard_count();
 		let shard_num = match shard_num_for_bytes(account_id.as_slice(), shard_count) {
@@ -280,6 +343,130 @@ impl<Hash> StateApi<Hash> for State
 			}
 		}
 	}
+
+	fn decoded_block_events(&self, shard_num: u16, hash: Option<Hash>) -> BoxFuture<Vec<BlockEvent>> {
+		let shard_count = self.config.get_shard_count();
+		if shard_num >= shard_count {
+			return Box::new(future::err(errors::Error::from(errors::ErrorKind::InvalidShard).into()));
+		}
+
+		// pin the best block, the events and the metadata must be of the same block
+		let hash_future: BoxFuture<Option<Hash>> = match hash {
+			Some(hash) => Box::new(future::ok(Some(hash))),
+			None => self.rpc_client.call_method_async("chain_getBlockHash", "Option<Hash>", (), shard_num)
+				.unwrap_or_else(|e| Box::new(future::err(e.into()))),
+		};
+
+		let rpc_client = self.rpc_client.clone();
+		let decoders = self.decoders.clone();
+		Box::new(hash_future.and_then(move |hash| -> BoxFuture<Vec<BlockEvent>> {
+			let hash = match hash {
+				Some(hash) => hash,
+				None => return Box::new(future::ok(vec![])),
+			};
+			let events_future: BoxFuture<Option<StorageData>> = match rpc_client.call_method_async("state_getStorage", "Option<StorageData>", (BLOCK_EVENTS_PREFIX, hash.clone()), shard_num) {
+				Ok(future) => future,
+				Err(e) => return Box::new(future::err(e.into())),
+			};
+			let decoder_future = event_decoder(rpc_client, decoders, shard_num, hash);
+
+			Box::new(decoder_future.join(events_future).and_then(|(decoder, result)| -> RpcResult<Vec<BlockEvent>> {
+				let data = result.map(|x| x.0).unwrap_or_default();
+				decoder.decode(&data).map_err(|e| errors::Error::from(errors::ErrorKind::DecodeError(e)).into())
+			}))
+		}))
+	}
+
+	fn subscribe_balance(&self, _metadata: Self::Metadata, subscriber: Subscriber<BalanceChange<Hash>>, address: Address) {
+		let (account_id, shard_num) = match self.account_shard(&address) {
+			Ok(v) => v,
+			Err(e) => {
+				let _ = subscriber.reject(e.into());
+				return;
+			}
+		};
+
+		let keys = vec![
+			get_storage_key(&account_id, StorageKeyId::FreeBalance),
+			get_storage_key(&account_id, StorageKeyId::ReservedBalance),
+		];
+		self.subscribe_storage_sum(subscriber, keys, shard_num);
+	}
+
+	fn unsubscribe_balance(&self, _metadata: Option<Self::Metadata>, id: SubscriptionId) -> RpcResult<bool> {
+		Ok(self.subscriptions.cancel(id))
+	}
+
+	fn subscribe_asset_balance(&self, _metadata: Self::Metadata, subscriber: Subscriber<BalanceChange<Hash>>, address: Address, shard_code: String, asset_id: u32) {
+		let (account_id, shard_num) = match self.account_shard(&address) {
+			Ok(v) => v,
+			Err(e) => {
+				let _ = subscriber.reject(e.into());
+				return;
+			}
+		};
+		let shard_code = match hex::decode(shard_code) {
+			Ok(code) => code,
+			Err(_e) => {
+				let _ = subscriber.reject(errors::Error::from(errors::ErrorKind::InvalidShardCode).into());
+				return;
+			}
+		};
+
+		let keys = vec![get_storage_key(&(shard_code, asset_id, account_id), StorageKeyId::AssetBalance)];
+		self.subscribe_storage_sum(subscriber, keys, shard_num);
+	}
+
+	fn unsubscribe_asset_balance(&self, _metadata: Option<Self::Metadata>, id: SubscriptionId) -> RpcResult<bool> {
+		Ok(self.subscriptions.cancel(id))
+	}
+}
+
+impl State {
+	/// Account of the address, of the network of the configured hrp if any
+	fn account_id(&self, address: &Address) -> errors::Result<AccountId> {
+		let account_id = match &self.config.hrp {
+			Some(hrp) => AccountId::from_address_with_hrp(address, hrp),
+			None => AccountId::from_address(address).map(|(account_id, _hrp)| account_id),
+		};
+		account_id.map_err(|e| errors::ErrorKind::InvalidAddress(e.to_string()).into())
+	}
+
+	fn account_shard(&self, address: &Address) -> errors::Result<(AccountId, u16)> {
+		let account_id = self.account_id(address)?;
+		let shard_num = match shard_num_for_bytes(account_id.as_slice(), self.config.get_shard_count()) {
+			Some(shard_num) => shard_num,
+			None => return Err(errors::ErrorKind::InvalidShard.into()),
+		};
+		Ok((account_id, shard_num))
+	}
+
+	/// Notify the sum of the values of `keys` whenever a block changes one of them
+	fn subscribe_storage_sum<Hash>(&self, subscriber: Subscriber<BalanceChange<Hash>>, keys: Vec<StorageKey>, shard_num: u16) where
+		Hash: Send + Sync + 'static + Serialize + DeserializeOwned,
+	{
+		let changes: BoxStream<StorageChangeSet<Hash>> = match self.rpc_client.subscribe("state_subscribeStorage", (keys,), "state_storage", "state_unsubscribeStorage", "StorageChangeSet", shard_num) {
+			Ok(stream) => stream,
+			Err(e) => {
+				let _ = subscriber.reject(e.into());
+				return;
+			}
+		};
+
+		// the first notification holds every key, the next ones only the changed keys
+		let mut values: HashMap<Vec<u8>, BigUint> = HashMap::new();
+		let balances = changes.map(move |change_set| {
+			for (key, data) in change_set.changes {
+				values.insert(key.0, get_big_uint(data));
+			}
+			BalanceChange {
+				block: change_set.block,
+				balance: Hex(values.values().fold(BigUint::from(0u8), |sum, value| sum + value)),
+			}
+		});
+
+		self.subscriptions.add(subscriber, |sink| subscriptions::forward(sink, balances, "storage changes"));
+	}
 }
 
 enum StorageKeyId {
@@ -294,6 +481,38 @@ enum StorageKeyId {
 	AssetNextId,
 }
 
+/// Event decoder of the runtime of a block, built from the metadata of the runtime on first use.
+/// A runtime changing its events bumps `spec_version`, the impl version is part of the key too
+/// so that a runtime upgraded without the bump is not decoded with stale metadata.
+fn event_decoder<Hash>(rpc_client: RpcClient, decoders: Arc<RwLock<HashMap<(String, u32, u32), Arc<EventDecoder>>>>, shard_num: u16, hash: Hash) -> BoxFuture<Arc<EventDecoder>>
+	where Hash: Send + Serialize + Clone + 'static
+{
+	let version_future: BoxFuture<SpecVersion> = match rpc_client.call_method_async("state_getRuntimeVersion", "RuntimeVersion", (hash.clone(), ), shard_num) {
+		Ok(future) => future,
+		Err(e) => return Box::new(future::err(e.into())),
+	};
+
+	Box::new(version_future.and_then(move |version| -> BoxFuture<Arc<EventDecoder>> {
+		let spec = (version.spec_name, version.spec_version, version.impl_version);
+		if let Some(decoder) = decoders.read().get(&spec) {
+			return Box::new(future::ok(decoder.clone()));
+		}
+
+		let metadata_future: BoxFuture<Bytes> = match rpc_client.call_method_async("state_getMetadata", "Bytes", (hash, ), shard_num) {
+			Ok(future) => future,
+			Err(e) => return Box::new(future::err(e.into())),
+		};
+		Box::new(metadata_future.and_then(move |metadata| -> RpcResult<Arc<EventDecoder>> {
+			let decoder = EventDecoder::from_metadata(&metadata.0)
+				.map_err(|e| errors::Error::from(errors::ErrorKind::DecodeError(e)))?;
+			log::debug!("event decoder of runtime {} {} {}", spec.0, spec.1, spec.2);
+			let decoder = Arc::new(decoder);
+			decoders.write().insert(spec, decoder.clone());
+			Ok(decoder)
+		}))
+	}))
+}
+
 fn get_prefix(storage_key_id: StorageKeyId) -> &'static [u8] {
 	match storage_key_id {
 		StorageKeyId::FreeBalance => b"Balances FreeBalance",
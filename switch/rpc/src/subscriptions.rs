@@ -0,0 +1,95 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{self, AtomicUsize};
+
+use jsonrpc_pubsub::{SubscriptionId, typed::{self, Subscriber}};
+use log::warn;
+use parking_lot::Mutex;
+use serde::Serialize;
+use crate::rpc::{self, futures::{Future, IntoFuture, Sink, Stream, future, sync::oneshot}};
+use tokio::runtime::TaskExecutor;
+
+/// Active subscriptions of the switch, each one backed by a future forwarding the
+/// notifications of an upstream node.
+#[derive(Clone)]
+pub struct Subscriptions {
+	next_id: Arc<AtomicUsize>,
+	active_subscriptions: Arc<Mutex<HashMap<SubscriptionId, oneshot::Sender<()>>>>,
+	executor: TaskExecutor,
+}
+
+impl Subscriptions {
+	pub fn new(executor: TaskExecutor) -> Self {
+		Subscriptions {
+			next_id: Arc::new(AtomicUsize::new(1)),
+			active_subscriptions: Default::default(),
+			executor,
+		}
+	}
+
+	/// Assign an id to `subscriber` and spawn the future returned by `into_future`,
+	/// until it completes or the subscription is cancelled. The subscription is no longer
+	/// active once the future completes.
+	pub fn add<T, E, G, R, F>(&self, subscriber: Subscriber<T, E>, into_future: G) -> Option<SubscriptionId> where
+		G: FnOnce(typed::Sink<T, E>) -> R,
+		R: IntoFuture<Future=F, Item=(), Error=()>,
+		F: Future<Item=(), Error=()> + Send + 'static,
+	{
+		let id = SubscriptionId::Number(self.next_id.fetch_add(1, atomic::Ordering::AcqRel) as u64);
+		let sink = subscriber.assign_id(id.clone()).ok()?;
+		let (tx, rx) = oneshot::channel();
+		let (active_subscriptions, active_id) = (self.active_subscriptions.clone(), id.clone());
+		let future = into_future(sink)
+			.into_future()
+			.select(rx.map_err(|e| warn!("Error timing out: {:?}", e)))
+			.then(move |_| {
+				active_subscriptions.lock().remove(&active_id);
+				Ok(())
+			});
+
+		self.active_subscriptions.lock().insert(id.clone(), tx);
+		self.executor.spawn(future);
+		Some(id)
+	}
+
+	/// Cancel a subscription, returns false if it is not active.
+	pub fn cancel(&self, id: SubscriptionId) -> bool {
+		if let Some(tx) = self.active_subscriptions.lock().remove(&id) {
+			let _ = tx.send(());
+			return true;
+		}
+		false
+	}
+}
+
+/// Forward the notifications of an upstream subscription to `sink`. The error ending the
+/// upstream subscription is notified too, so that the client knows to subscribe again.
+pub fn forward<T, S>(sink: typed::Sink<T>, upstream: S, name: &'static str) -> impl Future<Item=(), Error=()> where
+	T: Serialize,
+	S: Stream<Item=T, Error=rpc::Error>,
+{
+	sink
+		.sink_map_err(|e| warn!("Error sending notifications: {:?}", e))
+		.send_all(upstream.map(Ok).or_else(move |e| {
+			warn!("Error receiving {}: {:?}", name, e);
+			future::ok::<_, ()>(Err(e))
+		}))
+		.map(|_| ())
+}
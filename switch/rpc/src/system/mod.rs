@@ -18,7 +18,7 @@
 use jsonrpc_derive::rpc;
 use crate::Config;
 use crate::errors;
-use crate::client::{RpcClient};
+use crate::client::{RpcClient, EndpointStatus};
 use yee_serde_hex::Hex;
 
 /// Substrate state API
@@ -27,6 +27,10 @@ pub trait SystemApi {
 	/// Returns a storage entry at a specific block's state.
 	#[rpc(name = "system_getShardCount")]
 	fn shard_count(&self) -> errors::Result<Hex<u16>>;
+
+	/// Returns the status of the nodes behind the switch.
+	#[rpc(name = "system_endpoints")]
+	fn endpoints(&self) -> errors::Result<Vec<EndpointStatus>>;
 }
 
 /// State API with subscriptions support.
@@ -37,10 +41,10 @@ pub struct System {
 
 impl System {
 	/// Create new State API RPC handler.
-	pub fn new(config: Config) -> Self {
+	pub fn new(rpc_client: RpcClient) -> Self {
 		Self {
-			config: config.clone(),
-			rpc_client: RpcClient::new(config),
+			config: rpc_client.config().clone(),
+			rpc_client,
         }
 	}
 }
@@ -51,4 +55,8 @@ impl SystemApi for System
 
 		Ok(Hex(self.config.shards.len() as u16))
 	}
+
+	fn endpoints(&self) -> errors::Result<Vec<EndpointStatus>> {
+		Ok(self.rpc_client.endpoint_status())
+	}
 }
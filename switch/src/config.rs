@@ -33,22 +33,32 @@ use log::trace;
 ///
 /// ### Content
 /// ```
+/// # prefix of the addresses accepted, any if absent
+/// hrp = "yee"
+///
 /// [shards]
 /// [shards.0]
 /// rpc = ["http://127.0.0.1:9933"]
+/// ws = ["ws://127.0.0.1:9944"]
 ///
 /// [shards.1]
 /// rpc = ["http://127.0.0.1:19933"]
+/// ws = ["ws://127.0.0.1:19944"]
 /// ```
 #[derive(Serialize, Deserialize)]
 #[derive(Debug, Clone)]
 pub struct Shard {
     pub rpc: Vec<String>,
+    /// Upstream of the subscriptions, optional
+    #[serde(default)]
+    pub ws: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize)]
 #[derive(Debug, Clone)]
 pub struct SwitchConf {
+    #[serde(default)]
+    pub hrp: Option<String>,
     pub shards: HashMap<String, Shard>,
 }
 
@@ -59,12 +69,14 @@ impl From<SwitchConf> for yee_primitives::Config {
         for (k, v) in conf.shards {
             let shard = yee_primitives::Shard {
                 rpc: v.rpc,
+                ws: v.ws,
             };
             shards.insert(k, shard);
         }
 
         yee_primitives::Config {
-            shards
+            shards,
+            hrp: conf.hrp.map(Into::into),
         }
     }
 }
@@ -99,16 +111,27 @@ fn get_dev_config(cmd: &SwitchCommandCmd) -> error::Result<SwitchConf> {
     for param in params {
         let shard_num = param.shard_num;
         let rpc_port = param.rpc_port;
+        let ws_port = param.ws_port;
         shards.insert(format!("{}", shard_num).to_string(), Shard {
             rpc: vec![format!("http://localhost:{}", rpc_port).to_string()],
+            ws: vec![format!("ws://localhost:{}", ws_port).to_string()],
         });
     }
 
     Ok(SwitchConf {
+        hrp: None,
         shards
     })
 }
 
+/// Path of the relay recommit db
+pub fn relay_db_path(cmd: &SwitchCommandCmd, version: &VersionInfo) -> PathBuf {
+    let mut path = base_path(cmd, version);
+    path.push("db");
+    path.push("relay");
+    path
+}
+
 fn conf_path(base_path: &Path) -> PathBuf {
     let mut path = base_path.to_owned();
     path.push("conf");
@@ -29,16 +29,25 @@ use yee_switch_rpc::state::State;
 use yee_switch_rpc::system::System;
 use yee_switch_rpc::chain::Chain;
 use yee_switch_rpc::pow::Pow;
-use crate::config::get_config;
+use yee_switch_rpc::relay::Relay;
+use yee_switch_rpc::client::RpcClient;
+use yee_switch_rpc::subscriptions::Subscriptions;
+use crate::config::{get_config, relay_db_path};
 use crate::params::DEFAULT_RPC_PORT;
 use crate::params::DEFAULT_WS_PORT;
 use tokio::runtime::Runtime;
 use yee_mining2::work_manager::WorkManagerConfig;
+use yee_mining2::stratum::StratumConfig;
 
 pub const TARGET: &str = "switch";
 
 mod relay_recommit;
+mod relay_store;
+mod relay_tracker;
 use relay_recommit::RelayRecommitManager;
+use relay_store::RelayStore;
+use relay_tracker::DefaultRelayTracker;
+use std::sync::Arc;
 use parity_codec::alloc::collections::HashMap;
 
 pub fn run(cmd: SwitchCommandCmd, version: VersionInfo) -> error::Result<()> {
@@ -46,6 +55,14 @@ pub fn run(cmd: SwitchCommandCmd, version: VersionInfo) -> error::Result<()> {
 
     let rpc_config: yee_primitives::Config = config.into();
 
+    // shared by the rpc handlers, so that they see the same endpoint health
+    let rpc_client = RpcClient::new(rpc_config.clone());
+    rpc_client.start_health_check();
+
+    // runs the subscriptions forwarded from the upstream nodes
+    let runtime = Runtime::new()?;
+    let subscriptions = Subscriptions::new(runtime.executor());
+
 
     let rpc_interface: &str = if cmd.rpc_external { "0.0.0.0" } else { "127.0.0.1" };
 
@@ -57,7 +74,7 @@ pub fn run(cmd: SwitchCommandCmd, version: VersionInfo) -> error::Result<()> {
 
     let (signal, exit) = exit_future::signal();
 
-    let work_manger = if cmd.enable_work_manager || cmd.mine {
+    let work_manger = if cmd.enable_work_manager || cmd.mine || cmd.stratum_port.is_some() {
         let work_manager_config = WorkManagerConfig {
             job_refresh_interval: cmd.job_refresh_interval,
             job_cache_size: cmd.job_cache_size,
@@ -72,9 +89,31 @@ pub fn run(cmd: SwitchCommandCmd, version: VersionInfo) -> error::Result<()> {
         yee_mining2::start_mining(work_manager, &rpc_config).map_err(|e| "mining error")?;
     }
 
-    if cmd.enable_relay_recommit {
-        let rpc_config = rpc_config.clone();
-        let from = match cmd.relay_recommit_from {
+    let ledger = if let Some(port) = cmd.stratum_port {
+        let work_manager = work_manger.clone().expect("qed");
+        let stratum_config = StratumConfig {
+            listen: parse_address(&format!("{}:{}", rpc_interface, port), None)?,
+            share_factor: cmd.stratum_share_factor,
+            pplns_window: cmd.stratum_pplns_window,
+        };
+        let ledger = yee_mining2::start_stratum(work_manager, Arc::new(rpc_client.clone()), stratum_config).map_err(|e| format!("stratum error: {}", e))?;
+        Some(ledger)
+    } else {
+        None
+    };
+
+    let relay_tracker = if cmd.enable_relay_recommit {
+        let store = RelayStore::open(&relay_db_path(&cmd, &version))?;
+        Some(Arc::new(DefaultRelayTracker::with_store(store)))
+    } else {
+        None
+    };
+
+    if let Some(tracker) = relay_tracker.clone() {
+        let rpc_client = rpc_client.clone();
+        // continue from the blocks processed before the restart, unless given on the command line
+        let mut from = tracker.cursors();
+        let cmd_from = match cmd.relay_recommit_from {
             Some(v) => {
                 match serde_json::from_str(v.as_str()) {
                     Ok(v) => {
@@ -88,26 +127,29 @@ pub fn run(cmd: SwitchCommandCmd, version: VersionInfo) -> error::Result<()> {
             },
             None => HashMap::new()
         };
+        from.extend(cmd_from);
 
         std::thread::spawn(move||{
-            let relay_monitor = RelayRecommitManager::new(rpc_config, from);
+            let relay_monitor = RelayRecommitManager::new(rpc_client, from, tracker);
             relay_monitor.start();
         });
     }
 
     let handler = || {
-        let author = Author::new(rpc_config.clone());
-        let state = State::new(rpc_config.clone());
-        let system = System::new(rpc_config.clone());
-        let chain = Chain::new(rpc_config.clone());
-
-        let pow =  work_manger.clone().map(Pow::new);
-        yee_switch_rpc_servers::rpc_handler::<_, _, _, _, _, yee_runtime::Hash, yee_runtime::BlockNumber>(
+        let author = Author::new(rpc_client.clone(), subscriptions.clone());
+        let state = State::new(rpc_client.clone(), subscriptions.clone());
+        let system = System::new(rpc_client.clone());
+        let chain = Chain::new(rpc_client.clone(), subscriptions.clone());
+
+        let pow =  work_manger.clone().map(|work_manager| Pow::new(work_manager, ledger.clone()));
+        let relay = relay_tracker.clone().map(|tracker| Relay::new(rpc_client.clone(), tracker));
+        yee_switch_rpc_servers::rpc_handler::<_, _, _, _, _, _, yee_runtime::Hash, yee_runtime::BlockNumber>(
             author,
             state,
             system,
             chain,
             pow,
+            relay,
         )
     };
 
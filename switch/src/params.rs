@@ -68,6 +68,18 @@ pub struct SwitchCommandCmd {
     #[structopt(long = "enable-work-manager")]
     pub enable_work_manager: bool,
 
+    /// Specify stratum TCP port, starts the stratum server of the work manager
+    #[structopt(long = "stratum-port", value_name = "PORT")]
+    pub stratum_port: Option<u16>,
+
+    /// Specify the share target of stratum miners, as a multiple of the block target
+    #[structopt(long = "stratum-share-factor", value_name = "FACTOR", default_value = "16")]
+    pub stratum_share_factor: u64,
+
+    /// Specify the number of the last stratum shares a found block is paid to
+    #[structopt(long = "stratum-pplns-window", value_name = "SHARES", default_value = "1000")]
+    pub stratum_pplns_window: usize,
+
     /// enable relay recommit
     #[structopt(long = "enable-relay-recommit")]
     pub enable_relay_recommit: bool,
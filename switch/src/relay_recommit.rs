@@ -11,108 +11,135 @@ use jsonrpc_core::BoxFuture;
 use log::{debug, error, info, warn};
 use parity_codec::{Compact, Decode, Encode};
 use parking_lot::RwLock;
+use serde_derive::Deserialize;
 use serde_json::Value;
 use tokio::runtime::Runtime;
 use tokio::sync::mpsc;
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 use tokio::timer::Interval;
 
-use relay_monitor::types::{decode_extrinsic, RpcBlockResponse};
-use yee_primitives::Config;
+use relay_monitor::types::{decode_extrinsic, decode_refund, RpcBlockResponse};
 use yee_runtime::Hash;
+use yee_sr_primitives::{RelayCallInfo, legacy_relay_calls, relay_registry_enabled};
 use yee_switch_rpc::client::RpcClient;
+use yee_switch_rpc::relay::ExtrinsicLocation;
 
 use crate::error;
+use crate::relay_tracker::DefaultRelayTracker;
 
-struct ExtrinsicFlag {
-    shard: u16,
-    hash: Hash,
-    number: u64,
-    index: u32,
-    flag: u8,
+/// Blocks above the finalized block scanned for relay extrinsics on the best chain
+const MAX_UNFINALIZED_BLOCKS: u64 = 32;
+
+/// First runtime spec version declaring `RelayApi`
+const RELAY_API_SPEC_VERSION: u32 = 6;
+
+/// (shard, block hash, block, relay calls of the block, finalized)
+type FetchedBlock = (u16, Hash, RpcBlockResponse, Vec<RelayCallInfo>, bool);
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct SpecVersion {
+    spec_version: u32,
 }
 
 pub struct RelayRecommitManager {
     rpc_client: Arc<RpcClient>,
+    // configured shards, from the switch conf
+    config_shard_count: u16,
+    // shard count of the chain, updated when a scale out commits
+    shard_count: Arc<RwLock<u16>>,
+    // shards a fetch thread is started for
+    fetching: Arc<RwLock<u16>>,
     from: HashMap<u16, u64>,
-    // value: u16: shard, Hash: Block hash, u64: block number, u32: tx index, u8: flag
-    cross_shard_txs: Arc<RwLock<HashMap<Hash, ExtrinsicFlag>>>,
-    current_height: Arc<RwLock<HashMap<u16, u64>>>,
+    tracker: Arc<DefaultRelayTracker>,
 }
 
 impl RelayRecommitManager {
-    pub fn new(config: Config, from: HashMap<u16, u64>) -> Self {
+    pub fn new(rpc_client: RpcClient, from: HashMap<u16, u64>, tracker: Arc<DefaultRelayTracker>) -> Self {
+        let config_shard_count = rpc_client.config().get_shard_count();
         Self {
-            rpc_client: Arc::new(RpcClient::new(config)),
+            rpc_client: Arc::new(rpc_client),
+            config_shard_count,
+            shard_count: Arc::new(RwLock::new(config_shard_count)),
+            fetching: Arc::new(RwLock::new(0)),
             from,
-            cross_shard_txs: Arc::new(RwLock::new(HashMap::new())),
-            current_height: Arc::new(RwLock::new(HashMap::new())),
+            tracker,
         }
     }
 
     pub fn start(&self) {
-        let (tx, rx): (UnboundedSender<(u16, Hash, RpcBlockResponse)>, UnboundedReceiver<(u16, Hash, RpcBlockResponse)>) = mpsc::unbounded_channel();
-        let rpc_client = self.rpc_client.clone();
-        self.start_fetch_thread(rpc_client, tx);
+        let (tx, rx): (UnboundedSender<FetchedBlock>, UnboundedReceiver<FetchedBlock>) = mpsc::unbounded_channel();
 
-        let cross_shard_txs = self.cross_shard_txs.clone();
-        let current_height = self.current_height.clone();
+        // shards of the chain, the configured shards may include the ones of a coming scale out
+        let chain_shard_count = self.chain_shard_count().unwrap_or(self.config_shard_count);
+        info!("shard count: {}, configured shards: {}", chain_shard_count, self.config_shard_count);
+        *self.shard_count.write() = chain_shard_count;
+        let fetcher = Fetcher {
+            rpc_client: self.rpc_client.clone(),
+            from: self.from.clone(),
+            tx,
+            fetching: self.fetching.clone(),
+            config_shard_count: self.config_shard_count,
+        };
+        fetcher.start(chain_shard_count);
+
+        let tracker = self.tracker.clone();
         let rpc_client = self.rpc_client.clone();
+        let config_shard_count = self.config_shard_count;
+        let shard_count = self.shard_count.clone();
 
         let (mut tx_relay, rx_relay): (UnboundedSender<(u16, Hash, u32)>, UnboundedReceiver<(u16, Hash, u32)>) = mpsc::unbounded_channel();
 
         let _ = std::thread::Builder::new().name("finalized-loop".to_string()).spawn(move || {
             let mut rt = Runtime::new().expect("can't start finalized-loop thread");
-            let rx_fu = rx.for_each(move |(shard, hash, block)| {
-                {
-                    let mut current_height = current_height.write();
-                    let entry = current_height.entry(shard).or_insert(block.block.header.number);
-                    *entry = block.block.header.number;
+            let rx_fu = rx.for_each(move |(shard, hash, block, registry, finalized)| {
+                let header = &block.block.header;
+                let number = header.number;
+                // until a scale out commits, the nodes of a new shard still serve the shard they split from
+                let tc = match header.shard_info() {
+                    Some((shard_num, count)) if shard_num == shard => count,
+                    Some(_) => return Ok(()),
+                    None => *shard_count.read(),
+                };
+                if let (true, Some(count)) = (finalized, header.scale_out_committed()) {
+                    info!("shard#{} scale out committed, shard count: {}", shard, count);
+                    *shard_count.write() = count;
+                    if count > config_shard_count {
+                        warn!("shard count {} is larger than the configured shards {}", count, config_shard_count);
+                    }
+                    fetcher.start(count);
                 }
-                if block.block.extrinsics.len() > 5 {
-                    let txs = block.block.extrinsics;
-                    for i in 5..txs.len() {
-                        let tx = txs[i].clone();
-                        let mut tx_arr = hex::decode(tx.trim_start_matches("0x")).expect("qed");
-                        let mut length_prefix: Vec<u8> = Compact(tx_arr.len() as u32).encode();
-                        length_prefix.append(&mut tx_arr);
-                        match decode_extrinsic(length_prefix, 4u16, shard) {
-                            (true, Some(h)) => {
-                                let flag = ExtrinsicFlag { shard, hash, number: block.block.header.number, index: i as u32, flag: 0b01u8 };
-                                // cross shard origin extrinsic
-                                let mut txs = cross_shard_txs.write();
-                                let entry = txs.entry(h).or_insert(flag);
-                                if entry.flag & 0b10u8 == 0b10u8 {  // prune
-                                    txs.remove(&h);
-                                } else {
-                                    entry.flag |= 0b01u8;
-                                }
-                            }
-                            (false, Some(h)) => {
-                                let flag = ExtrinsicFlag { shard, hash, number: block.block.header.number, index: i as u32, flag: 0b10u8 };
-                                // relay extrinsic
-                                let mut txs = cross_shard_txs.write();
-                                let entry = txs.entry(h).or_insert(flag);
-                                if entry.flag & 0b01u8 == 0b01u8 {  // prune
-                                    txs.remove(&h);
-                                } else {
-                                    entry.flag |= 0b10u8;
-                                }
-                            }
-                            _ => {}
+
+                for (i, tx) in block.block.extrinsics.iter().enumerate() {
+                    let mut tx_arr = match hex::decode(tx.trim_start_matches("0x")) {
+                        Ok(v) => v,
+                        Err(_) => continue,
+                    };
+                    let mut length_prefix: Vec<u8> = Compact(tx_arr.len() as u32).encode();
+                    length_prefix.append(&mut tx_arr);
+                    let location = ExtrinsicLocation { shard_num: shard, block_hash: hash, block_number: number, index: i as u32, finalized };
+                    if finalized {
+                        if let Some(h) = decode_refund(length_prefix.clone()) {
+                            tracker.on_refund(h, location.clone());
                         }
                     }
+                    // inherents are unsigned and not relays, they decode to no hash
+                    match decode_extrinsic(length_prefix, tc, shard, &registry) {
+                        // cross shard origin extrinsic
+                        (true, Some(h)) if finalized => tracker.on_origin(h, location),
+                        // relay extrinsic
+                        (false, Some(h)) => tracker.on_relay(h, location),
+                        _ => {}
+                    }
+                }
+                if !finalized {
+                    return Ok(());
                 }
+                tracker.on_finalized(shard, number);
+
                 // recommit
-                let txs = cross_shard_txs.read();
-                for (_, flag) in txs.iter() {
-                    let s = flag.shard;
-                    let b_hash = flag.hash.clone();
-                    let num = flag.number;
-                    let index = flag.index;
-                    if s == shard && block.block.header.number > num && block.block.header.number - num > 10 {
-                        tx_relay.try_send((shard, b_hash, index));
-                    }
+                for (b_hash, index) in tracker.stuck(shard) {
+                    let _ = tx_relay.try_send((shard, b_hash, index));
                 }
                 Ok(())
             }).map_err(|_e| {});
@@ -127,23 +154,53 @@ impl RelayRecommitManager {
         });
     }
 
-    fn start_fetch_thread(&self, rpc_client: Arc<RpcClient>, tx: UnboundedSender<(u16, Hash, RpcBlockResponse)>) {
-        for i in 0..4 {
-            let from = self.from.clone();
-            let rpc_client_tmp = rpc_client.clone();
-            let tx_tmp = tx.clone();
+    /// Shard count of the chain, from the shard info digest of the finalized block of shard 0
+    fn chain_shard_count(&self) -> Option<u16> {
+        let mut rt = Runtime::new().ok()?;
+        match rt.block_on(get_finalized_block_future(self.rpc_client.clone(), 0)) {
+            Ok(Ok(Some(block))) => block.block.header.shard_info().map(|(_, count)| count),
+            _ => {
+                warn!("can't get shard count of the chain");
+                None
+            }
+        }
+    }
+}
+
+/// Starts the fetch threads, one per shard
+struct Fetcher {
+    rpc_client: Arc<RpcClient>,
+    from: HashMap<u16, u64>,
+    tx: UnboundedSender<FetchedBlock>,
+    fetching: Arc<RwLock<u16>>,
+    config_shard_count: u16,
+}
+
+impl Fetcher {
+    /// Start the fetch threads of the configured shards below `shard_count` not started yet
+    fn start(&self, shard_count: u16) {
+        let mut fetching = self.fetching.write();
+        let to = shard_count.min(self.config_shard_count);
+        for i in *fetching..to {
+            let from = self.from.get(&i).cloned();
+            let rpc_client_tmp = self.rpc_client.clone();
+            let tx_tmp = self.tx.clone();
+            info!("start fetching blocks of shard#{}", i);
             let _ = std::thread::Builder::new().name(format!("fetch-shard#{}-block", i)).spawn(move || {
-                let shard = i as u16;
-                start_single_shard_fetch_thread(rpc_client_tmp.clone(), shard, from.get(&shard).cloned(), tx_tmp);
+                let shard = i;
+                start_single_shard_fetch_thread(rpc_client_tmp, shard, from, tx_tmp);
             });
         }
+        *fetching = (*fetching).max(to);
     }
 }
 
-fn start_single_shard_fetch_thread(rpc_client: Arc<RpcClient>, shard: u16, from: Option<u64>, mut tx: UnboundedSender<(u16, Hash, RpcBlockResponse)>) {
+fn start_single_shard_fetch_thread(rpc_client: Arc<RpcClient>, shard: u16, from: Option<u64>, mut tx: UnboundedSender<FetchedBlock>) {
     let mut rt = Runtime::new().expect("can't start start_single_shard_fetch_thread");
     let mut current = from.unwrap_or(0u64);
     let mut latest = current;
+    // relay registries by runtime spec version
+    let mut registries = HashMap::new();
     loop {
         let rpc_client_tmp = rpc_client.clone();
         let block = get_finalized_block_future(rpc_client_tmp, shard);
@@ -177,10 +234,37 @@ fn start_single_shard_fetch_thread(rpc_client: Arc<RpcClient>, shard: u16, from:
                         break;
                     }
                 };
-                let _ = tx.try_send((shard, hash, block));
+                // without the registry relay extrinsics can't be told apart, retry the same blocks later
+                let registry = match get_block_relay_registry(&mut rt, &rpc_client, shard, hash, &block, &mut registries) {
+                    Some(registry) => registry,
+                    None => {
+                        info!("shard#{} can't get relay registry of block#{}", shard, num);
+                        break;
+                    }
+                };
+                let _ = tx.try_send((shard, hash, block, registry, true));
                 current = num;
             }
         }
+
+        // relay extrinsics on the best chain, not finalized yet
+        if current > 0 {
+            for num in current + 1..current + 1 + MAX_UNFINALIZED_BLOCKS {
+                let rpc_client_tmp = rpc_client.clone();
+                let block_fu = get_block_by_number_future(rpc_client_tmp, num, shard);
+                match rt.block_on(block_fu) {
+                    Ok(Ok((Some(block), hash))) => {
+                        match get_block_relay_registry(&mut rt, &rpc_client, shard, hash, &block, &mut registries) {
+                            Some(registry) => {
+                                let _ = tx.try_send((shard, hash, block, registry, false));
+                            }
+                            None => break,
+                        }
+                    }
+                    _ => break,
+                }
+            }
+        }
         std::thread::sleep(Duration::from_secs(10));
     }
 }
@@ -266,6 +350,44 @@ pub fn get_block_hash_future(
     Box::new(result)
 }
 
+/// Relay calls the block `hash` was built with, as the verifier resolves them: the legacy ones before
+/// the relay registry fork or if the runtime of the block predates `RelayApi`, the registry of the runtime otherwise
+fn get_block_relay_registry(rt: &mut Runtime, rpc_client: &Arc<RpcClient>, shard: u16, hash: Hash, block: &RpcBlockResponse,
+                            registries: &mut HashMap<u32, Vec<RelayCallInfo>>) -> Option<Vec<RelayCallInfo>> {
+    if !relay_registry_enabled(block.block.header.fork_id()) {
+        return Some(legacy_relay_calls());
+    }
+    let spec_version = rt.block_on(get_runtime_version_future(rpc_client.clone(), hash, shard)).ok()?.spec_version;
+    if spec_version < RELAY_API_SPEC_VERSION {
+        return Some(legacy_relay_calls());
+    }
+    if let Some(registry) = registries.get(&spec_version) {
+        return Some(registry.clone());
+    }
+    let registry = rt.block_on(get_relay_registry_future(rpc_client.clone(), hash, shard)).ok()?;
+    registries.insert(spec_version, registry.clone());
+    Some(registry)
+}
+
+fn get_runtime_version_future(rpc_client: Arc<RpcClient>, hash: Hash, shard: u16) -> Box<dyn Future<Item=SpecVersion, Error=jsonrpc_core::Error> + Send> {
+    let result: BoxFuture<SpecVersion> = rpc_client
+        .call_method_async("state_getRuntimeVersion", "RuntimeVersion", (hash, ), shard)
+        .unwrap_or_else(|e| Box::new(future::err(e.into())));
+    Box::new(result)
+}
+
+/// Relay calls declared by the runtime at block `hash`, the legacy ones if they can't be decoded
+pub fn get_relay_registry_future(rpc_client: Arc<RpcClient>, hash: Hash, shard: u16) -> Box<dyn Future<Item=Vec<RelayCallInfo>, Error=jsonrpc_core::Error> + Send> {
+    let result: BoxFuture<String> = rpc_client
+        .call_method_async("state_call", "", ("RelayApi_relay_registry", "0x", hash), shard)
+        .unwrap_or_else(|e| Box::new(future::err(e.into())));
+    Box::new(result.map(|x| {
+        hex::decode(x.trim_start_matches("0x")).ok()
+            .and_then(|bytes| Decode::decode(&mut bytes.as_slice()))
+            .unwrap_or_else(legacy_relay_calls)
+    }))
+}
+
 pub fn get_finalized_future(rpc_client: Arc<RpcClient>, shard: u16) -> Box<dyn Future<Item=Option<Hash>, Error=jsonrpc_core::Error> + Send> {
     let result: BoxFuture<Option<Hash>> = rpc_client
         .call_method_async("chain_getFinalizedHead", "", (), shard)
@@ -0,0 +1,97 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use kvdb::KeyValueDB;
+use kvdb_rocksdb::{Database, DatabaseConfig};
+use log::warn;
+use parity_codec::{Decode, Encode};
+
+use yee_runtime::Hash;
+
+use crate::error;
+use crate::relay_tracker::RelayRecord;
+
+const NUM_COLUMNS: u32 = 2;
+
+/// Shard num => number of the last finalized block processed
+const COL_CURSOR: Option<u32> = Some(0);
+/// Origin extrinsic hash => relay record, for the origin extrinsics not finalized on the destination shard
+const COL_PENDING: Option<u32> = Some(1);
+
+/// On-disk state of the relay recommit manager, kept across switch restarts
+pub struct RelayStore {
+    db: Database,
+}
+
+impl RelayStore {
+    pub fn open(path: &Path) -> error::Result<Self> {
+        let config = DatabaseConfig::with_columns(Some(NUM_COLUMNS));
+        let path = path.to_str().ok_or("Invalid relay db path")?;
+        let db = Database::open(&config, path)?;
+        Ok(Self { db })
+    }
+
+    pub fn cursors(&self) -> HashMap<u16, u64> {
+        self.db.iter(COL_CURSOR).filter_map(|(key, value)| {
+            let shard: u16 = Decode::decode(&mut &key[..])?;
+            let number: u64 = Decode::decode(&mut &value[..])?;
+            Some((shard, number))
+        }).collect()
+    }
+
+    pub fn set_cursor(&self, shard: u16, number: u64) {
+        let mut tx = self.db.transaction();
+        tx.put(COL_CURSOR, &shard.encode(), &number.encode());
+        self.write(tx);
+    }
+
+    pub fn pending(&self) -> Vec<(Hash, RelayRecord)> {
+        self.db.iter(COL_PENDING).filter_map(|(key, value)| {
+            let hash: Hash = Decode::decode(&mut &key[..])?;
+            let record: RelayRecord = serde_json::from_slice(&value).ok()?;
+            Some((hash, record))
+        }).collect()
+    }
+
+    pub fn put_pending(&self, hash: &Hash, record: &RelayRecord) {
+        let value = match serde_json::to_vec(record) {
+            Ok(value) => value,
+            Err(e) => {
+                warn!("can't encode relay record: {:?}", e);
+                return;
+            }
+        };
+        let mut tx = self.db.transaction();
+        tx.put(COL_PENDING, &hash.encode(), &value);
+        self.write(tx);
+    }
+
+    pub fn remove_pending(&self, hash: &Hash) {
+        let mut tx = self.db.transaction();
+        tx.delete(COL_PENDING, &hash.encode());
+        self.write(tx);
+    }
+
+    fn write(&self, tx: kvdb::DBTransaction) {
+        if let Err(e) = self.db.write(tx) {
+            warn!("can't write relay db: {:?}", e);
+        }
+    }
+}
@@ -0,0 +1,307 @@
+// Copyright (C) 2019 Yee Foundation.
+//
+// This file is part of YeeChain.
+//
+// YeeChain is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// YeeChain is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with YeeChain.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+
+use parking_lot::RwLock;
+use serde_derive::{Deserialize, Serialize};
+
+use yee_runtime::Hash;
+use yee_switch_rpc::relay::{ExtrinsicLocation, RelayInfo, RelayStatus, RelayTracker};
+
+use crate::relay_store::RelayStore;
+
+/// Finalized blocks of the source shard after which an origin extrinsic without relay extrinsic is stuck
+pub const STUCK_DELAY: u64 = 10;
+
+/// Finalized blocks a finalized relay is kept for, and an origin extrinsic waits for its relay
+/// before it is dropped, counted on the shard of the relay and of the origin respectively
+pub const RETENTION: u64 = 10_000;
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct RelayRecord {
+    origin: Option<ExtrinsicLocation<Hash>>,
+    relay: Option<ExtrinsicLocation<Hash>>,
+    #[serde(default)]
+    refund: Option<ExtrinsicLocation<Hash>>,
+}
+
+impl RelayRecord {
+    /// The origin extrinsic is known, and the relay extrinsic is neither finalized nor refunded
+    fn is_pending(&self) -> bool {
+        self.origin.is_some() && self.refund.is_none()
+            && !self.relay.as_ref().map(|relay| relay.finalized).unwrap_or(false)
+    }
+}
+
+/// Cross shard origin extrinsics and their relay extrinsics, keyed by the hash of the origin extrinsic
+///
+/// When both locks are held, `records` is locked before `finalized`.
+pub struct DefaultRelayTracker {
+    records: RwLock<HashMap<Hash, RelayRecord>>,
+    // finalized block number of each shard
+    finalized: RwLock<HashMap<u16, u64>>,
+    // keeps the pending records and the cursors across restarts
+    store: Option<RelayStore>,
+}
+
+impl DefaultRelayTracker {
+    pub fn new() -> Self {
+        Self {
+            records: RwLock::new(HashMap::new()),
+            finalized: RwLock::new(HashMap::new()),
+            store: None,
+        }
+    }
+
+    /// Tracker backed by `store`, starting with the pending records and cursors saved in it
+    pub fn with_store(store: RelayStore) -> Self {
+        Self {
+            records: RwLock::new(store.pending().into_iter().collect()),
+            finalized: RwLock::new(store.cursors()),
+            store: Some(store),
+        }
+    }
+
+    /// Number of the last finalized block processed of each shard
+    pub fn cursors(&self) -> HashMap<u16, u64> {
+        self.finalized.read().clone()
+    }
+
+    /// An origin extrinsic in a finalized block of its shard
+    pub fn on_origin(&self, hash: Hash, location: ExtrinsicLocation<Hash>) {
+        let mut records = self.records.write();
+        let record = records.entry(hash).or_default();
+        record.origin = Some(location);
+        self.persist(&hash, record);
+    }
+
+    /// A relay extrinsic in a block of the destination shard, finalized or on the best chain
+    pub fn on_relay(&self, hash: Hash, location: ExtrinsicLocation<Hash>) {
+        let mut records = self.records.write();
+        let record = records.entry(hash).or_default();
+        if let Some(ref relay) = record.relay {
+            if relay.finalized && !location.finalized {
+                return;
+            }
+        }
+        record.relay = Some(location);
+        self.persist(&hash, record);
+    }
+
+    /// A receipt extrinsic in a finalized block of the source shard, refunding the origin
+    /// extrinsic whose relay failed on the destination shard
+    pub fn on_refund(&self, hash: Hash, location: ExtrinsicLocation<Hash>) {
+        let mut records = self.records.write();
+        let record = records.entry(hash).or_default();
+        record.refund = Some(location);
+        self.persist(&hash, record);
+    }
+
+    /// Block `number` of `shard` is finalized and its extrinsics are recorded. Relays seen on the
+    /// best chain at or below it but not in the finalized blocks were retracted.
+    pub fn on_finalized(&self, shard: u16, number: u64) {
+        self.finalized.write().insert(shard, number);
+        if let Some(ref store) = self.store {
+            store.set_cursor(shard, number);
+        }
+        let store = self.store.as_ref();
+        self.records.write().retain(|hash, record| {
+            let (retracted, expired) = match record.relay {
+                Some(ref relay) if relay.shard_num == shard => (
+                    !relay.finalized && relay.block_number <= number,
+                    relay.finalized && relay.block_number + RETENTION < number,
+                ),
+                _ => (false, false),
+            };
+            if retracted {
+                record.relay = None;
+                self.persist(hash, record);
+            }
+            // never relayed
+            let abandoned = record.relay.is_none() && record.origin.as_ref()
+                .map(|origin| origin.shard_num == shard && origin.block_number + RETENTION < number)
+                .unwrap_or(false);
+            if abandoned {
+                if let Some(store) = store {
+                    store.remove_pending(hash);
+                }
+            }
+            !expired && !abandoned && (record.origin.is_some() || record.relay.is_some())
+        });
+    }
+
+    /// Origin extrinsics of `shard` stuck without relay extrinsic: (block hash, index)
+    pub fn stuck(&self, shard: u16) -> Vec<(Hash, u32)> {
+        let records = self.records.read();
+        let finalized = self.finalized.read();
+        records.values()
+            .filter(|record| record.relay.is_none())
+            .filter_map(|record| record.origin.as_ref())
+            .filter(|origin| origin.shard_num == shard && is_stuck(origin, &finalized))
+            .map(|origin| (origin.block_hash, origin.index))
+            .collect()
+    }
+
+    fn persist(&self, hash: &Hash, record: &RelayRecord) {
+        if let Some(ref store) = self.store {
+            if record.is_pending() {
+                store.put_pending(hash, record);
+            } else {
+                store.remove_pending(hash);
+            }
+        }
+    }
+}
+
+fn is_stuck(origin: &ExtrinsicLocation<Hash>, finalized: &HashMap<u16, u64>) -> bool {
+    finalized.get(&origin.shard_num)
+        .map(|number| *number > origin.block_number + STUCK_DELAY)
+        .unwrap_or(false)
+}
+
+impl RelayTracker for DefaultRelayTracker {
+    type Hash = Hash;
+
+    fn relay_info(&self, hash: &Hash) -> Option<RelayInfo<Hash>> {
+        let records = self.records.read();
+        let record = records.get(hash)?;
+        let status = match (&record.origin, &record.relay) {
+            _ if record.refund.is_some() => RelayStatus::Refunded,
+            (_, Some(relay)) if relay.finalized => RelayStatus::Finalized,
+            (_, Some(_)) => RelayStatus::Relayed,
+            (Some(origin), None) if is_stuck(origin, &self.finalized.read()) => RelayStatus::Stuck,
+            _ => RelayStatus::Pending,
+        };
+        Some(RelayInfo {
+            status,
+            origin: record.origin.clone(),
+            relay: record.relay.clone(),
+            refund: record.refund.clone(),
+        })
+    }
+
+    fn stuck_list(&self) -> Vec<(Hash, RelayInfo<Hash>)> {
+        let hashes: Vec<Hash> = {
+            let records = self.records.read();
+            let finalized = self.finalized.read();
+            records.iter()
+                .filter(|(_, record)| record.relay.is_none())
+                .filter(|(_, record)| record.origin.as_ref().map(|origin| is_stuck(origin, &finalized)).unwrap_or(false))
+                .map(|(hash, _)| *hash)
+                .collect()
+        };
+        hashes.into_iter().filter_map(|hash| self.relay_info(&hash).map(|info| (hash, info))).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(shard_num: u16, block_number: u64, finalized: bool) -> ExtrinsicLocation<Hash> {
+        ExtrinsicLocation { shard_num, block_hash: Hash::repeat_byte(block_number as u8), block_number, index: 5, finalized }
+    }
+
+    fn status(tracker: &DefaultRelayTracker, hash: &Hash) -> Option<RelayStatus> {
+        tracker.relay_info(hash).map(|info| info.status)
+    }
+
+    #[test]
+    fn relay_status_follows_the_relay_extrinsic() {
+        let tracker = DefaultRelayTracker::new();
+        let hash = Hash::repeat_byte(1);
+        assert_eq!(status(&tracker, &hash), None);
+
+        tracker.on_origin(hash, location(0, 100, true));
+        tracker.on_finalized(0, 100);
+        assert_eq!(status(&tracker, &hash), Some(RelayStatus::Pending));
+
+        tracker.on_relay(hash, location(1, 50, false));
+        assert_eq!(status(&tracker, &hash), Some(RelayStatus::Relayed));
+
+        tracker.on_relay(hash, location(1, 50, true));
+        tracker.on_finalized(1, 50);
+        assert_eq!(status(&tracker, &hash), Some(RelayStatus::Finalized));
+
+        tracker.on_relay(hash, location(1, 52, false));
+        assert_eq!(status(&tracker, &hash), Some(RelayStatus::Finalized));
+    }
+
+    #[test]
+    fn failed_relay_is_refunded() {
+        let tracker = DefaultRelayTracker::new();
+        let hash = Hash::repeat_byte(1);
+        tracker.on_origin(hash, location(0, 100, true));
+        tracker.on_relay(hash, location(1, 50, true));
+        tracker.on_finalized(1, 50);
+        assert_eq!(status(&tracker, &hash), Some(RelayStatus::Finalized));
+
+        tracker.on_refund(hash, location(0, 120, true));
+        tracker.on_finalized(0, 120);
+        let info = tracker.relay_info(&hash).unwrap();
+        assert_eq!(info.status, RelayStatus::Refunded);
+        assert_eq!(info.refund, Some(location(0, 120, true)));
+        assert!(tracker.stuck(0).is_empty());
+    }
+
+    #[test]
+    fn retracted_relay_is_stuck_and_recommitted() {
+        let tracker = DefaultRelayTracker::new();
+        let hash = Hash::repeat_byte(1);
+        tracker.on_origin(hash, location(0, 100, true));
+        tracker.on_finalized(0, 100);
+        tracker.on_relay(hash, location(1, 50, false));
+
+        // block 50 of shard 1 was finalized without the relay extrinsic
+        tracker.on_finalized(1, 50);
+        assert_eq!(status(&tracker, &hash), Some(RelayStatus::Pending));
+        assert!(tracker.stuck(0).is_empty());
+
+        tracker.on_finalized(0, 100 + STUCK_DELAY + 1);
+        assert_eq!(status(&tracker, &hash), Some(RelayStatus::Stuck));
+        assert_eq!(tracker.stuck(0), vec![(Hash::repeat_byte(100), 5)]);
+        assert!(tracker.stuck(1).is_empty());
+    }
+
+    #[test]
+    fn finalized_relay_expires() {
+        let tracker = DefaultRelayTracker::new();
+        let hash = Hash::repeat_byte(1);
+        tracker.on_origin(hash, location(0, 100, true));
+        tracker.on_relay(hash, location(1, 50, true));
+
+        tracker.on_finalized(1, 50 + RETENTION);
+        assert!(status(&tracker, &hash).is_some());
+        tracker.on_finalized(1, 50 + RETENTION + 1);
+        assert_eq!(status(&tracker, &hash), None);
+    }
+
+    #[test]
+    fn unrelayed_origin_expires() {
+        let tracker = DefaultRelayTracker::new();
+        let hash = Hash::repeat_byte(1);
+        tracker.on_origin(hash, location(0, 100, true));
+
+        tracker.on_finalized(1, 100 + RETENTION + 1);
+        tracker.on_finalized(0, 100 + RETENTION);
+        assert_eq!(status(&tracker, &hash), Some(RelayStatus::Stuck));
+        tracker.on_finalized(0, 100 + RETENTION + 1);
+        assert_eq!(status(&tracker, &hash), None);
+        assert!(tracker.stuck(0).is_empty());
+    }
+}